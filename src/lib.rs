@@ -0,0 +1,12397 @@
+use colored::Colorize;
+use ordered_float::OrderedFloat;
+use rand::{Rng, SeedableRng};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub fn dir(&self) -> i8 {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+
+    pub fn colored(&self) -> colored::Color {
+        match self {
+            Color::White => colored::Color::White,
+            Color::Black => colored::Color::BrightRed,
+        }
+    }
+
+    pub fn other(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    /// `w` or `b` in either case
+    pub fn from_char(c: char) -> Option<Color> {
+        match c.to_ascii_lowercase() {
+            'w' => Some(Color::White),
+            'b' => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Color::White => 'w',
+            Color::Black => 'b',
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Color::White => write!(f, "{}", "White".color(self.colored())),
+            Color::Black => write!(f, "{}", "Black".color(self.colored())),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Piece {
+    Pawn,
+    Queen,
+    /// Fairy piece with its own moves, see `PieceKind`
+    Custom(&'static dyn PieceKind),
+}
+
+impl Piece {
+    pub fn kind(&self) -> &'static dyn PieceKind {
+        match self {
+            Piece::Pawn => &PawnKind,
+            Piece::Queen => &QueenKind,
+            Piece::Custom(kind) => *kind,
+        }
+    }
+
+    /// `p` or `q` in either case, custom pieces can't be parsed
+    pub fn from_char(c: char) -> Option<Piece> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(Piece::Pawn),
+            'q' => Some(Piece::Queen),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        self.kind().symbol().to_ascii_lowercase()
+    }
+
+    /// Index of the piece in the Zobrist keys, all custom pieces share one
+    pub fn index(&self) -> usize {
+        match self {
+            Piece::Pawn => 0,
+            Piece::Queen => 1,
+            Piece::Custom(_) => 2,
+        }
+    }
+}
+
+// custom pieces are told apart by their names
+impl PartialEq for Piece {
+    fn eq(&self, other: &Piece) -> bool {
+        match (self, other) {
+            (Piece::Custom(a), Piece::Custom(b)) => a.name() == b.name(),
+            _ => self.index() == other.index(),
+        }
+    }
+}
+
+impl Eq for Piece {}
+
+impl Display for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind().name())
+    }
+}
+
+/// Movement of a kind of piece, implement it for fairy pieces used as `Piece::Custom`
+///
+/// Custom pieces never promote, are rated by `value` and have no positional rating
+pub trait PieceKind: std::fmt::Debug + Sync {
+    fn name(&self) -> &'static str;
+
+    fn symbol(&self) -> char;
+
+    /// Worth of the piece in pawns
+    fn value(&self) -> f32;
+
+    /// All moves of the piece standing on `pos`, captures included, the forced capture
+    /// rules are applied by `Board::find_all_current_moves`
+    fn generate_moves(&self, pos: (u8, u8), color: Color, board: &dyn BoardView) -> Vec<Move>;
+}
+
+/// The board as a `PieceKind` sees it, the same for boards of every size
+pub trait BoardView {
+    /// Squares along one side
+    fn size(&self) -> u8;
+
+    /// Piece on the square, `None` for an empty square or one off the board
+    fn piece_at(&self, row: u8, col: u8) -> Option<PlayersPiece>;
+
+    /// Moves the piece of `color` on `pos` would have if it were `piece`
+    fn moves_as(&self, pos: (u8, u8), color: Color, piece: Piece) -> Vec<Move>;
+
+    fn in_bounds(&self, row: i8, col: i8) -> bool {
+        (0..self.size() as i8).contains(&row) && (0..self.size() as i8).contains(&col)
+    }
+
+    fn is_free(&self, row: i8, col: i8) -> bool {
+        self.in_bounds(row, col) && self.piece_at(row as u8, col as u8).is_none()
+    }
+}
+
+impl<const N: usize> BoardView for Board<N> {
+    fn size(&self) -> u8 {
+        N as u8
+    }
+
+    fn piece_at(&self, row: u8, col: u8) -> Option<PlayersPiece> {
+        Board::piece_at(self, row, col)
+    }
+
+    fn moves_as(&self, pos: (u8, u8), color: Color, piece: Piece) -> Vec<Move> {
+        self.piece_moves(pos, color, piece, None)
+    }
+}
+
+#[derive(Debug)]
+pub struct PawnKind;
+
+impl PieceKind for PawnKind {
+    fn name(&self) -> &'static str {
+        "Pawn"
+    }
+
+    fn symbol(&self) -> char {
+        'P'
+    }
+
+    fn value(&self) -> f32 {
+        1.0
+    }
+
+    fn generate_moves(&self, pos: (u8, u8), color: Color, board: &dyn BoardView) -> Vec<Move> {
+        board.moves_as(pos, color, Piece::Pawn)
+    }
+}
+
+#[derive(Debug)]
+pub struct QueenKind;
+
+impl PieceKind for QueenKind {
+    fn name(&self) -> &'static str {
+        "Queen"
+    }
+
+    fn symbol(&self) -> char {
+        'Q'
+    }
+
+    fn value(&self) -> f32 {
+        3.0
+    }
+
+    fn generate_moves(&self, pos: (u8, u8), color: Color, board: &dyn BoardView) -> Vec<Move> {
+        board.moves_as(pos, color, Piece::Queen)
+    }
+}
+
+/// Static rating of a position from the current player's point of view, the search rates
+/// the positions at its horizon with it, see `Board::set_evaluator`
+pub trait Evaluator: std::fmt::Debug + Sync {
+    fn name(&self) -> &'static str;
+
+    fn evaluate(&self, board: &Board) -> f32;
+}
+
+// evaluators are told apart by their names
+impl PartialEq for dyn Evaluator {
+    fn eq(&self, other: &dyn Evaluator) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for dyn Evaluator {}
+
+/// Material, position and capture terms weighted by `RateConfig`,
+/// see `Board::evaluation_breakdown`
+#[derive(Debug)]
+pub struct DefaultEvaluator;
+
+impl Evaluator for DefaultEvaluator {
+    fn name(&self) -> &'static str {
+        "Default"
+    }
+
+    fn evaluate(&self, board: &Board) -> f32 {
+        board.evaluation_breakdown().total
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayersPiece {
+    pub color: Color,
+    pub piece: Piece,
+}
+
+impl PlayersPiece {
+    pub fn new(color: Color, piece: Piece) -> PlayersPiece {
+        PlayersPiece { color, piece }
+    }
+}
+
+impl Display for PlayersPiece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use {Color::*, Piece::*};
+
+        #[cfg(feature = "simple_pieces")]
+        match (self.piece, self.color) {
+            (Pawn, White) => write!(f, "{}", "P".white()),
+            (Pawn, Black) => write!(f, "{}", "P".red()),
+            (Queen, White) => write!(f, "{}", "Q".white()),
+            (Queen, Black) => write!(f, "{}", "Q".red()),
+            (Custom(kind), White) => write!(f, "{}", kind.symbol().to_string().white()),
+            (Custom(kind), Black) => write!(f, "{}", kind.symbol().to_string().red()),
+        }
+
+        #[cfg(all(feature = "reversed_pieces", not(feature = "simple_pieces")))]
+        match (self.piece, self.color) {
+            (Pawn, White) => write!(f, "{}", "🨣".white()),
+            (Pawn, Red) => write!(f, "{}", "♙".red()),
+            (Queen, White) => write!(f, "{}", "🨟".white()),
+            (Queen, Red) => write!(f, "{}", "♕".red()),
+            (Custom(kind), White) => write!(f, "{}", kind.symbol().to_string().white()),
+            (Custom(kind), Red) => write!(f, "{}", kind.symbol().to_string().red()),
+        }
+
+        #[cfg(all(not(feature = "reversed_pieces"), not(feature = "simple_pieces")))]
+        match (self.piece, self.color) {
+            (Pawn, White) => write!(f, "{}", "♙".white()),
+            (Pawn, Red) => write!(f, "{}", "♙".red()),
+            (Queen, White) => write!(f, "{}", "♕".white()),
+            (Queen, Red) => write!(f, "{}", "♕".red()),
+            (Custom(kind), White) => write!(f, "{}", kind.symbol().to_string().white()),
+            (Custom(kind), Red) => write!(f, "{}", kind.symbol().to_string().red()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PosUncolorPiece {
+    pub piece: Piece,
+    pub row: u8,
+    pub col: u8,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    // NOT TRUE, since we need to know if the piece upgrades to queen
+    // we don't need to save the piece, since we can just get it from the board
+    // using the `from` position
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+
+    pub piece: Piece,
+
+    // but we need to save what this move killed, so that we can undo it
+    pub kill: Option<PosUncolorPiece>,
+
+    // we save color so that Queen can move multiple times in one turn
+    // so one move is **NOT** synonymous with one turn
+    pub color: Color,
+
+    // null move is a pass, `color` is the player who passed, other fields are zeroed
+    pub is_null: bool,
+}
+
+impl Move {
+    /// Pass of `color`, see `Board::apply_null_move`
+    pub fn null(color: Color) -> Move {
+        Move {
+            from: (0, 0),
+            to: (0, 0),
+            piece: Piece::Pawn,
+            kill: None,
+            color,
+            is_null: true,
+        }
+    }
+
+    pub fn continues(&self) -> bool {
+        self.continues_on(8)
+    }
+
+    /// `from`, `to` and the square of the killed piece, so `C1 -> D2` yields C1 and D2
+    /// and `C1 -> E3` killing D2 yields C1, E3 and D2
+    pub fn all_squares(&self) -> impl Iterator<Item = (u8, u8)> {
+        let kill = self.kill.map(|kill| (kill.row, kill.col));
+        [Some(self.from), Some(self.to), kill].into_iter().flatten()
+    }
+
+    /// `all_squares` whose content the move changes, the same squares for every move but
+    /// a null move, which yields none, as the pass leaves its placeholder square untouched
+    pub fn affected_squares(&self) -> impl Iterator<Item = (u8, u8)> {
+        let is_null = self.is_null;
+        self.all_squares().filter(move |_| !is_null)
+    }
+
+    pub fn is_upgrade(&self) -> bool {
+        self.is_upgrade_on(8)
+    }
+
+    pub fn future_piece(&self) -> Piece {
+        self.future_piece_on(8)
+    }
+
+    /// `continues` on a board with `size` rows
+    pub fn continues_on(&self, size: usize) -> bool {
+        self.kill.is_some() && !self.is_upgrade_on(size)
+    }
+
+    /// `is_upgrade` on a board with `size` rows
+    pub fn is_upgrade_on(&self, size: usize) -> bool {
+        let last_row = match self.color {
+            Color::White => size - 1,
+            Color::Black => 0,
+        };
+        !self.is_null && self.piece == Piece::Pawn && self.to.0 as usize == last_row
+    }
+
+    /// `future_piece` on a board with `size` rows
+    pub fn future_piece_on(&self, size: usize) -> Piece {
+        if self.is_upgrade_on(size) {
+            Piece::Queen
+        } else {
+            self.piece
+        }
+    }
+
+    pub fn filter_killer_moves(mut moves: Vec<Move>) -> Vec<Move> {
+        moves.retain(|m| m.kill.is_some());
+        moves
+    }
+
+    pub fn contains_killer_move(moves: &[Move]) -> bool {
+        moves.iter().any(|m| m.kill.is_some())
+    }
+
+    pub fn filter_piece_moves(piece: Piece, mut moves: Vec<Move>) -> Vec<Move> {
+        moves.retain(|m| m.piece == piece);
+        moves
+    }
+
+    pub fn contains_piece_move(piece: Piece, moves: &[Move]) -> bool {
+        moves.iter().any(|m| m.piece == piece)
+    }
+}
+
+pub fn format_pos(pos: (u8, u8)) -> String {
+    format!("{}{}", ((pos.0 + b'A') as char), pos.1 + 1)
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_null {
+            return write!(f, "--");
+        }
+
+        let pos = format!("{} -> {}", format_pos(self.from), format_pos(self.to));
+        write!(f, "{}", pos)?;
+
+        if let Some(PosUncolorPiece { piece, row, col }) = self.kill {
+            write!(f, " # {} {}", format_pos((row, col)), piece)?;
+        }
+
+        if self.is_upgrade() {
+            write!(f, " @@")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    NoPieceAtSource,
+    WrongColor,
+    NotInLegalSet,
+    Ambiguous,
+    /// A capture is in progress and has to be finished first
+    ContinuationRequired,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::NoPieceAtSource => write!(f, "There is no piece to move"),
+            MoveError::WrongColor => write!(f, "The piece belongs to the other player"),
+            MoveError::NotInLegalSet => write!(f, "The move is not legal"),
+            MoveError::Ambiguous => write!(f, "More legal moves match"),
+            MoveError::ContinuationRequired => write!(f, "The capture has to be finished"),
+        }
+    }
+}
+
+impl Move {
+    /// Builds the legal move between two squares, as clicked by a user,
+    /// filling in the piece, color and kill from `board`
+    pub fn from_coords(
+        from_row: u8,
+        from_col: u8,
+        to_row: u8,
+        to_col: u8,
+        board: &Board,
+    ) -> Result<Move, MoveError> {
+        let Some(piece) = board.piece_at(from_row, from_col) else {
+            return Err(MoveError::NoPieceAtSource);
+        };
+
+        if piece.color != board.current_player() {
+            return Err(MoveError::WrongColor);
+        }
+
+        let mut moves = board
+            .find_all_current_moves()
+            .into_iter()
+            .filter(|m| m.from == (from_row, from_col) && m.to == (to_row, to_col));
+
+        match (moves.next(), moves.next()) {
+            (Some(move_), None) => Ok(move_),
+            (Some(_), Some(_)) => Err(MoveError::Ambiguous),
+            (None, _) => Err(MoveError::NotInLegalSet),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    OutOfBounds,
+    LightSquare,
+    Occupied,
+    EmptySquare,
+    PawnOnLastRow,
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::OutOfBounds => write!(f, "Position is out of the board"),
+            BoardError::LightSquare => write!(f, "Pieces can only stand on dark squares"),
+            BoardError::Occupied => write!(f, "The square is already occupied"),
+            BoardError::EmptySquare => write!(f, "There is no piece on the square"),
+            BoardError::PawnOnLastRow => write!(f, "Pawn can't stand on its last row"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    OutOfBounds { row: u8, col: u8 },
+    LightSquare { row: u8, col: u8 },
+    SameSquare { row: u8, col: u8 },
+    PawnOnLastRow { row: u8, col: u8 },
+    TooManyPieces { color: Color, count: usize },
+    TooManyQueens { color: Color, count: usize },
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            PlacementError::OutOfBounds { row, col } => {
+                write!(f, "Position ({}, {}) is out of the board", row, col)
+            }
+            PlacementError::LightSquare { row, col } => {
+                write!(f, "{} is a light square", format_pos((row, col)))
+            }
+            PlacementError::SameSquare { row, col } => {
+                write!(f, "More pieces on {}", format_pos((row, col)))
+            }
+            PlacementError::PawnOnLastRow { row, col } => {
+                write!(
+                    f,
+                    "Pawn on {} should already be a queen",
+                    format_pos((row, col))
+                )
+            }
+            PlacementError::TooManyPieces { color, count } => {
+                write!(
+                    f,
+                    "Player {} has {} pieces, at most 12 are allowed",
+                    color, count
+                )
+            }
+            PlacementError::TooManyQueens { color, count } => {
+                write!(
+                    f,
+                    "Player {} has {} queens, at most 12 are allowed",
+                    color, count
+                )
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    BadFormat,
+    BadSide,
+    BadSquare,
+    BadMoveNumber,
+    BadClock,
+    Placement(PlacementError),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::BadFormat => write!(f, "Malformed position"),
+            FenError::BadSide => write!(f, "Side to move must be W or B"),
+            FenError::BadSquare => write!(f, "Square number must be 1-32"),
+            FenError::BadMoveNumber => write!(f, "Move number must be a positive number"),
+            FenError::BadClock => write!(f, "Fifty move clock must be a number"),
+            FenError::Placement(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PdnError {
+    BadHeader,
+    UnclosedComment,
+    BadResult,
+    BadMove(ParseError),
+}
+
+impl Display for PdnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdnError::BadHeader => write!(f, "Header must look like [Name \"value\"]"),
+            PdnError::UnclosedComment => write!(f, "Comment is missing its closing brace"),
+            PdnError::BadResult => write!(f, "Result must be 1-0, 0-1, 1/2-1/2 or *"),
+            PdnError::BadMove(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    EmptyRun,
+    BadMove,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "Data ended in the middle of a run"),
+            DecodeError::EmptyRun => write!(f, "Run without any move"),
+            DecodeError::BadMove => write!(f, "Malformed move"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum JsonError {
+    /// Not valid JSON, at the byte offset
+    Syntax(usize),
+    /// Field missing, of the wrong type or not fitting the rest
+    BadField(&'static str),
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::Syntax(pos) => write!(f, "Invalid JSON at byte {}", pos),
+            JsonError::BadField(name) => write!(f, "Missing or invalid field {}", name),
+        }
+    }
+}
+
+impl Move {
+    /// `[from, to, flags, kill]`, positions are packed as `row << 4 | col`, flags are
+    /// 1 black, 2 queen, 4 null move, 8 kill and 16 killed queen
+    pub fn to_bytes(self) -> [u8; 4] {
+        let pos = |(row, col): (u8, u8)| (row << 4) | col;
+        let flags = (self.color == Color::Black) as u8
+            | ((self.piece == Piece::Queen) as u8) << 1
+            | (self.is_null as u8) << 2
+            | (self.kill.is_some() as u8) << 3
+            | (self.kill.is_some_and(|k| k.piece == Piece::Queen) as u8) << 4;
+        let kill = self.kill.map_or(0, |k| pos((k.row, k.col)));
+
+        [pos(self.from), pos(self.to), flags, kill]
+    }
+
+    pub fn from_bytes(bytes: [u8; 4]) -> Result<Move, DecodeError> {
+        let pos = |byte: u8| match (byte >> 4, byte & 0xf) {
+            (row @ 0..=7, col @ 0..=7) => Ok((row, col)),
+            _ => Err(DecodeError::BadMove),
+        };
+        let piece = |queen| if queen { Piece::Queen } else { Piece::Pawn };
+
+        let [from, to, flags, kill] = bytes;
+        if flags >= 32 || (flags & 8 == 0 && (flags & 16 != 0 || kill != 0)) {
+            return Err(DecodeError::BadMove);
+        }
+
+        let kill = if flags & 8 != 0 {
+            let (row, col) = pos(kill)?;
+            Some(PosUncolorPiece {
+                piece: piece(flags & 16 != 0),
+                row,
+                col,
+            })
+        } else {
+            None
+        };
+
+        Ok(Move {
+            from: pos(from)?,
+            to: pos(to)?,
+            piece: piece(flags & 2 != 0),
+            kill,
+            color: if flags & 1 != 0 {
+                Color::Black
+            } else {
+                Color::White
+            },
+            is_null: flags & 4 != 0,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseMoveError {
+    BadFormat,
+    OutOfBounds,
+    UnknownPiece,
+    NoPieceAtSource,
+    IllegalMove,
+}
+
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMoveError::BadFormat => write!(f, "Malformed move"),
+            ParseMoveError::OutOfBounds => write!(f, "Position is out of the board"),
+            ParseMoveError::UnknownPiece => write!(f, "Unknown piece"),
+            ParseMoveError::NoPieceAtSource => write!(f, "There is no piece to move"),
+            ParseMoveError::IllegalMove => write!(f, "The move is not legal"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    BadFormat,
+    BadSquare,
+    IllegalMove,
+    /// Board grid isn't made of as many rows as squares per row, or has unknown characters
+    BadGrid,
+    Placement(PlacementError),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::BadFormat => write!(f, "Malformed game log"),
+            ParseError::BadSquare => write!(f, "Square number must be 1-32"),
+            ParseError::IllegalMove => write!(f, "The move is not legal"),
+            ParseError::BadGrid => write!(f, "Board must be a grid of w, W, b, B and ."),
+            ParseError::Placement(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Number of a dark square, 1-32, counting from A1 row by row
+pub fn square_number(pos: (u8, u8)) -> u8 {
+    pos.0 * 4 + pos.1 / 2 + 1
+}
+
+pub fn square_pos(number: u8) -> Result<(u8, u8), ParseError> {
+    if !(1..=32).contains(&number) {
+        return Err(ParseError::BadSquare);
+    }
+
+    let row = (number - 1) / 4;
+    Ok((row, (number - 1) % 4 * 2 + row % 2))
+}
+
+pub fn parse_pos(s: &str) -> Result<(u8, u8), ParseMoveError> {
+    let mut chars = s.chars();
+    let (Some(row), Some(col), None) = (chars.next(), chars.next(), chars.next()) else {
+        return Err(ParseMoveError::BadFormat);
+    };
+
+    let row = row.to_ascii_uppercase();
+    if !row.is_ascii_alphabetic() || !col.is_ascii_digit() {
+        return Err(ParseMoveError::BadFormat);
+    }
+
+    match (row as u8 - b'A', col as u8 - b'0') {
+        (row @ 0..=7, col @ 1..=8) => Ok((row, col - 1)),
+        _ => Err(ParseMoveError::OutOfBounds),
+    }
+}
+
+/// Parses the `Display` format of `Move`
+///
+/// The format doesn't contain the moving piece nor its color, so they are inferred:
+/// the color from the direction of the move and the piece from its length and
+/// from the promotion marker (a pawn reaching the last row always promotes)
+impl TryFrom<&str> for Move {
+    type Error = ParseMoveError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut tokens = s.split_whitespace();
+
+        let from = parse_pos(tokens.next().ok_or(ParseMoveError::BadFormat)?)?;
+        if tokens.next() != Some("->") {
+            return Err(ParseMoveError::BadFormat);
+        }
+        let to = parse_pos(tokens.next().ok_or(ParseMoveError::BadFormat)?)?;
+
+        let mut kill = None;
+        let mut upgrade = false;
+        let mut next = tokens.next();
+
+        if next == Some("#") {
+            let (row, col) = parse_pos(tokens.next().ok_or(ParseMoveError::BadFormat)?)?;
+            let piece = match tokens.next().ok_or(ParseMoveError::BadFormat)? {
+                "Pawn" => Piece::Pawn,
+                "Queen" => Piece::Queen,
+                _ => return Err(ParseMoveError::UnknownPiece),
+            };
+            kill = Some(PosUncolorPiece { piece, row, col });
+            next = tokens.next();
+        }
+
+        if next == Some("@@") {
+            upgrade = true;
+            next = tokens.next();
+        }
+
+        if next.is_some() {
+            return Err(ParseMoveError::BadFormat);
+        }
+
+        let (dr, dc) = (to.0 as i8 - from.0 as i8, to.1 as i8 - from.1 as i8);
+        if dr == 0 || dr.abs() != dc.abs() {
+            return Err(ParseMoveError::BadFormat);
+        }
+
+        let color = if dr > 0 { Color::White } else { Color::Black };
+        let last_row = if color == Color::White { 7 } else { 0 };
+        let pawn_length = if kill.is_some() { 2 } else { 1 };
+
+        let piece = if upgrade {
+            if to.0 != last_row || dr.abs() != pawn_length {
+                return Err(ParseMoveError::BadFormat);
+            }
+            Piece::Pawn
+        } else if dr.abs() != pawn_length || to.0 == last_row {
+            Piece::Queen
+        } else {
+            Piece::Pawn
+        };
+
+        Ok(Move {
+            from,
+            to,
+            piece,
+            kill,
+            color,
+            is_null: false,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    FiftyMove,
+    Repetition,
+    Agreement,
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::FiftyMove => write!(f, "fifty move rule"),
+            DrawReason::Repetition => write!(f, "repetition"),
+            DrawReason::Agreement => write!(f, "agreement"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    InProgress,
+    Won(Color),
+    Drawn(DrawReason),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PieceRates {
+    pub pawn: f32,
+    pub queen: f32,
+}
+
+impl Eq for PieceRates {}
+
+impl PieceRates {
+    pub fn rate(&self, piece: Piece) -> f32 {
+        match piece {
+            Piece::Pawn => self.pawn,
+            Piece::Queen => self.queen,
+            Piece::Custom(kind) => self.pawn * kind.value(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PositionRates {
+    /// The closer to the opponent's side, the higher the rate
+    /// First line is `pawn`, second is 2×`pawn` and so on
+    pub pawn: f32,
+
+    /// The closer to the center of the board, the higher the rate
+    /// Edge is `queen`, inner edge is 2×`queen` and so on
+    /// Other way of doing things would be euclidean distance × `queen`
+    pub queen: f32,
+
+    /// Penalty for each pawn without any friendly piece on a neighbouring diagonal
+    pub isolated: f32,
+
+    /// Penalty for each empty dark square on the player's own back rank
+    pub back_rank_hole: f32,
+
+    /// Penalty for each empty column inside a gap of 2 or more columns
+    /// between the player's leftmost and rightmost pieces
+    pub column_gap: f32,
+
+    /// Penalty for each piece below 4 pieces
+    pub low_material: f32,
+
+    /// Penalty for each queen the opponent has over the player
+    pub queen_deficit: f32,
+
+    /// Bonus for each square the player occupies or can reach in one move
+    pub territory: f32,
+
+    /// Bonus for each pawn that can safely promote in at most 3 quiet moves
+    pub promo_threat: f32,
+
+    /// Bonus for each square only the player can reach in one move
+    pub control: f32,
+
+    /// Bonus for each piece on the player's own back rank
+    pub back_rank: f32,
+
+    /// Penalty for each piece without any move, captures included
+    pub trapped: f32,
+
+    /// Bonus for each legal move, see `Board::mobility_score`
+    pub mobility: f32,
+
+    /// Bonus for each capture the player's pieces have, see `Board::capture_move_count`,
+    /// so the rating gains the difference to the opponent's captures
+    pub tension: f32,
+}
+
+impl Eq for PositionRates {}
+
+/// Position weights of the endgame, where queen centrality matters more than pawn advancement
+pub type EndgameRates = PositionRates;
+
+impl PositionRates {
+    pub fn rate(&self, row: u8, col: u8, color: Color, piece: Piece) -> f32 {
+        match piece {
+            Piece::Pawn => {
+                ((match color {
+                    Color::White => row + 1,
+                    Color::Black => 8 - row,
+                }) as f32)
+                    * self.pawn
+            }
+            Piece::Queen => {
+                let centered = |v: i8| 5 - ((v * 2 - 7).abs() + 1) / 2;
+                let row = centered(row as i8);
+                let col = centered(col as i8);
+
+                (row as f32 + col as f32) * self.queen
+            }
+            Piece::Custom(_) => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod pos_rate_tests {
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        let rates = PositionRates {
+            pawn: 1.0,
+            queen: 1.0,
+            isolated: 0.0,
+            back_rank_hole: 0.0,
+            column_gap: 0.0,
+            low_material: 0.0,
+            queen_deficit: 0.0,
+            territory: 0.0,
+            promo_threat: 0.0,
+            control: 0.0,
+            back_rank: 0.0,
+            trapped: 0.0,
+            mobility: 0.0,
+            tension: 0.0,
+        };
+
+        // rates the only piece of a board set up with `set_piece`
+        let rate = |row, col, color, piece| {
+            let mut board: Board = Board::empty_with_rates(test_rates());
+            board
+                .set_piece(row, col, Some(PlayersPiece::new(color, piece)))
+                .unwrap();
+            let (row, col, piece) = board.all_players_pieces(color).next().unwrap();
+            rates.rate(row, col, color, piece)
+        };
+
+        assert_eq!(rate(0, 0, Color::White, Piece::Pawn), 1.0);
+        assert_eq!(rate(0, 0, Color::Black, Piece::Pawn), 8.0);
+        assert_eq!(rate(7, 0, Color::White, Piece::Pawn), 8.0);
+        assert_eq!(rate(7, 0, Color::Black, Piece::Pawn), 1.0);
+
+        assert_eq!(rate(0, 0, Color::White, Piece::Queen), 1.0 + 1.0);
+        assert_eq!(rate(3, 3, Color::White, Piece::Queen), 4.0 + 4.0);
+        assert_eq!(rate(0, 3, Color::White, Piece::Queen), 1.0 + 4.0);
+    }
+
+    #[test]
+    fn test_set_piece() {
+        let mut board: Board = Board::new(3, test_rates());
+        board.push(board.find_all_current_moves()[0]).unwrap();
+        let piece = PlayersPiece::new(Color::Black, Piece::Queen);
+
+        board.set_piece(4, 4, Some(piece)).unwrap();
+        assert_eq!(board.piece_at(4, 4), Some(piece));
+        assert_eq!(board.hash, board.compute_zobrist_hash());
+        assert_eq!(board.moves.len(), 1);
+
+        board.set_piece(4, 4, None).unwrap();
+        assert_eq!(board.piece_at(4, 4), None);
+        assert_eq!(board.set_piece(8, 0, None), Err(BoardError::OutOfBounds));
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KillRates {
+    pub pawn: f32,
+    pub queen: f32,
+}
+
+impl Eq for KillRates {}
+
+impl KillRates {
+    pub fn rate(&self, piece: Piece) -> f32 {
+        match piece {
+            Piece::Pawn => self.pawn,
+            Piece::Queen => self.queen,
+            Piece::Custom(kind) => self.pawn * kind.value(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateConfig {
+    pub pieces: PieceRates,
+    pub position: PositionRates,
+
+    /// Used instead of `position` once `Board::is_endgame`
+    pub endgame: EndgameRates,
+    pub kills: KillRates,
+    pub win: f32,
+    pub max_depth: usize,
+
+    /// Weight of the minimax score against the game outcome in `TrainingLabel::blended`
+    pub training_blend: f32,
+
+    /// Complexity `Board::adapt_depth` aims for, see `Board::compute_game_complexity`
+    pub target_complexity: f64,
+
+    /// Penalize moves repeating a position for the third time when winning,
+    /// see `Board::get_winning_threshold_score`
+    pub avoid_repetition: bool,
+
+    /// Highest search score at which the engine still accepts a draw
+    pub draw_acceptance_threshold: f32,
+
+    /// Number of half-moves without a capture or promotion after which the game is drawn
+    pub draw_after_turns: usize,
+
+    /// Search forced replies, promotions and queen recaptures one turn deeper
+    pub use_extensions: bool,
+
+    /// Rescue threatened pieces first, without running the full search
+    pub prefer_defensive: bool,
+
+    /// Add `compute_threat_score` advantage to the rating
+    pub use_threat_score: bool,
+
+    /// How much worse than the static rating every move must be for a tempo loss
+    pub tempo_loss_threshold: f32,
+
+    /// Search the likely best moves first, see `Board::order_moves`
+    pub use_move_ordering: bool,
+
+    /// Half-moves of captures searched past `max_depth` before rating the position
+    pub quiescence_depth: usize,
+
+    /// Try quiet moves that caused a cutoff at the same ply first, see `KillerTable`
+    pub use_killer_moves: bool,
+
+    /// Order the other quiet moves by how often they caused a cutoff, see `HistoryTable`
+    pub use_history_heuristic: bool,
+
+    /// Cut off positions still winning after the current player passes, outside of endgames
+    pub use_null_move: bool,
+
+    /// Generate quiet moves only once the search gets to them, see `MoveGenerator`
+    pub use_lazy_moves: bool,
+}
+
+impl Eq for RateConfig {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    NegativeWin,
+    ZeroDepth,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::NegativeWin => write!(f, "Score of a win can't be negative"),
+            ConfigError::ZeroDepth => write!(f, "Search depth has to be at least 1"),
+        }
+    }
+}
+
+/// `RateConfig` starting from the defaults the engine plays with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateConfigBuilder {
+    config: RateConfig,
+}
+
+impl Default for RateConfigBuilder {
+    fn default() -> Self {
+        RateConfigBuilder {
+            config: RateConfig {
+                pieces: PieceRates {
+                    pawn: 1.0,
+                    queen: 3.0,
+                },
+                position: PositionRates {
+                    // pawn: 0.5,
+                    // queen: 1.5,
+                    pawn: 0.0,
+                    queen: 0.0,
+                    isolated: 0.5,
+                    back_rank_hole: 0.5,
+                    column_gap: 0.5,
+                    low_material: 2.0,
+                    queen_deficit: 3.0,
+                    territory: 0.0,
+                    promo_threat: 0.0,
+                    control: 0.0,
+                    back_rank: 0.0,
+                    trapped: 0.0,
+                    mobility: 0.0,
+                    tension: 0.0,
+                },
+                endgame: EndgameRates {
+                    pawn: 0.0,
+                    queen: 0.5,
+                    isolated: 0.0,
+                    back_rank_hole: 0.0,
+                    column_gap: 0.0,
+                    low_material: 2.0,
+                    queen_deficit: 3.0,
+                    territory: 0.0,
+                    promo_threat: 0.0,
+                    control: 0.0,
+                    back_rank: 0.0,
+                    trapped: 0.0,
+                    mobility: 0.0,
+                    tension: 0.0,
+                },
+                kills: KillRates {
+                    pawn: 10.0,
+                    queen: 30.0,
+                },
+                win: 1000.0,
+                max_depth: 5,
+                training_blend: 0.5,
+                target_complexity: 1e6,
+                avoid_repetition: true,
+                draw_acceptance_threshold: 0.0,
+                draw_after_turns: 80,
+                use_extensions: true,
+                prefer_defensive: false,
+                use_threat_score: false,
+                tempo_loss_threshold: 0.5,
+                use_move_ordering: true,
+                quiescence_depth: 4,
+                use_killer_moves: true,
+                use_history_heuristic: true,
+                use_null_move: true,
+                use_lazy_moves: true,
+            },
+        }
+    }
+}
+
+impl RateConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn piece_pawn(mut self, rate: f32) -> Self {
+        self.config.pieces.pawn = rate;
+        self
+    }
+
+    pub fn piece_queen(mut self, rate: f32) -> Self {
+        self.config.pieces.queen = rate;
+        self
+    }
+
+    pub fn position_pawn(mut self, rate: f32) -> Self {
+        self.config.position.pawn = rate;
+        self
+    }
+
+    pub fn position_queen(mut self, rate: f32) -> Self {
+        self.config.position.queen = rate;
+        self
+    }
+
+    pub fn kill_pawn(mut self, rate: f32) -> Self {
+        self.config.kills.pawn = rate;
+        self
+    }
+
+    pub fn kill_queen(mut self, rate: f32) -> Self {
+        self.config.kills.queen = rate;
+        self
+    }
+
+    pub fn win(mut self, win: f32) -> Self {
+        self.config.win = win;
+        self
+    }
+
+    pub fn mobility(mut self, rate: f32) -> Self {
+        self.config.position.mobility = rate;
+        self
+    }
+
+    /// Sets `draw_after_turns`, 80 half-moves for the 40-move rule
+    pub fn draw_moves(mut self, moves: usize) -> Self {
+        self.config.draw_after_turns = moves;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.config.max_depth = max_depth;
+        self
+    }
+
+    pub fn build(self) -> Result<RateConfig, ConfigError> {
+        if self.config.win < 0.0 {
+            return Err(ConfigError::NegativeWin);
+        }
+        if self.config.max_depth == 0 {
+            return Err(ConfigError::ZeroDepth);
+        }
+        Ok(self.config)
+    }
+}
+
+const TRAINING_MAGIC: &[u8; 4] = b"CKTG";
+
+const BOOK_MAGIC: &[u8; 4] = b"CKOB";
+
+/// Largest supported `Board` size
+pub const MAX_BOARD_SIZE: usize = 10;
+
+/// Rows of pawns each side starts with on the standard board, as in the PDN and FEN
+/// square numbering
+pub const STARTING_LINES: u8 = 3;
+
+/// Random keys for `[square][color][piece]`, one more for black on the move
+/// and `[square]` of the piece in the middle of a capture
+///
+/// Squares are `row * MAX_BOARD_SIZE + col`, so the keys are shared by all board sizes
+struct ZobristKeys {
+    pieces: [[[u64; 3]; 2]; MAX_BOARD_SIZE * MAX_BOARD_SIZE],
+    black_to_move: u64,
+    capturing: [u64; MAX_BOARD_SIZE * MAX_BOARD_SIZE],
+}
+
+impl ZobristKeys {
+    fn square((row, col): (u8, u8)) -> usize {
+        row as usize * MAX_BOARD_SIZE + col as usize
+    }
+
+    fn piece(&self, pos: (u8, u8), color: Color, piece: Piece) -> u64 {
+        self.pieces[ZobristKeys::square(pos)][color as usize][piece.index()]
+    }
+
+    fn capturing(&self, pos: (u8, u8)) -> u64 {
+        self.capturing[ZobristKeys::square(pos)]
+    }
+}
+
+fn zobrist_keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        // fixed seed, so hashes are the same across runs
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x636865636b);
+        let mut pieces = [[[0; 3]; 2]; MAX_BOARD_SIZE * MAX_BOARD_SIZE];
+        for key in pieces.iter_mut().flatten().flatten() {
+            *key = rng.gen();
+        }
+
+        let black_to_move = rng.gen();
+        let mut capturing = [0; MAX_BOARD_SIZE * MAX_BOARD_SIZE];
+        for key in capturing.iter_mut() {
+            *key = rng.gen();
+        }
+
+        ZobristKeys {
+            pieces,
+            black_to_move,
+            capturing,
+        }
+    })
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    #[default]
+    Unfinished,
+}
+
+impl GameResult {
+    pub const ALL: [GameResult; 4] = [
+        GameResult::WhiteWins,
+        GameResult::BlackWins,
+        GameResult::Draw,
+        GameResult::Unfinished,
+    ];
+
+    /// Result of the game played on `board` so far
+    pub fn of<const N: usize>(board: &Board<N>) -> GameResult {
+        match board.winner() {
+            Some(Color::White) => GameResult::WhiteWins,
+            Some(Color::Black) => GameResult::BlackWins,
+            None if board.is_draw() => GameResult::Draw,
+            None => GameResult::Unfinished,
+        }
+    }
+
+    /// `1-0`, `0-1`, `1/2-1/2` or `*` for an unfinished game
+    pub fn to_pdn(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unfinished => "*",
+        }
+    }
+
+    pub fn from_pdn(s: &str) -> Option<GameResult> {
+        GameResult::ALL.into_iter().find(|r| r.to_pdn() == s)
+    }
+}
+
+/// Headers of a PDN game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameMetadata {
+    pub event: String,
+    /// `YYYY.MM.DD`, with `?` for the unknown digits
+    ///
+    /// Kept as the PDN text instead of a `chrono::NaiveDate`: chrono isn't available to this
+    /// build, and even an optional dependency has to be resolved. Partly unknown dates like
+    /// `2023.??.??` wouldn't fit a `NaiveDate` anyway
+    pub date: String,
+    pub white: String,
+    pub black: String,
+    pub result: GameResult,
+    /// Other headers in the order of the file
+    pub tags: Vec<(String, String)>,
+}
+
+impl Default for GameMetadata {
+    fn default() -> Self {
+        GameMetadata {
+            event: "?".to_string(),
+            date: "????.??.??".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            result: GameResult::Unfinished,
+            tags: vec![],
+        }
+    }
+}
+
+/// Game read from PDN or being played, see `Board::to_pdn`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game {
+    board: Board,
+    metadata: GameMetadata,
+    /// Every move of the game, kept even when the board is rewound for analysis
+    moves: Vec<Move>,
+}
+
+impl Game {
+    /// Game from the starting position
+    pub fn new(metadata: GameMetadata, rates: RateConfig) -> Game {
+        Game {
+            board: Board::new(STARTING_LINES, rates),
+            metadata,
+            moves: vec![],
+        }
+    }
+
+    /// Plays `move_` on the board and records it, the result is updated once the game ends,
+    /// returns the winner like `Board::push`
+    pub fn play_move(&mut self, move_: Move) -> Result<Option<Color>, MoveError> {
+        let winner = self.board.push(move_)?;
+        self.moves.push(move_);
+        self.metadata.result = GameResult::of(&self.board);
+        Ok(winner)
+    }
+
+    /// Reads the headers, then replays the moves from the starting position,
+    /// `{comments}` are skipped
+    pub fn from_pdn(pdn: &str, rates: RateConfig) -> Result<Game, PdnError> {
+        let mut metadata = GameMetadata::default();
+        let mut movetext = String::new();
+        for line in pdn.lines().map(str::trim) {
+            let Some(header) = line.strip_prefix('[') else {
+                movetext += line;
+                movetext.push('\n');
+                continue;
+            };
+
+            let (name, value) = header
+                .strip_suffix(']')
+                .and_then(|h| h.split_once(' '))
+                .ok_or(PdnError::BadHeader)?;
+            let value = value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .ok_or(PdnError::BadHeader)?
+                .to_string();
+            match name {
+                "Event" => metadata.event = value,
+                "Date" => metadata.date = value,
+                "White" => metadata.white = value,
+                "Black" => metadata.black = value,
+                "Result" => {
+                    metadata.result = GameResult::from_pdn(&value).ok_or(PdnError::BadResult)?
+                }
+                _ => metadata.tags.push((name.to_string(), value)),
+            }
+        }
+
+        let mut text = String::new();
+        let mut rest = movetext.as_str();
+        while let Some(start) = rest.find('{') {
+            text += &rest[..start];
+            let end = rest[start..].find('}').ok_or(PdnError::UnclosedComment)?;
+            rest = &rest[start + end + 1..];
+        }
+        text += rest;
+
+        let mut board = Board::new(STARTING_LINES, rates);
+        for token in text.split_whitespace() {
+            if GameResult::from_pdn(token).is_some() || token.ends_with('.') {
+                continue;
+            }
+            board
+                .push_algebraic_turn(token)
+                .map_err(PdnError::BadMove)?;
+        }
+
+        Ok(Game {
+            moves: board.moves.clone(),
+            board,
+            metadata,
+        })
+    }
+
+    pub fn to_pdn(&self) -> String {
+        Board::to_pdn(&self.moves, &self.metadata)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Board to search or rewind, moves made on it directly aren't recorded in the game
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    pub fn metadata(&self) -> &GameMetadata {
+        &self.metadata
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+}
+
+/// Post-game analysis from `Board::report_game_summary`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSummary {
+    pub winner: Option<Color>,
+    pub total_turns: usize,
+
+    /// Every half-move in the 1-32 square notation, e.g. `11-15` or `15x22`
+    pub moves: Vec<String>,
+
+    /// Search score before every half-move and after the last one, from White's point of view
+    pub evaluation: Vec<f32>,
+
+    /// Half-moves, with their index, after which the mover's evaluation dropped by more than 0.5
+    pub blunders: Vec<(usize, Move)>,
+
+    /// Average evaluation of the positions White and Black were on move in, from their own
+    /// point of view
+    pub average_evaluation: (f32, f32),
+
+    /// `Board::compute_game_complexity` before every half-move
+    pub complexity: Vec<f64>,
+}
+
+impl Display for GameSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.winner {
+            Some(winner) => writeln!(f, "Winner: {}", winner)?,
+            None => writeln!(f, "Winner: none")?,
+        }
+        writeln!(
+            f,
+            "Turns: {}, half-moves: {}",
+            self.total_turns,
+            self.moves.len()
+        )?;
+        writeln!(f, "Moves: {}", self.moves.join(" "))?;
+
+        let (white, black) = self.average_evaluation;
+        writeln!(
+            f,
+            "Average evaluation: White {:.2}, Black {:.2}",
+            white, black
+        )?;
+
+        writeln!(f, "Blunders:")?;
+        for (i, move_) in &self.blunders {
+            writeln!(
+                f,
+                "  {}. {} {}: {:.2} -> {:.2}",
+                i + 1,
+                move_.color,
+                self.moves[*i],
+                self.evaluation[*i],
+                self.evaluation[i + 1]
+            )?;
+        }
+
+        writeln!(f, "Evaluation / complexity:")?;
+        for (i, complexity) in self.complexity.iter().enumerate() {
+            writeln!(
+                f,
+                "  {}. {:.2} / {:.0}",
+                i + 1,
+                self.evaluation[i],
+                complexity
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Characteristics of a position that tell how hard it is to search
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SearchProfile {
+    pub branching_factor: usize,
+    pub captures: usize,
+    pub time_to_first_capture: Option<usize>,
+}
+
+/// Training target for a single position, from the perspective of the player on move
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrainingLabel {
+    pub minimax_score: f32,
+
+    /// `1` for a win, `-1` for a loss, `0` for a draw and `None` if the game isn't over yet
+    pub game_outcome: Option<f32>,
+
+    pub blended: f32,
+}
+
+impl TrainingLabel {
+    pub fn new(minimax_score: f32, game_outcome: Option<f32>, alpha: f32) -> TrainingLabel {
+        let blended = match game_outcome {
+            Some(outcome) => alpha * minimax_score + (1.0 - alpha) * outcome,
+            None => minimax_score,
+        };
+
+        TrainingLabel {
+            minimax_score,
+            game_outcome,
+            blended,
+        }
+    }
+}
+
+/// Search of the position after the opponent's expected move, running on its own thread
+#[derive(Debug)]
+struct PonderJob {
+    expected: Move,
+    cancel: Arc<AtomicBool>,
+
+    // signalled after every iteration and once the thread stops
+    progress: Arc<(Mutex<PonderProgress>, Condvar)>,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct PonderProgress {
+    /// Best move of the deepest finished iteration, with its depth
+    best: Option<(Move, usize)>,
+    /// The thread stopped, cancelled or after its last iteration
+    finished: bool,
+}
+
+/// Pondering state of a `Board`, clones of the board don't ponder
+#[derive(Debug, Default)]
+struct Ponder {
+    job: Option<PonderJob>,
+}
+
+impl Ponder {
+    fn cancel(&mut self) -> Option<PonderJob> {
+        let job = self.job.take()?;
+        job.cancel.store(true, Ordering::Relaxed);
+        Some(job)
+    }
+}
+
+impl Clone for Ponder {
+    fn clone(&self) -> Ponder {
+        Ponder::default()
+    }
+}
+
+// pondering doesn't change the position
+impl PartialEq for Ponder {
+    fn eq(&self, _: &Ponder) -> bool {
+        true
+    }
+}
+
+impl Eq for Ponder {}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Rules the moves are generated by
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// Czech draughts, queens have to capture before pawns
+    Standard,
+    /// International draughts, the capture taking the most pieces has to be played
+    International,
+}
+
+/// Position and game state of an `N`×`N` board, rules work for any size up to
+/// `MAX_BOARD_SIZE`, the search and evaluation only for the standard 8×8
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board<const N: usize = 8> {
+    board: [[Option<PlayersPiece>; N]; N],
+    moves: Vec<Move>,
+    turn: usize,
+
+    // on the move before the first of `moves`, Black only for positions loaded with Black to move
+    first_player: Color,
+    show_moves_for: Option<(u8, u8)>,
+    rating: RateConfig,
+    evaluator: &'static dyn Evaluator,
+    variant: Variant,
+
+    // half-moves since the last capture or promotion, with its value before every move
+    fifty_move_clock: usize,
+    clock_history: Vec<usize>,
+
+    // both players agreed to a draw in the current position, taking a move back cancels it
+    agreed_draw: bool,
+
+    // background search started by `start_pondering`
+    ponder: Ponder,
+
+    // Zobrist hash of the position, updated by every push and pop, with its value before
+    // every move
+    hash: u64,
+    hash_history: Vec<u64>,
+}
+
+impl<const N: usize> Board<N> {
+    pub fn new(lines: u8, rates: RateConfig) -> Self {
+        let mut board = Self::empty_with_rates(rates);
+
+        for i in 0..N as u8 {
+            for j in 0..N as u8 {
+                // starting at A1 (0, 0) <=> 2|0 + 0
+                if (i + j) % 2 == 0 {
+                    if i < lines {
+                        *board.get_mut(i, j) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+                    } else if i >= N as u8 - lines {
+                        *board.get_mut(i, j) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+                    }
+                }
+            }
+        }
+        board.hash = board.compute_zobrist_hash();
+
+        board
+    }
+
+    /// Board without any pieces, set them up with `set_piece` or `set_position`
+    pub fn empty_with_rates(rates: RateConfig) -> Self {
+        assert!(N <= MAX_BOARD_SIZE, "Board is too large");
+        Board {
+            board: [[None; N]; N],
+            moves: Vec::new(),
+            turn: 0,
+            first_player: Color::White,
+            show_moves_for: None,
+            rating: rates,
+            evaluator: &DefaultEvaluator,
+            variant: Variant::Standard,
+            fifty_move_clock: 0,
+            clock_history: Vec::new(),
+            agreed_draw: false,
+            ponder: Ponder::default(),
+            hash: 0,
+            hash_history: Vec::new(),
+        }
+    }
+
+    /// Copy of the pieces and the settings without the move history, cheap enough to
+    /// try out piece placements on while evaluating a position
+    pub fn position_copy(&self) -> Self {
+        Board {
+            board: self.board,
+            moves: Vec::new(),
+            turn: self.turn,
+            first_player: self.current_player(),
+            show_moves_for: None,
+            rating: self.rating,
+            evaluator: self.evaluator,
+            variant: self.variant,
+            fifty_move_clock: self.fifty_move_clock,
+            clock_history: Vec::new(),
+            agreed_draw: self.agreed_draw,
+            ponder: Ponder::default(),
+            hash: self.hash,
+            hash_history: Vec::new(),
+        }
+    }
+
+    /// Puts `piece` on the square for the duration of `f`, then restores what was there
+    pub fn with_piece<T>(
+        &mut self,
+        row: u8,
+        col: u8,
+        piece: Option<PlayersPiece>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let previous = std::mem::replace(self.get_mut(row, col), piece);
+        let ret = f(self);
+        *self.get_mut(row, col) = previous;
+        ret
+    }
+
+    /// Square whose piece `Display` highlights, with its legal destinations drawn as dimmed
+    /// pieces, `None` turns it off
+    pub fn set_show_moves_for(&mut self, pos: Option<(u8, u8)>) {
+        self.show_moves_for = pos;
+    }
+
+    pub fn show_moves_for(&self) -> Option<(u8, u8)> {
+        self.show_moves_for
+    }
+
+    /// Rates positions with `evaluator` instead of `DefaultEvaluator`
+    pub fn set_evaluator(&mut self, evaluator: &'static dyn Evaluator) {
+        self.evaluator = evaluator;
+    }
+
+    pub fn occupied_by(&self, row: u8, col: u8) -> Option<Color> {
+        self.board[row as usize][col as usize].map(|p| p.color)
+    }
+
+    pub fn last_player(&self) -> Option<Color> {
+        self.last_move().map(|m| m.color)
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.moves.last().copied()
+    }
+
+    pub fn current_player(&self) -> Color {
+        let Some(move_) = self.last_move() else {
+            return self.first_player;
+        };
+
+        let Move { to, color, .. } = move_;
+
+        if move_.continues_on(N) && !self.find_moves(to.0, to.1, Some(true)).unwrap().is_empty() {
+            color
+        } else {
+            color.other()
+        }
+    }
+
+    /// Piece on the square, `None` for an empty square or one off the board
+    #[inline]
+    pub fn piece_at(&self, row: u8, col: u8) -> Option<PlayersPiece> {
+        *self.board.get(row as usize)?.get(col as usize)?
+    }
+
+    fn get_ref(&self, row: u8, col: u8) -> &Option<PlayersPiece> {
+        &self.board[row as usize][col as usize]
+    }
+
+    fn get_mut(&mut self, row: u8, col: u8) -> &mut Option<PlayersPiece> {
+        &mut self.board[row as usize][col as usize]
+    }
+
+    pub fn in_bounds(&self, row: i8, col: i8) -> bool {
+        (0..N as i8).contains(&row) && (0..N as i8).contains(&col)
+    }
+
+    pub fn is_free(&self, row: i8, col: i8) -> bool {
+        self.in_bounds(row, col) && self.get_ref(row as u8, col as u8).is_none()
+    }
+
+    pub fn find_moves(&self, row: u8, col: u8, kills: Option<bool>) -> Option<Vec<Move>> {
+        let PlayersPiece { piece, color } = (*self.get_ref(row, col))?;
+        Some(self.piece_moves((row, col), color, piece, kills))
+    }
+
+    /// Moves `piece` of `color` would have from `pos`, only the captures for `Some(true)`
+    /// and only the other moves for `Some(false)`
+    fn piece_moves(
+        &self,
+        (row, col): (u8, u8),
+        color: Color,
+        piece: Piece,
+        kills: Option<bool>,
+    ) -> Vec<Move> {
+        let (rowu, colu) = (row, col);
+        let (rowi, coli) = (row as i8, col as i8);
+
+        match piece {
+            Piece::Pawn => {
+                let mut moves = vec![];
+
+                for col_offset in [-1, 1] {
+                    let (row, col) = (rowi + color.dir(), coli + col_offset);
+                    if self.is_free(row, col) {
+                        if !kills.unwrap_or(false) {
+                            moves.push(Move {
+                                from: (rowu, colu),
+                                to: (row as u8, col as u8),
+                                piece,
+                                kill: None,
+                                color,
+                                is_null: false,
+                            })
+                        }
+                    } else if self.in_bounds(row, col)
+                        && self.get_ref(row as u8, col as u8).unwrap().color != color
+                        && self.is_free(row + color.dir(), col + col_offset)
+                        && kills.unwrap_or(true)
+                    {
+                        let killed = self.get_ref(row as u8, col as u8).unwrap().piece;
+                        moves.push(Move {
+                            from: (rowu, colu),
+                            to: ((row + color.dir()) as u8, (col + col_offset) as u8),
+                            piece,
+                            kill: Some(PosUncolorPiece {
+                                piece: killed,
+                                row: row as u8,
+                                col: col as u8,
+                            }),
+                            color,
+                            is_null: false,
+                        })
+                    }
+                }
+
+                moves
+            }
+            Piece::Queen => {
+                let mut moves = vec![];
+
+                for row_offset in [-1, 1] {
+                    for col_offset in [-1, 1] {
+                        let (mut row, mut col) = (rowi + row_offset, coli + col_offset);
+                        while self.is_free(row, col) {
+                            if !kills.unwrap_or(false) {
+                                moves.push(Move {
+                                    from: (rowu, colu),
+                                    to: (row as u8, col as u8),
+                                    piece,
+                                    kill: None,
+                                    color,
+                                    is_null: false,
+                                });
+                            }
+                            row += row_offset;
+                            col += col_offset;
+                        }
+
+                        if self.in_bounds(row, col)
+                            && self.get_ref(row as u8, col as u8).unwrap().color != color
+                            && self.is_free(row + row_offset, col + col_offset)
+                        {
+                            let killed = self.get_ref(row as u8, col as u8).unwrap().piece;
+
+                            if !kills.unwrap_or(true) {
+                                continue;
+                            }
+
+                            let (mut free_row, mut free_col) = (row + row_offset, col + col_offset);
+                            while self.is_free(free_row, free_col) {
+                                moves.push(Move {
+                                    from: (rowu, colu),
+                                    to: (free_row as u8, free_col as u8),
+                                    piece,
+                                    kill: Some(PosUncolorPiece {
+                                        piece: killed,
+                                        row: row as u8,
+                                        col: col as u8,
+                                    }),
+                                    color,
+                                    is_null: false,
+                                });
+
+                                free_row += row_offset;
+                                free_col += col_offset;
+                            }
+                        }
+                    }
+                }
+
+                moves
+            }
+            Piece::Custom(kind) => kind
+                .generate_moves((row, col), color, self)
+                .into_iter()
+                .filter(|m| kills.is_none_or(|kills| kills == m.kill.is_some()))
+                .collect(),
+        }
+    }
+
+    pub fn find_all_current_moves(&self) -> Vec<Move> {
+        self.find_all_moves(self.current_player())
+    }
+
+    /// Legal moves `player` would have if they were on the move
+    pub fn find_all_moves(&self, player: Color) -> Vec<Move> {
+        let captures = self.find_all_captures(player);
+        if !captures.is_empty() {
+            return captures;
+        }
+
+        self.all_players_pieces(player)
+            .flat_map(|p| self.find_moves(p.0, p.1, Some(false)).unwrap())
+            .collect()
+    }
+
+    /// Legal moves one at a time, captures first, see `MoveGenerator`
+    pub fn lazy_move_generator(&self) -> impl Iterator<Item = Move> + '_ {
+        let mut moves = MoveGenerator::unordered(self);
+        std::iter::from_fn(move || moves.next_unordered(self))
+    }
+
+    /// Captures `player` has to choose from, empty if they can't capture
+    pub fn find_all_captures(&self, player: Color) -> Vec<Move> {
+        let moves: Vec<_> = self
+            .all_players_pieces(player)
+            .flat_map(|p| self.find_moves(p.0, p.1, Some(true)).unwrap())
+            .collect();
+
+        if moves.is_empty() {
+            return moves;
+        }
+
+        if self.variant == Variant::International {
+            return self.filter_longest_captures(player, moves);
+        }
+
+        if !Move::contains_piece_move(Piece::Queen, &moves) {
+            return moves;
+        }
+
+        Move::filter_piece_moves(Piece::Queen, moves)
+    }
+
+    /// Captures starting the sequences that take the most pieces, in the middle of a sequence
+    /// only the capturing piece can go on
+    fn filter_longest_captures(&self, player: Color, mut moves: Vec<Move>) -> Vec<Move> {
+        if let Some(last) = self.last_move() {
+            if last.color == player && player == self.current_player() {
+                moves.retain(|m| m.from == last.to);
+            }
+        }
+
+        let mut board = self.clone();
+        let lengths: Vec<_> = moves
+            .iter()
+            .map(|&move_| board.capture_sequence_length(move_))
+            .collect();
+        let longest = lengths.iter().copied().max().unwrap_or(0);
+
+        moves
+            .into_iter()
+            .zip(lengths)
+            .filter(|&(_, length)| length == longest)
+            .map(|(move_, _)| move_)
+            .collect()
+    }
+
+    /// Most pieces the capture sequence starting with `move_` can take, the capturing piece
+    /// goes on as long as it can
+    fn capture_sequence_length(&mut self, move_: Move) -> usize {
+        self.with_move_unchecked(move_, |board| {
+            if board.current_player() != move_.color {
+                return 1;
+            }
+
+            let (row, col) = move_.to;
+            let longest = board
+                .find_moves(row, col, Some(true))
+                .unwrap()
+                .into_iter()
+                .map(|next| board.capture_sequence_length(next))
+                .max()
+                .unwrap_or(0);
+            1 + longest
+        })
+    }
+
+    /// Every complete capture sequence of the current player, the capturing piece goes on
+    /// as long as it can, empty when no capture is available
+    pub fn generate_forced_sequence(&mut self) -> Vec<Vec<Move>> {
+        let captures = Move::filter_killer_moves(self.find_all_current_moves());
+        captures
+            .into_iter()
+            .flat_map(|move_| self.capture_sequences(move_))
+            .collect()
+    }
+
+    /// Complete capture sequences starting with `move_`
+    pub fn capture_sequences(&mut self, move_: Move) -> Vec<Vec<Move>> {
+        self.with_move_unchecked(move_, |board| {
+            let (row, col) = move_.to;
+            let next = if board.current_player() == move_.color {
+                board.find_moves(row, col, Some(true)).unwrap()
+            } else {
+                vec![]
+            };
+            if next.is_empty() {
+                return vec![vec![move_]];
+            }
+
+            next.into_iter()
+                .flat_map(|next| board.capture_sequences(next))
+                .map(|sequence| [vec![move_], sequence].concat())
+                .collect()
+        })
+    }
+
+    /// Fewest half-moves, every jump of a capture included, in which `player` wins against
+    /// any defence, `None` when there is no such win in at most `max_depth` half-moves
+    ///
+    /// Exhaustive search without any pruning, so only for endgames with a few pieces
+    pub fn compute_dtw(&mut self, player: Color, max_depth: usize) -> Option<usize> {
+        // `player` wins against any defence in at most `plies` half-moves
+        fn forced_win<const N: usize>(board: &mut Board<N>, player: Color, plies: usize) -> bool {
+            if let Some(winner) = board.winner() {
+                return winner == player;
+            }
+            if plies == 0 || board.is_draw() {
+                return false;
+            }
+
+            let moving = board.current_player() == player;
+            let mut moves = board.find_all_current_moves().into_iter();
+            let mut wins = |m| board.with_move_unchecked(m, |b| forced_win(b, player, plies - 1));
+            if moving {
+                moves.any(&mut wins)
+            } else {
+                moves.all(&mut wins)
+            }
+        }
+
+        (0..=max_depth).find(|&plies| forced_win(self, player, plies))
+    }
+
+    /// Legal move picked uniformly at random, `None` when there are no moves
+    pub fn random_move(&self, rng: &mut impl Rng) -> Option<Move> {
+        let moves = self.find_all_current_moves();
+        (!moves.is_empty()).then(|| moves[rng.gen_range(0..moves.len())])
+    }
+
+    /// Plays random moves for both sides until the game ends or `max_turns` more turns
+    /// have been played, returns the played moves
+    pub fn random_game(&mut self, max_turns: usize, rng: &mut impl Rng) -> Vec<Move> {
+        let (first_move, last_turn) = (self.moves.len(), self.turn.saturating_add(max_turns));
+        while self.game_in_progress() && self.turn < last_turn {
+            let move_ = self.random_move(rng).expect("Game in progress has moves");
+            self.push(move_).expect("Legal move");
+        }
+        self.moves[first_move..].to_vec()
+    }
+
+    /// Legal moves of the piece on the square, empty for an empty square or the opponent's piece
+    ///
+    /// Can be empty even when the piece could move on its own, because another piece has to
+    /// capture instead
+    pub fn legal_moves_from(&self, row: u8, col: u8) -> Vec<Move> {
+        self.find_all_current_moves()
+            .into_iter()
+            .filter(|m| m.from == (row, col))
+            .collect()
+    }
+
+    /// Legal move from `A1-B2`, `A1xC3` for a capture or the `Display` format of `Move`,
+    /// the moving piece, its color and the captured piece are filled in from the board
+    pub fn parse_move(&self, s: &str) -> Result<Move, ParseMoveError> {
+        let s = s.trim();
+        let (from, to, capture) = if s.contains("->") {
+            let move_ = Move::try_from(s)?;
+            (move_.from, move_.to, move_.kill.is_some())
+        } else {
+            let (from, to) = s
+                .split_once(['-', 'x', 'X'])
+                .ok_or(ParseMoveError::BadFormat)?;
+            let capture = !s.contains('-');
+            (parse_pos(from)?, parse_pos(to)?, capture)
+        };
+
+        if self.get_ref(from.0, from.1).is_none() {
+            return Err(ParseMoveError::NoPieceAtSource);
+        }
+
+        self.legal_moves_from(from.0, from.1)
+            .into_iter()
+            .find(|m| m.to == to && m.kill.is_some() == capture)
+            .ok_or(ParseMoveError::IllegalMove)
+    }
+
+    /// All moves of `player`'s pieces, ignoring whose turn it is and the forced capture
+    pub fn find_all_moves_for(&self, player: Color) -> Vec<Move> {
+        self.all_players_pieces(player)
+            .flat_map(|(r, c, _)| self.find_moves(r, c, None).unwrap())
+            .collect()
+    }
+
+    /// `find_all_moves_for` only for pieces of the `piece` kind
+    pub fn find_moves_for_piece_type(&self, piece: Piece, player: Color) -> Vec<Move> {
+        self.all_players_pieces(player)
+            .filter(|&(_, _, p)| p == piece)
+            .flat_map(|(r, c, _)| self.find_moves(r, c, None).unwrap())
+            .collect()
+    }
+
+    pub fn has_moves_for_piece_type(&self, piece: Piece, player: Color) -> bool {
+        self.all_players_pieces(player)
+            .filter(|&(_, _, p)| p == piece)
+            .any(|(r, c, _)| !self.find_moves(r, c, None).unwrap().is_empty())
+    }
+
+    /// Whether `move_` is a legal move without a capture, when the current player has
+    /// no capture
+    fn is_quiet_move(&self, move_: Move) -> bool {
+        let (row, col) = move_.from;
+        move_.kill.is_none()
+            && move_.color == self.current_player()
+            && self
+                .find_moves(row, col, Some(false))
+                .is_some_and(|moves| moves.contains(&move_))
+    }
+
+    pub fn is_valid_move(&self, move_: Move) -> bool {
+        self.validate_move(move_).is_ok()
+    }
+
+    /// Why `move_` can't be played in the current position
+    pub fn validate_move(&self, move_: Move) -> Result<(), MoveError> {
+        let Some(piece) = self.get_ref(move_.from.0, move_.from.1) else {
+            return Err(MoveError::NoPieceAtSource);
+        };
+
+        let player = self.current_player();
+        if piece.color != player {
+            return Err(MoveError::WrongColor);
+        }
+
+        if self.find_all_current_moves().contains(&move_) {
+            Ok(())
+        } else if self.last_player() == Some(player) {
+            Err(MoveError::ContinuationRequired)
+        } else {
+            Err(MoveError::NotInLegalSet)
+        }
+    }
+
+    pub fn all_players_pieces(&self, player: Color) -> impl Iterator<Item = (u8, u8, Piece)> + '_ {
+        (0..N as u8)
+            .flat_map(move |r| {
+                (0..N as u8).map(move |c| match *self.get_ref(r, c) {
+                    Some(PlayersPiece { color, piece }) if color == player => Some((r, c, piece)),
+                    _ => None,
+                })
+            })
+            .flatten()
+    }
+
+    /// Pawns and queens of `player`, e.g. `(12, 0)` for both sides of `Board::new(3, ..)`,
+    /// custom pieces count as neither
+    pub fn piece_count(&self, player: Color) -> (usize, usize) {
+        self.all_players_pieces(player)
+            .fold((0, 0), |(pawns, queens), (_, _, piece)| match piece {
+                Piece::Pawn => (pawns + 1, queens),
+                Piece::Queen => (pawns, queens + 1),
+                Piece::Custom(_) => (pawns, queens),
+            })
+    }
+
+    /// Captures the pieces of `color` could make, whoever is on the move
+    pub fn capture_move_count(&self, color: Color) -> usize {
+        self.all_players_pieces(color)
+            .map(|(r, c, _)| self.find_moves(r, c, Some(true)).unwrap().len())
+            .sum()
+    }
+
+    /// Moves without a capture the pieces of `color` could make, even when a capture is forced
+    pub fn quiet_move_count(&self, color: Color) -> usize {
+        self.all_players_pieces(color)
+            .map(|(r, c, _)| self.find_moves(r, c, Some(false)).unwrap().len())
+            .sum()
+    }
+
+    /// All pieces of `player`, custom pieces included
+    pub fn total_piece_count(&self, player: Color) -> usize {
+        self.all_players_pieces(player).count()
+    }
+
+    /// At most 6 pieces are left or both sides already have a queen
+    pub fn is_endgame(&self) -> bool {
+        let total = self.total_piece_count(Color::White) + self.total_piece_count(Color::Black);
+        let has_queen = |player| self.piece_count(player).1 > 0;
+        total <= 6 || (has_queen(Color::White) && has_queen(Color::Black))
+    }
+
+    /// Position weights for the current phase of the game
+    fn position_rates(&self) -> PositionRates {
+        if self.is_endgame() {
+            self.rating.endgame
+        } else {
+            self.rating.position
+        }
+    }
+
+    pub fn all_current_pieces(&self) -> impl Iterator<Item = (u8, u8, Piece)> + '_ {
+        self.all_players_pieces(self.current_player())
+    }
+
+    pub fn winner(&self) -> Option<Color> {
+        if self.is_draw() {
+            return None;
+        }
+
+        if self.total_piece_count(Color::White) == 0 {
+            return Some(Color::Black);
+        }
+
+        if self.total_piece_count(Color::Black) == 0 {
+            return Some(Color::White);
+        }
+
+        self.find_all_current_moves()
+            .is_empty()
+            .then(|| self.current_player().other())
+    }
+
+    /// Half-moves played since the last capture or promotion
+    pub fn check_fifty_move_clock(&self) -> usize {
+        self.fifty_move_clock
+    }
+
+    /// Same as `check_fifty_move_clock`, a promotion resets the count as well
+    /// because it can't be undone either
+    pub fn moves_since_capture(&self) -> usize {
+        self.check_fifty_move_clock()
+    }
+
+    /// Drawn by the fifty move rule or by threefold repetition, the search rates these
+    /// positions 0 as well
+    pub fn is_draw(&self) -> bool {
+        self.draw_reason().is_some()
+    }
+
+    /// Ends the game in a draw by agreement, see `draw_offer_should_accept`
+    pub fn agree_draw(&mut self) {
+        self.agreed_draw = true;
+    }
+
+    /// Why the current position is a draw, every draw check goes through this
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        if self.agreed_draw {
+            Some(DrawReason::Agreement)
+        } else if self.draw_by_50_move_rule() {
+            Some(DrawReason::FiftyMove)
+        } else if self.is_draw_by_repetition() {
+            Some(DrawReason::Repetition)
+        } else {
+            None
+        }
+    }
+
+    pub fn draw_by_50_move_rule(&self) -> bool {
+        self.fifty_move_clock >= self.rating.draw_after_turns
+    }
+
+    /// Current position has been reached for the third time
+    pub fn is_draw_by_repetition(&self) -> bool {
+        let earlier = self
+            .hash_history
+            .iter()
+            .filter(|&&h| h == self.hash)
+            .count();
+        earlier + 1 >= 3
+    }
+
+    pub fn game_in_progress(&self) -> bool {
+        self.winner().is_none() && !self.is_draw()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        !self.game_in_progress()
+    }
+
+    pub fn game_status(&self) -> GameStatus {
+        if let Some(reason) = self.draw_reason() {
+            GameStatus::Drawn(reason)
+        } else if let Some(winner) = self.winner() {
+            GameStatus::Won(winner)
+        } else {
+            GameStatus::InProgress
+        }
+    }
+
+    /// Plays a legal move, returns the winner if it ends the game
+    pub fn push(&mut self, move_: Move) -> Result<Option<Color>, MoveError> {
+        self.validate_move(move_)?;
+        self.push_unsafe(move_);
+        Ok(self.winner())
+    }
+
+    pub fn push_unsafe(&mut self, move_: Move) {
+        let Move {
+            from,
+            to,
+            piece,
+            kill,
+            color,
+            ..
+        } = move_;
+        self.hash_history.push(self.hash);
+        *self.get_mut(from.0, from.1) = None;
+
+        if let Some(kill) = kill {
+            *self.get_mut(kill.row, kill.col) = None;
+        }
+
+        let piece = match move_.is_upgrade_on(N) {
+            true => Piece::Queen,
+            false => piece,
+        };
+        *self.get_mut(to.0, to.1) = Some(PlayersPiece::new(color, piece));
+
+        let keys = zobrist_keys();
+        self.hash ^= keys.piece(from, color, move_.piece);
+        self.hash ^= keys.piece(to, color, piece);
+        if let Some(kill) = kill {
+            self.hash ^= keys.piece((kill.row, kill.col), color.other(), kill.piece);
+        }
+
+        self.clock_history.push(self.fifty_move_clock);
+        if kill.is_some() || move_.is_upgrade_on(N) {
+            self.fifty_move_clock = 0;
+        } else {
+            self.fifty_move_clock += 1;
+        }
+
+        self.moves.push(move_);
+
+        if self.current_player() != color {
+            self.turn += 1;
+            self.hash ^= zobrist_keys().black_to_move;
+        }
+    }
+
+    /// Takes back the last move and returns it, as it was played on the board before the undo,
+    /// `None` when there is nothing to take back
+    pub fn undo(&mut self) -> Option<Move> {
+        (!self.moves.is_empty()).then(|| self.pop())
+    }
+
+    pub fn pop(&mut self) -> Move {
+        if self.current_player() != self.moves.last().expect("No moves to pop").color {
+            self.turn -= 1;
+            self.hash ^= zobrist_keys().black_to_move;
+        }
+
+        let move_ = self.moves.pop().expect("No moves to pop");
+        self.fifty_move_clock = self.clock_history.pop().expect("No moves to pop");
+        self.hash_history.pop();
+        self.agreed_draw = false;
+
+        if move_.is_null {
+            return move_;
+        }
+
+        let Move {
+            from,
+            to,
+            piece,
+            kill,
+            color,
+            ..
+        } = move_;
+
+        *self.get_mut(from.0, from.1) = Some(PlayersPiece::new(color, piece));
+
+        if let Some(PosUncolorPiece { row, col, piece }) = kill {
+            *self.get_mut(row, col) = Some(PlayersPiece::new(color.other(), piece));
+        }
+
+        *self.get_mut(to.0, to.1) = None;
+
+        let keys = zobrist_keys();
+        self.hash ^= keys.piece(from, color, piece);
+        self.hash ^= keys.piece(to, color, move_.future_piece_on(N));
+        if let Some(kill) = kill {
+            self.hash ^= keys.piece((kill.row, kill.col), color.other(), kill.piece);
+        }
+
+        move_
+    }
+
+    /// Undoes half-moves until only the first `ply` moves are left, no-op when there are fewer
+    ///
+    /// Returns the undone moves in the order they were played
+    pub fn undo_history_to_ply(&mut self, ply: usize) -> Vec<Move> {
+        let mut undone: Vec<_> = (ply..self.moves.len()).map(|_| self.pop()).collect();
+        undone.reverse();
+        undone
+    }
+
+    /// Undoes the whole game, returns its moves in the order they were played
+    pub fn pop_all(&mut self) -> Vec<Move> {
+        self.undo_history_to_ply(0)
+    }
+
+    /// Plays `moves` from the current position, on an illegal move the board is left as
+    /// it was before
+    pub fn replay(&mut self, moves: &[Move]) -> Result<(), MoveError> {
+        let ply = self.moves.len();
+        for &move_ in moves {
+            if let Err(err) = self.push(move_) {
+                self.undo_history_to_ply(ply);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Passes the turn to the opponent without moving any piece, used for null-move pruning
+    pub fn apply_null_move(&mut self) {
+        let color = self.current_player();
+        self.clock_history.push(self.fifty_move_clock);
+        self.hash_history.push(self.hash);
+        self.moves.push(Move::null(color));
+        self.turn += 1;
+        self.hash ^= zobrist_keys().black_to_move;
+    }
+
+    /// Copy of the board after the current player passed
+    pub fn after_null_move(&self) -> Board<N> {
+        let mut board = self.clone();
+        board.apply_null_move();
+        board
+    }
+
+    pub fn undo_null_move(&mut self) {
+        assert!(
+            self.last_move().expect("No moves to pop").is_null,
+            "Last move is not a null move"
+        );
+        self.pop();
+    }
+
+    /// Passes the turn to the other player, as allowed by some variants
+    pub fn flip_turn(&mut self) {
+        self.apply_null_move();
+    }
+
+    pub fn unflip_turn(&mut self) {
+        self.undo_null_move();
+    }
+
+    /// Panics if the board state contradicts itself
+    pub fn assert_consistent(&self) {
+        let next_players = self
+            .moves
+            .iter()
+            .skip(1)
+            .map(|m| m.color)
+            .chain(Some(self.current_player()));
+        let turns = self
+            .moves
+            .iter()
+            .zip(next_players)
+            .filter(|(m, next)| m.color != *next)
+            .count();
+        assert_eq!(turns, self.turn, "Turn doesn't match the move history");
+
+        for row in 0..N as u8 {
+            for col in 0..N as u8 {
+                let Some(piece) = self.get_ref(row, col) else {
+                    continue;
+                };
+
+                assert!((row + col) % 2 == 0, "Piece on a light square");
+                assert!(
+                    piece.piece != Piece::Pawn || Self::rows_to_promotion(row, piece.color) > 0,
+                    "Pawn left on the last row"
+                );
+            }
+        }
+    }
+
+    /// Forgets the move history, the current position becomes the start of the game
+    pub fn clear_history(&mut self) {
+        self.moves.clear();
+        self.turn = 0;
+        self.first_player = Color::White;
+        self.fifty_move_clock = 0;
+        self.clock_history.clear();
+        self.hash = self.compute_zobrist_hash();
+        self.hash_history.clear();
+    }
+
+    /// The position from the other side's point of view, with the colours swapped and the board
+    /// turned around, which mirrors the rows and keeps the pieces on dark squares,
+    /// the history is cleared, so the former Black is to move as White
+    pub fn flip_perspective(&self) -> Board<N> {
+        let mut flipped = self.clone();
+        let last = N as u8 - 1;
+        for row in 0..N as u8 {
+            for col in 0..N as u8 {
+                *flipped.get_mut(last - row, last - col) = self
+                    .get_ref(row, col)
+                    .map(|p| PlayersPiece::new(p.color.other(), p.piece));
+            }
+        }
+        flipped.show_moves_for = self.show_moves_for.map(|(r, c)| (last - r, last - c));
+        flipped.clear_history();
+        flipped
+    }
+
+    /// Pieces as a grid without any terminal codes unless `colored`, one line per row
+    /// starting with row A, `w` and `b` for pawns, `W` and `B` for queens and `.` for empty
+    /// squares, custom pieces are written as pawns, the move history isn't kept
+    pub fn export_ascii(&self, colored: bool) -> String {
+        let mut grid = String::new();
+        for row in 0..N as u8 {
+            for col in 0..N as u8 {
+                let Some(PlayersPiece { color, piece }) = *self.get_ref(row, col) else {
+                    grid.push('.');
+                    continue;
+                };
+                let symbol = match piece {
+                    Piece::Queen => color.to_char().to_ascii_uppercase(),
+                    Piece::Pawn | Piece::Custom(_) => color.to_char(),
+                };
+                if colored {
+                    grid += &symbol.to_string().color(color.colored()).to_string();
+                } else {
+                    grid.push(symbol);
+                }
+            }
+            grid.push('\n');
+        }
+        grid
+    }
+
+    /// Position as a standalone SVG image, row A on top like `Display`, with arrows for the
+    /// moves of the `show_moves_for` piece
+    #[cfg(feature = "svg")]
+    pub fn svg(&self) -> String {
+        const SQUARE: usize = 50;
+        let size = N * SQUARE;
+        let center = |(row, col): (u8, u8)| {
+            let at = |i: u8| i as usize * SQUARE + SQUARE / 2;
+            (at(col), at(row))
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+             viewBox=\"0 0 {size} {size}\">\n\
+             <defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"8\" refY=\"5\" \
+             markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\">\
+             <path d=\"M0,0 L10,5 L0,10 z\" fill=\"#2e7d32\"/></marker></defs>\n"
+        );
+        for row in 0..N as u8 {
+            for col in 0..N as u8 {
+                let fill = if (row + col) % 2 == 0 {
+                    "#b58863"
+                } else {
+                    "#f0d9b5"
+                };
+                let (x, y) = (col as usize * SQUARE, row as usize * SQUARE);
+                svg += &format!(
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE}\" height=\"{SQUARE}\" \
+                     fill=\"{fill}\"/>\n"
+                );
+            }
+        }
+
+        let pieces = [Color::White, Color::Black].into_iter().flat_map(|color| {
+            self.all_players_pieces(color)
+                .map(move |(r, c, p)| (r, c, color, p))
+        });
+        for (row, col, color, piece) in pieces {
+            let symbol = match (color, piece) {
+                (Color::White, Piece::Pawn) => '⛀',
+                (Color::White, Piece::Queen) => '⛁',
+                (Color::Black, Piece::Pawn) => '⛂',
+                (Color::Black, Piece::Queen) => '⛃',
+                (_, Piece::Custom(kind)) => kind.symbol(),
+            };
+            let fill = match color {
+                Color::White => "#ffffff",
+                Color::Black => "#c62828",
+            };
+            let (x, y) = center((row, col));
+            svg += &format!(
+                "<text x=\"{x}\" y=\"{y}\" font-size=\"40\" text-anchor=\"middle\" \
+                 dominant-baseline=\"central\" fill=\"{fill}\" stroke=\"#000000\">\
+                 {symbol}</text>\n"
+            );
+        }
+
+        let moves = self
+            .show_moves_for
+            .and_then(|(r, c)| self.find_moves(r, c, None))
+            .unwrap_or_default();
+        for move_ in moves {
+            let ((x1, y1), (x2, y2)) = (center(move_.from), center(move_.to));
+            svg += &format!(
+                "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#2e7d32\" \
+                 stroke-width=\"5\" marker-end=\"url(#arrow)\"/>\n"
+            );
+        }
+
+        svg + "</svg>\n"
+    }
+
+    /// Writes `svg` to `path`
+    #[cfg(feature = "svg")]
+    pub fn to_svg(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.svg())
+    }
+
+    /// Position from `export_ascii` without colors, White is on the move
+    pub fn from_ascii(s: &str, rates: RateConfig) -> Result<Self, ParseError> {
+        let rows: Vec<_> = s.lines().map(|line| line.trim()).collect();
+        if rows.len() != N || rows.iter().any(|row| row.chars().count() != N) {
+            return Err(ParseError::BadGrid);
+        }
+
+        let mut pieces = vec![];
+        for (row, line) in rows.iter().enumerate() {
+            for (col, symbol) in line.chars().enumerate() {
+                if symbol == '.' {
+                    continue;
+                }
+                let color = Color::from_char(symbol).ok_or(ParseError::BadGrid)?;
+                let piece = if symbol.is_ascii_uppercase() {
+                    Piece::Queen
+                } else {
+                    Piece::Pawn
+                };
+                pieces.push((row as u8, col as u8, PlayersPiece::new(color, piece)));
+            }
+        }
+
+        let mut board = Self::empty_with_rates(rates);
+        board.set_position(&pieces).map_err(ParseError::Placement)?;
+        Ok(board)
+    }
+
+    /// Puts `piece` on the square or empties it with `None`, without any checks of the rules
+    /// and keeping the move history, for setting up test positions
+    pub fn set_piece(
+        &mut self,
+        row: u8,
+        col: u8,
+        piece: Option<PlayersPiece>,
+    ) -> Result<(), BoardError> {
+        if !self.in_bounds(row as i8, col as i8) {
+            return Err(BoardError::OutOfBounds);
+        }
+
+        *self.get_mut(row, col) = piece;
+        self.hash = self.compute_zobrist_hash();
+        Ok(())
+    }
+
+    pub fn remove_piece(&mut self, row: u8, col: u8) -> Result<PlayersPiece, BoardError> {
+        if !self.in_bounds(row as i8, col as i8) {
+            return Err(BoardError::OutOfBounds);
+        }
+
+        let piece = self
+            .get_mut(row, col)
+            .take()
+            .ok_or(BoardError::EmptySquare)?;
+        self.clear_history();
+        Ok(piece)
+    }
+
+    pub fn add_piece(&mut self, row: u8, col: u8, piece: PlayersPiece) -> Result<(), BoardError> {
+        if !self.in_bounds(row as i8, col as i8) {
+            return Err(BoardError::OutOfBounds);
+        }
+        if !(row + col).is_multiple_of(2) {
+            return Err(BoardError::LightSquare);
+        }
+        if self.get_ref(row, col).is_some() {
+            return Err(BoardError::Occupied);
+        }
+        if piece.piece == Piece::Pawn && Self::rows_to_promotion(row, piece.color) == 0 {
+            return Err(BoardError::PawnOnLastRow);
+        }
+
+        *self.get_mut(row, col) = Some(piece);
+        self.clear_history();
+        Ok(())
+    }
+
+    /// Replaces all pieces with `pieces`, leaves the board untouched on error
+    pub fn set_position(
+        &mut self,
+        pieces: &[(u8, u8, PlayersPiece)],
+    ) -> Result<(), PlacementError> {
+        let placement: Vec<_> = pieces
+            .iter()
+            .map(|&(row, col, p)| (row, col, p.color, p.piece))
+            .collect();
+        Self::validate_piece_placement(&placement).map_err(|errors| errors[0])?;
+
+        let mut board = Self::empty_with_rates(self.rating);
+        for &(row, col, piece) in pieces {
+            board
+                .add_piece(row, col, piece)
+                .expect("Placement is validated");
+        }
+
+        self.board = board.board;
+        self.clear_history();
+        Ok(())
+    }
+
+    /// Every problem with placing `pieces` on an empty board
+    fn validate_piece_placement(
+        pieces: &[(u8, u8, Color, Piece)],
+    ) -> Result<(), Vec<PlacementError>> {
+        // as many as a side starts with
+        let max_pieces = N / 2 * (N / 2 - 1);
+
+        let mut errors = Vec::new();
+        let mut occupied = [[false; N]; N];
+        for &(row, col, color, piece) in pieces {
+            if row as usize >= N || col as usize >= N {
+                errors.push(PlacementError::OutOfBounds { row, col });
+                continue;
+            }
+            if !(row + col).is_multiple_of(2) {
+                errors.push(PlacementError::LightSquare { row, col });
+            }
+            if occupied[row as usize][col as usize] {
+                errors.push(PlacementError::SameSquare { row, col });
+            }
+            if piece == Piece::Pawn && Self::rows_to_promotion(row, color) == 0 {
+                errors.push(PlacementError::PawnOnLastRow { row, col });
+            }
+            occupied[row as usize][col as usize] = true;
+        }
+
+        for color in [Color::White, Color::Black] {
+            let count = |kind: Option<Piece>| {
+                pieces
+                    .iter()
+                    .filter(|&&(_, _, c, p)| c == color && kind.is_none_or(|kind| kind == p))
+                    .count()
+            };
+
+            let total = count(None);
+            if total > max_pieces {
+                errors.push(PlacementError::TooManyPieces {
+                    color,
+                    count: total,
+                });
+            }
+            let queens = count(Some(Piece::Queen));
+            if queens > max_pieces {
+                errors.push(PlacementError::TooManyQueens {
+                    color,
+                    count: queens,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Plays `move_`, runs `f` on the board and takes the move back, returns what `f` did,
+    /// panics when the move isn't legal or `f` leaves moves of its own on the board
+    ///
+    /// Searches are built from these, a minimax rates every move of the current player by
+    /// `board.with_move(m, |b| -b.minimax(depth - 1))` and keeps the best one
+    ///
+    /// # Examples
+    ///
+    /// A minimax over material, `depth` counts turns, so the score keeps its sign while
+    /// the same player goes on capturing:
+    ///
+    /// ```
+    /// # use checkengine::{Board, RateConfigBuilder, STARTING_LINES};
+    /// fn minimax(board: &mut Board, depth: usize) -> f32 {
+    ///     let player = board.current_player();
+    ///     if depth == 0 || board.winner().is_some() {
+    ///         return board.total_piece_count(player) as f32
+    ///             - board.total_piece_count(player.other()) as f32;
+    ///     }
+    ///
+    ///     board
+    ///         .find_all_current_moves()
+    ///         .into_iter()
+    ///         .map(|m| {
+    ///             board.with_move(m, |b| {
+    ///                 if b.current_player() == player {
+    ///                     minimax(b, depth)
+    ///                 } else {
+    ///                     -minimax(b, depth - 1)
+    ///                 }
+    ///             })
+    ///         })
+    ///         .fold(f32::NEG_INFINITY, f32::max)
+    /// }
+    ///
+    /// let rates = RateConfigBuilder::new().build().unwrap();
+    /// let mut board = Board::new(STARTING_LINES, rates);
+    /// assert_eq!(minimax(&mut board, 2), 0.);
+    /// assert_eq!(board.last_move(), None);
+    /// ```
+    pub fn with_move<T>(&mut self, move_: Move, f: impl FnOnce(&mut Self) -> T) -> T {
+        let moves_before = self.moves.len();
+        self.push(move_).expect("Legal move");
+        let ret = f(self);
+        self.pop();
+        assert_eq!(moves_before, self.moves.len());
+        ret
+    }
+
+    /// `with_move` without checking that `move_` is legal, for the hot loops of the search
+    ///
+    /// # Safety
+    ///
+    /// Not `unsafe` for Rust, memory is never at risk, but only pseudo-legal moves of the
+    /// current position may be passed: the ones just returned by `find_all_current_moves`
+    /// or by `find_moves` for a piece of the player on the move. Anything else leaves the
+    /// board, its hash and its history in an inconsistent state, which taking the move back
+    /// doesn't repair
+    ///
+    /// # Examples
+    ///
+    /// The minimax of `with_move` can skip the check, since it only plays moves
+    /// `find_all_current_moves` just returned:
+    ///
+    /// ```
+    /// # use checkengine::{Board, RateConfigBuilder, STARTING_LINES};
+    /// # fn minimax(board: &mut Board, depth: usize) -> f32 {
+    /// #     let player = board.current_player();
+    /// #     if depth == 0 || board.winner().is_some() {
+    /// #         return board.total_piece_count(player) as f32
+    /// #             - board.total_piece_count(player.other()) as f32;
+    /// #     }
+    /// board
+    ///     .find_all_current_moves()
+    ///     .into_iter()
+    ///     .map(|m| {
+    ///         board.with_move_unchecked(m, |b| {
+    ///             if b.current_player() == player {
+    ///                 minimax(b, depth)
+    ///             } else {
+    ///                 -minimax(b, depth - 1)
+    ///             }
+    ///         })
+    ///     })
+    ///     .fold(f32::NEG_INFINITY, f32::max)
+    /// # }
+    /// # let rates = RateConfigBuilder::new().build().unwrap();
+    /// # assert_eq!(minimax(&mut Board::new(STARTING_LINES, rates), 2), 0.);
+    /// ```
+    pub fn with_move_unchecked<T>(&mut self, move_: Move, f: impl FnOnce(&mut Self) -> T) -> T {
+        let moves_before = self.moves.len();
+        self.push_unsafe(move_);
+        let ret = f(self);
+        self.pop();
+        assert_eq!(moves_before, self.moves.len());
+        ret
+    }
+
+    /// Hash of the position computed from scratch, `zobrist_hash` is kept equal to it
+    fn compute_zobrist_hash(&self) -> u64 {
+        let keys = zobrist_keys();
+        let mut hash = match self.current_player() {
+            Color::White => 0,
+            Color::Black => keys.black_to_move,
+        };
+
+        for row in 0..N as u8 {
+            for col in 0..N as u8 {
+                if let Some(PlayersPiece { color, piece }) = *self.get_ref(row, col) {
+                    hash ^= keys.piece((row, col), color, piece);
+                }
+            }
+        }
+
+        hash
+    }
+
+    fn rows_to_promotion(row: u8, player: Color) -> u8 {
+        match player {
+            Color::White => N as u8 - 1 - row,
+            Color::Black => row,
+        }
+    }
+}
+
+/// JSON document, built by the `to_json` methods and read back by their `from_json`
+///
+/// Written by hand since serde isn't available to this build, `Display` gives the compact
+/// encoding and `Json::parse` reads any JSON text
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Fields in the order they were written
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Json, JsonError> {
+        let mut parser = JsonParser {
+            bytes: s.as_bytes(),
+            pos: 0,
+        };
+        let json = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.bytes.len() {
+            return Err(JsonError::Syntax(parser.pos));
+        }
+        Ok(json)
+    }
+
+    /// Field of an object, `None` for other values
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// `f32` written with its shortest decimal form instead of the digits of its `f64`
+    fn float(value: f32) -> Json {
+        Json::Number(value.to_string().parse().unwrap_or(f64::NAN))
+    }
+
+    fn field(&self, name: &'static str) -> Result<&Json, JsonError> {
+        self.get(name).ok_or(JsonError::BadField(name))
+    }
+
+    fn number_field(&self, name: &'static str) -> Result<f64, JsonError> {
+        match self.field(name)? {
+            Json::Number(n) => Ok(*n),
+            _ => Err(JsonError::BadField(name)),
+        }
+    }
+
+    fn float_field(&self, name: &'static str) -> Result<f32, JsonError> {
+        self.number_field(name).map(|n| n as f32)
+    }
+
+    fn usize_field(&self, name: &'static str) -> Result<usize, JsonError> {
+        let n = self.number_field(name)?;
+        if n >= 0.0 && n.fract() == 0.0 && n <= usize::MAX as f64 {
+            Ok(n as usize)
+        } else {
+            Err(JsonError::BadField(name))
+        }
+    }
+
+    fn bool_field(&self, name: &'static str) -> Result<bool, JsonError> {
+        match self.field(name)? {
+            Json::Bool(b) => Ok(*b),
+            _ => Err(JsonError::BadField(name)),
+        }
+    }
+
+    fn array_field(&self, name: &'static str) -> Result<&[Json], JsonError> {
+        match self.field(name)? {
+            Json::Array(items) => Ok(items),
+            _ => Err(JsonError::BadField(name)),
+        }
+    }
+
+    fn pos(pos: (u8, u8)) -> Json {
+        Json::Array(vec![Json::Number(pos.0 as f64), Json::Number(pos.1 as f64)])
+    }
+
+    /// `[row, col]` field, both below `MAX_BOARD_SIZE`
+    fn pos_field(&self, name: &'static str) -> Result<(u8, u8), JsonError> {
+        let coord = |json: &Json| match *json {
+            Json::Number(n) if n >= 0.0 && n.fract() == 0.0 && n < MAX_BOARD_SIZE as f64 => {
+                Ok(n as u8)
+            }
+            _ => Err(JsonError::BadField(name)),
+        };
+        match self.array_field(name)? {
+            [row, col] => Ok((coord(row)?, coord(col)?)),
+            _ => Err(JsonError::BadField(name)),
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = |f: &mut std::fmt::Formatter<'_>, s: &str| {
+            write!(f, "\"")?;
+            for c in s.chars() {
+                match c {
+                    '"' => write!(f, "\\\"")?,
+                    '\\' => write!(f, "\\\\")?,
+                    '\n' => write!(f, "\\n")?,
+                    '\r' => write!(f, "\\r")?,
+                    '\t' => write!(f, "\\t")?,
+                    c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                    c => write!(f, "{}", c)?,
+                }
+            }
+            write!(f, "\"")
+        };
+
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // JSON has no infinities
+            Json::Number(n) if !n.is_finite() => write!(f, "null"),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Recursive descent over the bytes of a JSON text, `pos` is where it failed on an error
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error<T>(&self) -> Result<T, JsonError> {
+        Err(JsonError::Syntax(self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.bytes.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            self.error()
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        fields.push((key, self.value()?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Object(fields))
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => self.error(),
+        }
+    }
+
+    fn literal(&mut self, text: &str, value: Json) -> Result<Json, JsonError> {
+        if self.bytes[self.pos..].starts_with(text.as_bytes()) {
+            self.pos += text.len();
+            Ok(value)
+        } else {
+            self.error()
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(b))
+        {
+            self.pos += 1;
+        }
+        // only ASCII was consumed, so the slice is on char boundaries
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).expect("ASCII digits");
+        match text.parse() {
+            Ok(n) => Ok(Json::Number(n)),
+            Err(_) => {
+                self.pos = start;
+                self.error()
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return self.error();
+        }
+        self.pos += 1;
+
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while self
+                .bytes
+                .get(self.pos)
+                .is_some_and(|&b| b != b'"' && b != b'\\')
+            {
+                self.pos += 1;
+            }
+            // the text came from a `&str` and stops only at ASCII quotes and backslashes
+            out += std::str::from_utf8(&self.bytes[start..self.pos]).expect("UTF-8 text");
+
+            match self.bytes.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.bytes.get(self.pos) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let code = self.hex_code()?;
+                            // characters outside the basic plane come as a surrogate pair
+                            let code = if (0xd800..0xdc00).contains(&code) {
+                                if !self.bytes[self.pos + 1..].starts_with(b"\\u") {
+                                    return self.error();
+                                }
+                                self.pos += 2;
+                                let low = self.hex_code()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return self.error();
+                                }
+                                0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                            } else {
+                                code
+                            };
+                            match char::from_u32(code) {
+                                Some(c) => c,
+                                None => return self.error(),
+                            }
+                        }
+                        _ => return self.error(),
+                    };
+                    out.push(escaped);
+                    self.pos += 1;
+                }
+                _ => return self.error(),
+            }
+        }
+    }
+
+    /// 4 hex digits after `\u`, `pos` is left on the last one
+    fn hex_code(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.pos + 1..self.pos + 5)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        match digits {
+            Some(code) => {
+                self.pos += 4;
+                Ok(code)
+            }
+            None => self.error(),
+        }
+    }
+}
+
+impl Color {
+    pub fn to_json(self) -> Json {
+        Json::String(
+            match self {
+                Color::White => "White",
+                Color::Black => "Black",
+            }
+            .to_string(),
+        )
+    }
+
+    pub fn from_json(json: &Json) -> Result<Color, JsonError> {
+        match json {
+            Json::String(s) if s == "White" => Ok(Color::White),
+            Json::String(s) if s == "Black" => Ok(Color::Black),
+            _ => Err(JsonError::BadField("color")),
+        }
+    }
+}
+
+impl Piece {
+    /// Name of the piece, custom pieces are written too, but can't be read back
+    pub fn to_json(self) -> Json {
+        Json::String(self.kind().name().to_string())
+    }
+
+    pub fn from_json(json: &Json) -> Result<Piece, JsonError> {
+        match json {
+            Json::String(s) if s == "Pawn" => Ok(Piece::Pawn),
+            Json::String(s) if s == "Queen" => Ok(Piece::Queen),
+            _ => Err(JsonError::BadField("piece")),
+        }
+    }
+}
+
+impl PlayersPiece {
+    /// `{"color": "White", "piece": "Pawn"}`
+    pub fn to_json(self) -> Json {
+        Json::object(vec![
+            ("color", self.color.to_json()),
+            ("piece", self.piece.to_json()),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<PlayersPiece, JsonError> {
+        Ok(PlayersPiece {
+            color: Color::from_json(json.field("color")?)?,
+            piece: Piece::from_json(json.field("piece")?)?,
+        })
+    }
+}
+
+impl PosUncolorPiece {
+    /// `{"row": 3, "col": 4, "piece": "Pawn"}`
+    pub fn to_json(self) -> Json {
+        Json::object(vec![
+            ("row", Json::Number(self.row as f64)),
+            ("col", Json::Number(self.col as f64)),
+            ("piece", self.piece.to_json()),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<PosUncolorPiece, JsonError> {
+        let row = json.usize_field("row")?;
+        let col = json.usize_field("col")?;
+        if row >= MAX_BOARD_SIZE || col >= MAX_BOARD_SIZE {
+            return Err(JsonError::BadField("kill"));
+        }
+        Ok(PosUncolorPiece {
+            piece: Piece::from_json(json.field("piece")?)?,
+            row: row as u8,
+            col: col as u8,
+        })
+    }
+}
+
+impl Move {
+    /// `{"from": [2, 2], "to": [3, 3], "piece": "Pawn", "kill": null, "color": "White"}`,
+    /// null moves have `"is_null": true` as well
+    pub fn to_json(self) -> Json {
+        let mut fields = vec![
+            ("from", Json::pos(self.from)),
+            ("to", Json::pos(self.to)),
+            ("piece", self.piece.to_json()),
+            (
+                "kill",
+                self.kill.map_or(Json::Null, PosUncolorPiece::to_json),
+            ),
+            ("color", self.color.to_json()),
+        ];
+        if self.is_null {
+            fields.push(("is_null", Json::Bool(true)));
+        }
+        Json::object(fields)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Move, JsonError> {
+        Ok(Move {
+            from: json.pos_field("from")?,
+            to: json.pos_field("to")?,
+            piece: Piece::from_json(json.field("piece")?)?,
+            kill: match json.field("kill")? {
+                Json::Null => None,
+                kill => Some(PosUncolorPiece::from_json(kill)?),
+            },
+            color: Color::from_json(json.field("color")?)?,
+            is_null: json.get("is_null").is_some() && json.bool_field("is_null")?,
+        })
+    }
+}
+
+impl PieceRates {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("pawn", Json::float(self.pawn)),
+            ("queen", Json::float(self.queen)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<PieceRates, JsonError> {
+        Ok(PieceRates {
+            pawn: json.float_field("pawn")?,
+            queen: json.float_field("queen")?,
+        })
+    }
+}
+
+impl KillRates {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("pawn", Json::float(self.pawn)),
+            ("queen", Json::float(self.queen)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<KillRates, JsonError> {
+        Ok(KillRates {
+            pawn: json.float_field("pawn")?,
+            queen: json.float_field("queen")?,
+        })
+    }
+}
+
+impl PositionRates {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("pawn", Json::float(self.pawn)),
+            ("queen", Json::float(self.queen)),
+            ("isolated", Json::float(self.isolated)),
+            ("back_rank_hole", Json::float(self.back_rank_hole)),
+            ("column_gap", Json::float(self.column_gap)),
+            ("low_material", Json::float(self.low_material)),
+            ("queen_deficit", Json::float(self.queen_deficit)),
+            ("territory", Json::float(self.territory)),
+            ("promo_threat", Json::float(self.promo_threat)),
+            ("control", Json::float(self.control)),
+            ("back_rank", Json::float(self.back_rank)),
+            ("trapped", Json::float(self.trapped)),
+            ("mobility", Json::float(self.mobility)),
+            ("tension", Json::float(self.tension)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<PositionRates, JsonError> {
+        Ok(PositionRates {
+            pawn: json.float_field("pawn")?,
+            queen: json.float_field("queen")?,
+            isolated: json.float_field("isolated")?,
+            back_rank_hole: json.float_field("back_rank_hole")?,
+            column_gap: json.float_field("column_gap")?,
+            low_material: json.float_field("low_material")?,
+            queen_deficit: json.float_field("queen_deficit")?,
+            territory: json.float_field("territory")?,
+            promo_threat: json.float_field("promo_threat")?,
+            control: json.float_field("control")?,
+            back_rank: json.float_field("back_rank")?,
+            trapped: json.float_field("trapped")?,
+            mobility: json.float_field("mobility")?,
+            tension: json.float_field("tension")?,
+        })
+    }
+}
+
+impl RateConfig {
+    /// Object with a field for every setting, named like the struct fields
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("pieces", self.pieces.to_json()),
+            ("position", self.position.to_json()),
+            ("endgame", self.endgame.to_json()),
+            ("kills", self.kills.to_json()),
+            ("win", Json::float(self.win)),
+            ("max_depth", Json::Number(self.max_depth as f64)),
+            ("training_blend", Json::float(self.training_blend)),
+            ("target_complexity", Json::Number(self.target_complexity)),
+            ("avoid_repetition", Json::Bool(self.avoid_repetition)),
+            (
+                "draw_acceptance_threshold",
+                Json::float(self.draw_acceptance_threshold),
+            ),
+            (
+                "draw_after_turns",
+                Json::Number(self.draw_after_turns as f64),
+            ),
+            ("use_extensions", Json::Bool(self.use_extensions)),
+            ("prefer_defensive", Json::Bool(self.prefer_defensive)),
+            ("use_threat_score", Json::Bool(self.use_threat_score)),
+            (
+                "tempo_loss_threshold",
+                Json::float(self.tempo_loss_threshold),
+            ),
+            ("use_move_ordering", Json::Bool(self.use_move_ordering)),
+            (
+                "quiescence_depth",
+                Json::Number(self.quiescence_depth as f64),
+            ),
+            ("use_killer_moves", Json::Bool(self.use_killer_moves)),
+            (
+                "use_history_heuristic",
+                Json::Bool(self.use_history_heuristic),
+            ),
+            ("use_null_move", Json::Bool(self.use_null_move)),
+            ("use_lazy_moves", Json::Bool(self.use_lazy_moves)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<RateConfig, JsonError> {
+        Ok(RateConfig {
+            pieces: PieceRates::from_json(json.field("pieces")?)?,
+            position: PositionRates::from_json(json.field("position")?)?,
+            endgame: PositionRates::from_json(json.field("endgame")?)?,
+            kills: KillRates::from_json(json.field("kills")?)?,
+            win: json.float_field("win")?,
+            max_depth: json.usize_field("max_depth")?,
+            training_blend: json.float_field("training_blend")?,
+            target_complexity: json.number_field("target_complexity")?,
+            avoid_repetition: json.bool_field("avoid_repetition")?,
+            draw_acceptance_threshold: json.float_field("draw_acceptance_threshold")?,
+            draw_after_turns: json.usize_field("draw_after_turns")?,
+            use_extensions: json.bool_field("use_extensions")?,
+            prefer_defensive: json.bool_field("prefer_defensive")?,
+            use_threat_score: json.bool_field("use_threat_score")?,
+            tempo_loss_threshold: json.float_field("tempo_loss_threshold")?,
+            use_move_ordering: json.bool_field("use_move_ordering")?,
+            quiescence_depth: json.usize_field("quiescence_depth")?,
+            use_killer_moves: json.bool_field("use_killer_moves")?,
+            use_history_heuristic: json.bool_field("use_history_heuristic")?,
+            use_null_move: json.bool_field("use_null_move")?,
+            use_lazy_moves: json.bool_field("use_lazy_moves")?,
+        })
+    }
+}
+
+impl Variant {
+    pub fn to_json(self) -> Json {
+        Json::String(format!("{:?}", self))
+    }
+
+    pub fn from_json(json: &Json) -> Result<Variant, JsonError> {
+        match json {
+            Json::String(s) if s == "Standard" => Ok(Variant::Standard),
+            Json::String(s) if s == "International" => Ok(Variant::International),
+            _ => Err(JsonError::BadField("variant")),
+        }
+    }
+}
+
+impl<const N: usize> Board<N> {
+    /// The rows of squares, `null` or a `PlayersPiece`, the moves of the game, the turn,
+    /// the player on the move, the fifty move clock, the variant and the rating
+    ///
+    /// `show_moves_for` and the evaluator are left out, a loaded board has the defaults
+    pub fn to_json(&self) -> Json {
+        let rows = self
+            .board
+            .iter()
+            .map(|row| {
+                Json::Array(
+                    row.iter()
+                        .map(|square| square.map_or(Json::Null, PlayersPiece::to_json))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        Json::object(vec![
+            ("board", Json::Array(rows)),
+            (
+                "moves",
+                Json::Array(self.moves.iter().map(|m| m.to_json()).collect()),
+            ),
+            ("turn", Json::Number(self.turn as f64)),
+            ("player", self.current_player().to_json()),
+            (
+                "fifty_move_clock",
+                Json::Number(self.fifty_move_clock as f64),
+            ),
+            ("agreed_draw", Json::Bool(self.agreed_draw)),
+            ("variant", self.variant.to_json()),
+            ("rating", self.rating.to_json()),
+        ])
+    }
+
+    /// Board from `to_json`, the moves are taken back on the squares and replayed, so they
+    /// can be undone and count for repetitions again
+    ///
+    /// The fifty move clock of the positions before the first capture or promotion of the
+    /// moves isn't written down, undoing into them counts it from 0
+    pub fn from_json(json: &Json) -> Result<Board<N>, JsonError> {
+        let mut board = Board::empty_with_rates(RateConfig::from_json(json.field("rating")?)?);
+        board.variant = Variant::from_json(json.field("variant")?)?;
+
+        let rows = json.array_field("board")?;
+        if rows.len() != N {
+            return Err(JsonError::BadField("board"));
+        }
+        for (row, squares) in board.board.iter_mut().zip(rows) {
+            match squares {
+                Json::Array(squares) if squares.len() == N => {
+                    for (square, json) in row.iter_mut().zip(squares) {
+                        *square = match json {
+                            Json::Null => None,
+                            piece => Some(PlayersPiece::from_json(piece)?),
+                        };
+                    }
+                }
+                _ => return Err(JsonError::BadField("board")),
+            }
+        }
+        let position = board.board;
+
+        let moves = json
+            .array_field("moves")?
+            .iter()
+            .map(Move::from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        let player = Color::from_json(json.field("player")?)?;
+        board.first_player = moves.first().map_or(player, |m| m.color);
+
+        fn square<const N: usize>(
+            board: &mut Board<N>,
+            (row, col): (u8, u8),
+        ) -> Result<&mut Option<PlayersPiece>, JsonError> {
+            if board.in_bounds(row as i8, col as i8) {
+                Ok(board.get_mut(row, col))
+            } else {
+                Err(JsonError::BadField("moves"))
+            }
+        }
+
+        // back to the position before the first move
+        for move_ in moves.iter().rev().filter(|m| !m.is_null) {
+            let moved = PlayersPiece::new(move_.color, move_.future_piece_on(N));
+            if square(&mut board, move_.to)?.take() != Some(moved) {
+                return Err(JsonError::BadField("moves"));
+            }
+            if let Some(kill) = move_.kill {
+                let killed = PlayersPiece::new(move_.color.other(), kill.piece);
+                if square(&mut board, (kill.row, kill.col))?
+                    .replace(killed)
+                    .is_some()
+                {
+                    return Err(JsonError::BadField("moves"));
+                }
+            }
+            let moved = PlayersPiece::new(move_.color, move_.piece);
+            if square(&mut board, move_.from)?.replace(moved).is_some() {
+                return Err(JsonError::BadField("moves"));
+            }
+        }
+
+        board.hash = board.compute_zobrist_hash();
+        for &move_ in &moves {
+            if move_.is_null {
+                board.apply_null_move();
+            } else {
+                board
+                    .push(move_)
+                    .map_err(|_| JsonError::BadField("moves"))?;
+            }
+        }
+        if board.board != position || board.current_player() != player {
+            return Err(JsonError::BadField("moves"));
+        }
+
+        board.turn = json.usize_field("turn")?;
+        let clock = json.usize_field("fifty_move_clock")?;
+        if clock != board.fifty_move_clock {
+            // counted from 0, with no capture or promotion among the moves every clock
+            // is off by as much, otherwise the last one is already right
+            let played = moves.iter().filter(|m| !m.is_null).count();
+            if board.fifty_move_clock != played || clock < played {
+                return Err(JsonError::BadField("fifty_move_clock"));
+            }
+            let offset = clock - played;
+            for c in board.clock_history.iter_mut() {
+                *c += offset;
+            }
+            board.fifty_move_clock = clock;
+        }
+        board.agreed_draw = json.bool_field("agreed_draw")?;
+
+        Ok(board)
+    }
+}
+
+/// Board searching `max_depth` turns deep until dropped, see `Board::with_max_depth`
+struct DepthGuard<'a, const N: usize> {
+    board: &'a mut Board<N>,
+    max_depth: usize,
+}
+
+impl<const N: usize> Deref for DepthGuard<'_, N> {
+    type Target = Board<N>;
+
+    fn deref(&self) -> &Board<N> {
+        self.board
+    }
+}
+
+impl<const N: usize> DerefMut for DepthGuard<'_, N> {
+    fn deref_mut(&mut self) -> &mut Board<N> {
+        self.board
+    }
+}
+
+// also when the search panics, so the board never keeps the temporary depth
+impl<const N: usize> Drop for DepthGuard<'_, N> {
+    fn drop(&mut self) {
+        self.board.rating.max_depth = self.max_depth;
+    }
+}
+
+impl<const N: usize> Board<N> {
+    /// The board with `max_depth` set to `depth` until the guard is dropped
+    fn with_max_depth(&mut self, depth: usize) -> DepthGuard<'_, N> {
+        let max_depth = std::mem::replace(&mut self.rating.max_depth, depth);
+        DepthGuard {
+            board: self,
+            max_depth,
+        }
+    }
+}
+
+impl Board<10> {
+    /// Starting position of international draughts
+    pub fn new_international(rates: RateConfig) -> Self {
+        let mut board = Self::new(4, rates);
+        board.variant = Variant::International;
+        board
+    }
+}
+
+impl Board {
+    /// Search score of the position from `player`'s point of view
+    pub fn rate(&mut self, player: Color) -> f32 {
+        self.rate_window(player, f32::NEG_INFINITY, f32::INFINITY)
+    }
+
+    /// `rate` with an alpha-beta window from `player`'s point of view, the result is exact
+    /// only inside of the window, otherwise it's just a bound
+    fn rate_window(&mut self, player: Color, alpha: f32, beta: f32) -> f32 {
+        // turns the search after a pass is shallower than the regular one
+        const NULL_MOVE_REDUCTION: usize = 2;
+
+        // negamax from the current player's point of view,
+        // `ply` counts every half-move from the root, to bound the extensions,
+        // `in_null_move` is set right after a pass, so that the opponent can't pass back
+        fn rate_inner(
+            board: &mut Board,
+            depth: usize,
+            ply: usize,
+            mut alpha: f32,
+            mut beta: f32,
+            in_null_move: bool,
+        ) -> f32 {
+            let RateConfig { win, max_depth, .. } = board.rating;
+            let player = board.current_player();
+            SEARCH_TABLES.with_borrow_mut(|tables| tables.nodes += 1);
+
+            if let Some(winner) = board.winner() {
+                return if winner == player { win } else { -win };
+            }
+
+            if board.is_draw() {
+                return 0.0;
+            }
+
+            if depth >= max_depth {
+                let plies = board.rating.quiescence_depth;
+                return quiescence(board, plies, alpha, beta);
+            }
+
+            let hash = board.search_hash();
+            let remaining = (max_depth - depth) as u8;
+            let original_alpha = alpha;
+            if let Some(entry) = SEARCH_TABLES
+                .with_borrow(|tables| tables.probe(hash))
+                .filter(|entry| entry.depth >= remaining)
+            {
+                match entry.flag {
+                    TTFlag::Exact => return entry.score,
+                    TTFlag::LowerBound => alpha = alpha.max(entry.score),
+                    TTFlag::UpperBound => beta = beta.min(entry.score),
+                }
+                if alpha >= beta {
+                    return entry.score;
+                }
+            }
+
+            let mut moves = if board.rating.use_lazy_moves {
+                MoveGenerator::new(board, ply)
+            } else {
+                MoveGenerator::eager(board, ply)
+            };
+
+            // if the position is still too good for the opponent after passing, a real move
+            // would be even better, unless it's a zugzwang, which is likely in endgames and
+            // when a capture is forced
+            let remaining_depth = max_depth - depth;
+            if board.rating.use_null_move
+                && !in_null_move
+                && remaining_depth > NULL_MOVE_REDUCTION
+                && beta.is_finite()
+                && !board.is_endgame()
+                && !moves.has_captures()
+            {
+                board.apply_null_move();
+                let depth = depth + 1 + NULL_MOVE_REDUCTION;
+                let rate = -rate_inner(board, depth, ply + 1, -beta, -alpha, true);
+                board.undo_null_move();
+                if rate >= beta {
+                    return rate;
+                }
+            }
+
+            let mut best: Option<(Move, f32)> = None;
+            while let Some(move_) = moves.next_move(board) {
+                let extension = board.search_extensions(move_, ply);
+                let rate = board.with_move_unchecked(move_, |board| {
+                    // continuation of the capture doesn't count as a turn
+                    if board.current_player() == player {
+                        rate_inner(board, depth, ply + 1, alpha, beta, false)
+                    } else {
+                        let depth = depth + 1 - extension;
+                        -rate_inner(board, depth, ply + 1, -beta, -alpha, false)
+                    }
+                });
+
+                assert!(!rate.is_nan(), "Nan");
+                if best.is_none_or(|(_, best)| rate > best) {
+                    best = Some((move_, rate));
+                }
+
+                alpha = alpha.max(rate);
+                if alpha >= beta {
+                    break;
+                }
+            }
+
+            let (move_, rate) = best.expect("No moves");
+            let flag = if rate <= original_alpha {
+                TTFlag::UpperBound
+            } else if rate >= beta {
+                TTFlag::LowerBound
+            } else {
+                TTFlag::Exact
+            };
+            let entry = TTEntry {
+                hash,
+                depth: remaining,
+                score: rate,
+                flag,
+                best_move: (flag != TTFlag::UpperBound).then_some(move_),
+            };
+            SEARCH_TABLES.with_borrow_mut(|tables| tables.record(ply, entry));
+            rate
+        }
+
+        // plays out the forced captures, so that the rating isn't taken in the middle of
+        // an exchange, at most `plies` of them
+        fn quiescence(board: &mut Board, plies: usize, mut alpha: f32, beta: f32) -> f32 {
+            let win = board.rating.win;
+            let player = board.current_player();
+
+            let captures = board.find_all_current_moves();
+            if plies == 0 || !Move::contains_killer_move(&captures) {
+                return board.rate_current_board();
+            }
+
+            let mut best = f32::NEG_INFINITY;
+            for move_ in captures {
+                SEARCH_TABLES.with_borrow_mut(|tables| tables.nodes += 1);
+                let rate = board.with_move_unchecked(move_, |board| {
+                    if let Some(winner) = board.winner() {
+                        if winner == player {
+                            win
+                        } else {
+                            -win
+                        }
+                    } else if board.is_draw() {
+                        0.0
+                    } else if board.current_player() == player {
+                        quiescence(board, plies - 1, alpha, beta)
+                    } else {
+                        -quiescence(board, plies - 1, -beta, -alpha)
+                    }
+                });
+
+                best = best.max(rate);
+                alpha = alpha.max(rate);
+                if alpha >= beta {
+                    break;
+                }
+            }
+
+            best
+        }
+
+        let owner = TableOwner {
+            rating: RateConfig {
+                max_depth: 0,
+                ..self.rating
+            },
+            evaluator: self.evaluator.name(),
+        };
+        SEARCH_TABLES.with_borrow_mut(|tables| tables.claim(owner));
+
+        if self.current_player() == player {
+            rate_inner(self, 0, 0, alpha, beta, false)
+        } else {
+            -rate_inner(self, 0, 0, -beta, -alpha, false)
+        }
+    }
+
+    /// Legal moves in the order the search should try them: the best move found for this
+    /// position before, captures by `static_exchange_evaluation`, then quiet moves, killer
+    /// moves of the search `ply` first and the rest by `order_quiet_moves`, and last
+    /// the captures losing material
+    fn find_all_current_moves_ordered(&mut self, ply: usize) -> Vec<Move> {
+        let moves = self.find_all_current_moves();
+        self.order_moves(moves, ply)
+    }
+
+    fn order_moves(&mut self, moves: Vec<Move>, ply: usize) -> Vec<Move> {
+        if !self.rating.use_move_ordering {
+            return moves;
+        }
+
+        let mut ordered = Vec::with_capacity(moves.len());
+        let take = |move_: Option<Move>, ordered: &mut Vec<Move>| {
+            if let Some(move_) = move_ {
+                if moves.contains(&move_) && !ordered.contains(&move_) {
+                    ordered.push(move_);
+                }
+            }
+        };
+
+        take(self.hash_move(), &mut ordered);
+
+        let (captures, quiet): (Vec<_>, Vec<_>) = moves
+            .iter()
+            .filter(|m| !ordered.contains(m))
+            .partition(|m| m.kill.is_some());
+        let (captures, losing_captures) = self.order_captures(captures);
+        ordered.extend(captures);
+
+        if self.rating.use_killer_moves {
+            for killer in self.killer_moves(ply) {
+                take(killer, &mut ordered);
+            }
+        }
+        let mut quiet: Vec<_> = quiet.into_iter().filter(|m| !ordered.contains(m)).collect();
+        self.order_quiet_moves(&mut quiet);
+
+        ordered.extend(quiet);
+        ordered.extend(losing_captures);
+        ordered
+    }
+
+    /// Best move the search found in this position before
+    fn hash_move(&self) -> Option<Move> {
+        let hash = self.search_hash();
+        SEARCH_TABLES.with_borrow(|tables| tables.probe(hash).and_then(|entry| entry.best_move))
+    }
+
+    /// Quiet moves that caused a cutoff in other positions at the same search ply
+    fn killer_moves(&self, ply: usize) -> [Option<Move>; 2] {
+        SEARCH_TABLES.with_borrow(|tables| tables.killers.get(ply))
+    }
+
+    /// Captures sorted by `static_exchange_evaluation`, split into the ones
+    /// not losing material and the ones losing it
+    fn order_captures(&mut self, captures: Vec<Move>) -> (Vec<Move>, Vec<Move>) {
+        let mut rated: Vec<_> = captures
+            .into_iter()
+            .map(|m| (self.static_exchange_evaluation(m), m))
+            .collect();
+        rated.sort_by_key(|&(see, _)| std::cmp::Reverse(OrderedFloat(see)));
+
+        let (winning, losing): (Vec<_>, Vec<_>) =
+            rated.into_iter().partition(|&(see, _)| see >= 0.0);
+        let moves = |rated: Vec<(f32, Move)>| rated.into_iter().map(|(_, m)| m).collect();
+        (moves(winning), moves(losing))
+    }
+
+    /// By the history heuristic, moves with the same history by the static rating after them
+    fn order_quiet_moves(&mut self, quiet: &mut [Move]) {
+        let use_history = self.rating.use_history_heuristic;
+        quiet.sort_by_cached_key(|&m| {
+            let history = if use_history {
+                SEARCH_TABLES.with_borrow(|tables| tables.history.score(m))
+            } else {
+                0
+            };
+            let rate = self.with_move_for_mover(m, |b| b.rate_current_board());
+            std::cmp::Reverse((history, OrderedFloat(rate)))
+        });
+    }
+
+    /// Extra depth for `move_` played `current_depth` half-moves from the root of the search
+    ///
+    /// Forced replies, promotions and queen recaptures get 1 more turn, until the search is
+    /// twice as deep as `max_depth`
+    fn search_extensions(&mut self, move_: Move, current_depth: usize) -> usize {
+        if !self.rating.use_extensions || current_depth >= 2 * self.rating.max_depth {
+            return 0;
+        }
+
+        let recapture = move_.kill.is_some_and(|k| k.piece == Piece::Queen)
+            && self.last_move().is_some_and(|m| m.kill.is_some());
+
+        (move_.is_upgrade()
+            || recapture
+            || self.with_move_unchecked(move_, |b| b.find_all_current_moves().len() == 1))
+            as usize
+    }
+
+    /// Static rating of the position from the current player's point of view, by the board's
+    /// `Evaluator`
+    pub fn rate_current_board(&self) -> f32 {
+        self.evaluator.evaluate(self)
+    }
+
+    /// `DefaultEvaluator` split into its terms, damped toward 0 in an endgame where the side
+    /// behind in material holds a fortress
+    pub fn evaluation_breakdown(&self) -> EvalBreakdown {
+        // the side ahead should still prefer lines without the fortress
+        const FORTRESS_DAMPING: f32 = 0.1;
+
+        let current_player = self.current_player();
+
+        fn rate_player(board: &Board, player: Color) -> SideEvaluation {
+            let RateConfig { pieces, kills, .. } = board.rating;
+            let position = board.position_rates();
+
+            let pos = board
+                .all_players_pieces(player)
+                .map(|(r, c, p)| position.rate(r, c, player, p))
+                .sum::<f32>();
+            let piece = board
+                .all_players_pieces(player)
+                .map(|(_, _, p)| pieces.rate(p))
+                .sum::<f32>();
+            // the captures are counted for `tension` as well, see `Board::capture_move_count`
+            let (kill, captures) = board
+                .all_players_pieces(player)
+                .flat_map(|(r, c, _)| board.find_moves(r, c, Some(true)).unwrap_or_default())
+                .fold((0.0, 0), |(kill, captures), m| {
+                    (kill + kills.rate(m.kill.unwrap().piece), captures + 1)
+                });
+            let territory = if position.territory != 0.0 {
+                board.count_squares_controlled_by_side(player) as f32 * position.territory
+            } else {
+                0.0
+            };
+            let promo_threat = if position.promo_threat != 0.0 {
+                board.count_reachable_promotion_squares(player) as f32 * position.promo_threat
+            } else {
+                0.0
+            };
+            let sign = match player {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let control = if position.control != 0.0 {
+                board
+                    .compute_control_matrix()
+                    .iter()
+                    .flatten()
+                    .filter(|&&c| c == sign)
+                    .count() as f32
+                    * position.control
+            } else {
+                0.0
+            };
+
+            let back_rank = if position.back_rank != 0.0 {
+                board.count_back_rank_pieces(player) as f32 * position.back_rank
+            } else {
+                0.0
+            };
+
+            let trapped = if position.trapped != 0.0 {
+                board.count_mobility_restricted_pieces(player) as f32 * position.trapped
+            } else {
+                0.0
+            };
+
+            let mobility = if position.mobility != 0.0 {
+                board.mobility_score(player) as f32 * position.mobility
+            } else {
+                0.0
+            };
+
+            let tension = captures as f32 * position.tension;
+
+            let position_score =
+                pos + territory + promo_threat + control + back_rank - trapped + mobility + tension;
+            SideEvaluation {
+                piece_score: piece,
+                position_score,
+                kill_score: kill,
+                total: piece + position_score + kill,
+            }
+        }
+        let patterns = self
+            .winning_endgame_patterns()
+            .iter()
+            .map(|p| p.eval_bonus)
+            .sum::<f32>();
+
+        let threats = if self.rating.use_threat_score {
+            self.compute_threat_score(current_player)
+                - self.compute_threat_score(current_player.other())
+        } else {
+            0.0
+        };
+
+        let (white, black) = (
+            rate_player(self, Color::White),
+            rate_player(self, Color::Black),
+        );
+        let (current, other) = match current_player {
+            Color::White => (white, black),
+            Color::Black => (black, white),
+        };
+        let breakdown = EvalBreakdown {
+            white,
+            black,
+            other_score: patterns + threats,
+            total: current.total - other.total + patterns + threats,
+        };
+
+        let holds_fortress = |player, own: SideEvaluation, opponent: SideEvaluation| {
+            own.piece_score < opponent.piece_score && self.detect_fortress_position(player)
+        };
+        if self.is_endgame()
+            && (holds_fortress(Color::White, white, black)
+                || holds_fortress(Color::Black, black, white))
+        {
+            breakdown.scaled(FORTRESS_DAMPING)
+        } else {
+            breakdown
+        }
+    }
+
+    /// Offensive potential of `player`: pieces that can be captured right now by their kind,
+    /// pawns that can promote in at most 2 moves as queen kills and opponent's pieces
+    /// attacked by 2 or more pieces by their kind, all weighted by `KillRates`
+    fn compute_threat_score(&self, player: Color) -> f32 {
+        let kills = self.rating.kills;
+
+        let mut attackers = [[0; 8]; 8];
+        let mut captures = 0.0;
+        for (r, c, _) in self.all_players_pieces(player) {
+            let mut killed: Vec<_> = self
+                .find_moves(r, c, Some(true))
+                .unwrap()
+                .into_iter()
+                .filter_map(|m| m.kill)
+                .collect();
+            // a queen can land on more squares after the same capture
+            killed.dedup();
+            captures += killed.iter().map(|k| kills.rate(k.piece)).sum::<f32>();
+
+            for kill in killed {
+                attackers[kill.row as usize][kill.col as usize] += 1;
+            }
+        }
+
+        let promotions = self
+            .all_players_pieces(player)
+            .filter(|&(r, c, p)| p == Piece::Pawn && self.promotes_within(r, c, player, 2))
+            .count() as f32
+            * kills.queen;
+
+        let double_attacks = self
+            .all_players_pieces(player.other())
+            .filter(|&(r, c, _)| attackers[r as usize][c as usize] >= 2)
+            .map(|(_, _, p)| kills.rate(p))
+            .sum::<f32>();
+
+        captures + promotions + double_attacks
+    }
+
+    /// Whether pawn of `player` on `row`, `col` can reach the last row in `moves` quiet moves
+    pub fn promotes_within(&self, row: u8, col: u8, player: Color, moves: u8) -> bool {
+        if Self::rows_to_promotion(row, player) == 0 {
+            return true;
+        }
+        if moves == 0 {
+            return false;
+        }
+
+        [-1, 1].iter().any(|dc| {
+            let (r, c) = (row as i8 + player.dir(), col as i8 + dc);
+            self.is_free(r, c) && self.promotes_within(r as u8, c as u8, player, moves - 1)
+        })
+    }
+
+    /// Material of `player` for counting purposes, a queen counts as 3 pawns
+    pub fn material_count(&self, player: Color) -> usize {
+        self.all_players_pieces(player)
+            .map(|(_, _, p)| match p {
+                Piece::Pawn | Piece::Custom(_) => 1,
+                Piece::Queen => 3,
+            })
+            .sum::<usize>()
+            .min(8)
+    }
+
+    /// Material balance of the current player looked up in `MATERIAL_TABLE`
+    pub fn material_counting_heuristic(&self) -> f32 {
+        let white = self.material_count(Color::White);
+        let black = self.material_count(Color::Black);
+        let balance = MATERIAL_TABLE.with(|table| table[white][black]);
+
+        match self.current_player() {
+            Color::White => balance,
+            Color::Black => -balance,
+        }
+    }
+
+    /// Whether `player`, while behind in material, holds a draw with 1 or 2 queens
+    /// sitting in the double corners, which the opponent can't attack in a single move
+    pub fn detect_fortress_position(&self, player: Color) -> bool {
+        const DOUBLE_CORNERS: [(u8, u8); 4] = [(0, 6), (1, 7), (6, 0), (7, 1)];
+
+        let opponent = player.other();
+        let queens: Vec<_> = self
+            .all_players_pieces(player)
+            .filter(|&(_, _, p)| p == Piece::Queen)
+            .collect();
+
+        if !(1..=2).contains(&queens.len())
+            || !queens
+                .iter()
+                .all(|&(r, c, _)| DOUBLE_CORNERS.contains(&(r, c)))
+            || self.total_piece_count(player) >= self.total_piece_count(opponent)
+        {
+            return false;
+        }
+
+        let can_capture = |board: &Board| {
+            board
+                .all_players_pieces(opponent)
+                .any(|(r, c, _)| !board.find_moves(r, c, Some(true)).unwrap().is_empty())
+        };
+
+        let mut board = self.clone();
+        if board.current_player() != opponent {
+            board.apply_null_move();
+        }
+
+        !can_capture(&board)
+            && board
+                .find_all_current_moves()
+                .into_iter()
+                .all(|m| !board.with_move_unchecked(m, |b| can_capture(b)))
+    }
+
+    /// Known winning patterns the current player has on the board
+    pub fn winning_endgame_patterns(&self) -> Vec<Pattern> {
+        WINNING_PATTERNS
+            .iter()
+            .filter(|p| (p.condition)(self))
+            .copied()
+            .collect()
+    }
+
+    fn count_pieces(&self, player: Color, piece: Piece) -> usize {
+        self.all_players_pieces(player)
+            .filter(|&(_, _, p)| p == piece)
+            .count()
+    }
+
+    /// Squares `player` can move to or capture on in one move, ignoring the forced capture rules
+    pub fn threat_map(&self, player: Color) -> [[bool; 8]; 8] {
+        let mut map = [[false; 8]; 8];
+        for (r, c, _) in self.all_players_pieces(player) {
+            for move_ in self.find_moves(r, c, None).unwrap() {
+                map[move_.to.0 as usize][move_.to.1 as usize] = true;
+                if let Some(kill) = move_.kill {
+                    map[kill.row as usize][kill.col as usize] = true;
+                }
+            }
+        }
+        map
+    }
+
+    /// Squares occupied by `player` together with the squares in their `threat_map`
+    pub fn count_squares_controlled_by_side(&self, player: Color) -> usize {
+        let mut map = self.threat_map(player);
+        for (r, c, _) in self.all_players_pieces(player) {
+            map[r as usize][c as usize] = true;
+        }
+        map.iter()
+            .flatten()
+            .filter(|&&controlled| controlled)
+            .count()
+    }
+
+    /// Whether a piece of `player` standing on the square could be captured right away
+    pub fn is_square_attacked(&mut self, row: u8, col: u8, player: Color) -> bool {
+        let probe = Some(PlayersPiece::new(player, Piece::Pawn));
+        self.with_piece(row, col, probe, |board| {
+            board.all_players_pieces(player.other()).any(|(r, c, _)| {
+                board
+                    .find_moves(r, c, Some(true))
+                    .unwrap()
+                    .iter()
+                    .filter_map(|m| m.kill)
+                    .any(|kill| (kill.row, kill.col) == (row, col))
+            })
+        })
+    }
+
+    /// Number of pawns of `player` that reach the last row in at most 3 quiet moves
+    /// without stepping on a square where they could be captured
+    pub fn count_reachable_promotion_squares(&self, player: Color) -> usize {
+        let pawns: Vec<_> = self
+            .all_players_pieces(player)
+            .filter(|&(r, _, p)| p == Piece::Pawn && Self::rows_to_promotion(r, player) <= 3)
+            .collect();
+
+        let mut board = self.position_copy();
+        pawns
+            .into_iter()
+            .filter(|&(r, c, _)| {
+                board.with_piece(r, c, None, |board| {
+                    let mut frontier = vec![(r, c)];
+                    for _ in 0..3 {
+                        let mut next = vec![];
+                        for (r, c) in frontier {
+                            for dc in [-1, 1] {
+                                let (row, col) = (r as i8 + player.dir(), c as i8 + dc);
+                                if board.is_free(row, col)
+                                    && !board.is_square_attacked(row as u8, col as u8, player)
+                                    && !next.contains(&(row as u8, col as u8))
+                                {
+                                    next.push((row as u8, col as u8));
+                                }
+                            }
+                        }
+
+                        if next
+                            .iter()
+                            .any(|&(r, _)| Self::rows_to_promotion(r, player) == 0)
+                        {
+                            return true;
+                        }
+                        frontier = next;
+                    }
+
+                    false
+                })
+            })
+            .count()
+    }
+
+    /// `1` for squares only White can reach in one move, `-1` for squares only Black can reach
+    /// and `0` for contested, unreachable and light squares
+    pub fn compute_control_matrix(&self) -> [[i8; 8]; 8] {
+        let white = self.threat_map(Color::White);
+        let black = self.threat_map(Color::Black);
+
+        let mut matrix = [[0; 8]; 8];
+        for (r, row) in matrix.iter_mut().enumerate() {
+            for (c, control) in row.iter_mut().enumerate() {
+                *control = match (white[r][c], black[r][c]) {
+                    (true, false) => 1,
+                    (false, true) => -1,
+                    _ => 0,
+                };
+            }
+        }
+        matrix
+    }
+
+    /// Sum of `compute_control_matrix`, positive when White controls more squares
+    pub fn control_advantage(&self) -> i32 {
+        self.compute_control_matrix()
+            .iter()
+            .flatten()
+            .map(|&c| c as i32)
+            .sum()
+    }
+
+    pub fn territory_advantage(&self) -> i32 {
+        let player = self.current_player();
+        self.count_squares_controlled_by_side(player) as i32
+            - self.count_squares_controlled_by_side(player.other()) as i32
+    }
+
+    pub fn back_rank(player: Color) -> u8 {
+        match player {
+            Color::White => 0,
+            Color::Black => 7,
+        }
+    }
+
+    /// Pieces of `player` on the dark squares of their own back rank
+    pub fn count_back_rank_pieces(&self, player: Color) -> u8 {
+        let back_rank = Board::back_rank(player);
+        (0..8)
+            .filter(|c| (back_rank + c).is_multiple_of(2))
+            .filter(|&c| self.occupied_by(back_rank, c) == Some(player))
+            .count() as u8
+    }
+
+    pub fn has_full_back_rank(&self, player: Color) -> bool {
+        self.count_back_rank_pieces(player) == 4
+    }
+
+    /// Positions of the pieces of `player` that can't move at all,
+    /// regardless of whether a capture is forced elsewhere
+    pub fn fully_trapped_pieces(&self, player: Color) -> Vec<(u8, u8)> {
+        self.all_players_pieces(player)
+            .filter(|&(r, c, _)| self.find_moves(r, c, None).unwrap().is_empty())
+            .map(|(r, c, _)| (r, c))
+            .collect()
+    }
+
+    /// Legal moves `player` would have if they were on the move
+    pub fn mobility_score(&self, player: Color) -> usize {
+        self.find_all_moves(player).len()
+    }
+
+    pub fn count_mobility_restricted_pieces(&self, player: Color) -> usize {
+        self.fully_trapped_pieces(player).len()
+    }
+
+    /// Raw counts of the weakness indicators, in the same order as their `PositionRates` weights
+    pub fn weakness_counts(&self, player: Color) -> [(&'static str, usize, f32); 5] {
+        let rates = self.position_rates();
+
+        let isolated = self
+            .all_players_pieces(player)
+            .filter(|&(r, c, p)| {
+                p == Piece::Pawn
+                    && [(-1, -1), (-1, 1), (1, -1), (1, 1)].iter().all(|(dr, dc)| {
+                        let (row, col) = (r as i8 + dr, c as i8 + dc);
+                        !self.in_bounds(row, col)
+                            || self.occupied_by(row as u8, col as u8) != Some(player)
+                    })
+            })
+            .count();
+
+        let back_rank_holes = 4 - self.count_back_rank_pieces(player) as usize;
+
+        let mut columns = [false; 8];
+        for (_, c, _) in self.all_players_pieces(player) {
+            columns[c as usize] = true;
+        }
+        let occupied: Vec<_> = (0..8).filter(|&c| columns[c]).collect();
+        let column_gaps = occupied
+            .windows(2)
+            .map(|w| w[1] - w[0] - 1)
+            .filter(|&gap| gap >= 2)
+            .sum();
+
+        let low_material = 4usize.saturating_sub(self.total_piece_count(player));
+
+        let queens = |color| {
+            self.all_players_pieces(color)
+                .filter(|&(_, _, p)| p == Piece::Queen)
+                .count()
+        };
+        let queen_deficit = queens(player.other()).saturating_sub(queens(player));
+
+        [
+            ("isolated pawns", isolated, rates.isolated),
+            ("back rank holes", back_rank_holes, rates.back_rank_hole),
+            ("column gaps", column_gaps, rates.column_gap),
+            ("low material", low_material, rates.low_material),
+            ("queen deficit", queen_deficit, rates.queen_deficit),
+        ]
+    }
+
+    /// Aggregated weakness of `player`, 0 for no weaknesses, more negative the weaker
+    pub fn strategic_weakness_score(&self, player: Color) -> f32 {
+        self.weakness_counts(player)
+            .iter()
+            .map(|&(_, count, weight)| -(count as f32) * weight)
+            .sum()
+    }
+
+    pub fn strategic_report(&self, player: Color) -> String {
+        let mut report = format!("Strategic weaknesses of {}\n", player);
+        for (name, count, weight) in self.weakness_counts(player) {
+            report += &format!(
+                "{:>16}: {} × {} = {}\n",
+                name,
+                count,
+                weight,
+                -(count as f32) * weight
+            );
+        }
+        report += &format!(
+            "{:>16}: {}\n",
+            "total",
+            self.strategic_weakness_score(player)
+        );
+        report
+    }
+
+    /// Whether `player` gets a queen within `turns` of their own turns, whatever the opponent plays
+    ///
+    /// Only pawns at most 3 rows from promotion are considered for `player`
+    pub fn forces_promotion(&mut self, player: Color, turns: usize) -> bool {
+        if let Some(winner) = self.winner() {
+            return winner == player;
+        }
+
+        let moves = self.find_all_current_moves();
+
+        if self.current_player() != player {
+            return moves.into_iter().all(|m| {
+                self.with_move_unchecked(m, |board| board.forces_promotion(player, turns))
+            });
+        }
+
+        if turns == 0 {
+            return false;
+        }
+
+        moves
+            .into_iter()
+            .any(|m| self.breaks_through_with(m, player, turns))
+    }
+
+    pub fn breaks_through_with(&mut self, move_: Move, player: Color, turns: usize) -> bool {
+        if move_.piece != Piece::Pawn || Self::rows_to_promotion(move_.from.0, player) > 3 {
+            return false;
+        }
+
+        move_.is_upgrade()
+            || self.with_move_unchecked(move_, |board| {
+                let turns = if board.current_player() == player {
+                    turns
+                } else {
+                    turns - 1
+                };
+                board.forces_promotion(player, turns)
+            })
+    }
+
+    /// Finds a pawn that promotes in at most 3 turns even against the best defence
+    ///
+    /// Returns the first move of the breakthrough and the number of turns it takes
+    pub fn evaluate_pawn_breakthrough(&mut self) -> Option<(Move, usize)> {
+        let player = self.current_player();
+        let moves = self.find_all_current_moves();
+
+        (1..=3).find_map(|turns| {
+            moves
+                .iter()
+                .copied()
+                .find(|&m| self.breaks_through_with(m, player, turns))
+                .map(|m| (m, turns))
+        })
+    }
+
+    /// Material won by `capture`, minus the capturing piece when the opponent can take it right back
+    pub fn capture_gain(&mut self, capture: Move) -> f32 {
+        let pieces = self.rating.pieces;
+        let gained = capture.kill.map_or(0.0, |kill| pieces.rate(kill.piece));
+
+        let lost = self.with_move_unchecked(capture, |board| {
+            let recaptured = board.current_player() != capture.color
+                && board
+                    .find_all_current_moves()
+                    .iter()
+                    .filter_map(|m| m.kill)
+                    .any(|kill| (kill.row, kill.col) == capture.to);
+
+            if recaptured {
+                pieces.rate(capture.future_piece())
+            } else {
+                0.0
+            }
+        });
+
+        gained - lost
+    }
+
+    /// Material won by the exchange `capture` starts, the side to move recaptures the piece
+    /// that just captured with its least valuable attacker, or stops when that loses
+    /// material, continuations of a capture chain aren't followed
+    pub fn static_exchange_evaluation(&mut self, capture: Move) -> f32 {
+        let pieces = self.rating.pieces;
+        // `gains[i]` is the material of the side making the i-th capture if the exchange
+        // stopped right after it
+        let mut gains = vec![capture.kill.map_or(0.0, |kill| pieces.rate(kill.piece))];
+
+        let moves_before = self.moves.len();
+        let mut last = capture;
+        self.push_unsafe(capture);
+        while self.current_player() != last.color {
+            let attackers: Vec<_> = self.all_players_pieces(self.current_player()).collect();
+            let recapture = attackers
+                .into_iter()
+                .flat_map(|(r, c, _)| self.find_moves(r, c, Some(true)).unwrap())
+                .filter(|m| m.kill.is_some_and(|kill| (kill.row, kill.col) == last.to))
+                .min_by_key(|m| OrderedFloat(pieces.rate(m.piece)));
+            let Some(recapture) = recapture else {
+                break;
+            };
+
+            gains.push(pieces.rate(last.future_piece()) - gains[gains.len() - 1]);
+            self.push_unsafe(recapture);
+            last = recapture;
+        }
+        while self.moves.len() > moves_before {
+            self.pop();
+        }
+
+        // every side but the first, whose capture is forced, may stop the exchange
+        for i in (1..gains.len()).rev() {
+            gains[i - 1] = -(-gains[i - 1]).max(gains[i]);
+        }
+        gains[0]
+    }
+
+    /// Capture with the highest immediate material gain, without running the full search
+    pub fn find_best_capture(&mut self) -> Option<Move> {
+        let captures = Move::filter_killer_moves(self.find_all_current_moves());
+        captures
+            .into_iter()
+            .max_by_key(|m| OrderedFloat(self.capture_gain(*m)))
+    }
+
+    /// Pieces of `player` the other player could capture, if it was their turn
+    pub fn threats_to(&self, player: Color) -> Vec<PosUncolorPiece> {
+        let mut threats: Vec<_> = self
+            .all_players_pieces(player.other())
+            .flat_map(|(r, c, _)| self.find_moves(r, c, Some(true)).unwrap())
+            .filter_map(|m| m.kill)
+            .collect();
+        threats.sort_by_key(|k| (k.row, k.col));
+        threats.dedup();
+        threats
+    }
+
+    /// Squares of the pieces `color` could capture, if it was its turn, see `threats_to`
+    pub fn threats(&self, color: Color) -> Vec<(u8, u8)> {
+        self.threats_to(color.other())
+            .iter()
+            .map(|kill| (kill.row, kill.col))
+            .collect()
+    }
+
+    /// The piece on the square could be captured by the opponent, if it was their turn
+    pub fn is_threatened(&self, row: u8, col: u8) -> bool {
+        self.piece_at(row, col)
+            .is_some_and(|piece| self.threats(piece.color.other()).contains(&(row, col)))
+    }
+
+    pub fn most_valuable_threat(&self, player: Color) -> f32 {
+        self.threats_to(player)
+            .iter()
+            .map(|k| self.rating.pieces.rate(k.piece))
+            .fold(0.0, f32::max)
+    }
+
+    /// Move that leaves the most valuable piece out of reach, by moving it, blocking
+    /// the attacker or capturing, `None` if nothing is threatened
+    pub fn find_defensive_move(&mut self) -> Option<Move> {
+        let player = self.current_player();
+        if self.threats_to(player).is_empty() {
+            return None;
+        }
+
+        let moves = self.find_all_current_moves();
+        moves.into_iter().max_by_key(|&m| {
+            let gain = if m.kill.is_some() {
+                self.capture_gain(m)
+            } else {
+                0.0
+            };
+            let left = self.with_move_unchecked(m, |b| b.most_valuable_threat(player));
+            OrderedFloat(gain - left)
+        })
+    }
+
+    pub fn find_best_move(&mut self) -> Move {
+        self.find_best_move_with(())
+    }
+
+    /// Move of `book` for this position, if it's legal here
+    fn book_move(&self, book: &OpeningBook) -> Option<Move> {
+        let move_ = book.get(self.search_hash())?;
+        self.find_all_current_moves()
+            .contains(&move_)
+            .then_some(move_)
+    }
+
+    /// `book_move`, or `find_best_move` for positions the book doesn't know
+    pub fn find_best_move_with_book(&mut self, book: &OpeningBook) -> Move {
+        self.book_move(book)
+            .unwrap_or_else(|| self.find_best_move())
+    }
+
+    /// `find_best_move` searching `depth` turns deep instead of `max_depth`,
+    /// the configuration is left as it was
+    pub fn find_best_move_with_depth(&mut self, depth: usize) -> Move {
+        assert!(depth > 0, "Search depth has to be at least 1");
+        self.with_max_depth(depth).find_best_move()
+    }
+
+    /// `find_best_move` that keeps search results in `table`
+    pub fn find_best_move_with(&mut self, table: impl TableSource) -> Move {
+        // killers and history of the last search are from other positions, iterative
+        // deepening keeps them between the depths
+        SEARCH_TABLES.with_borrow_mut(|tables| {
+            tables.killers.clear();
+            tables.history.clear();
+        });
+
+        if self.rating.prefer_defensive {
+            if let Some(move_) = self.find_defensive_move() {
+                return move_;
+            }
+        }
+
+        table.with_table(|| self.iterative_deepening(None, |_, _, _| {}))
+    }
+
+    /// `find_best_move` with the diagnostics of the search, always searches, even when
+    /// `prefer_defensive` would rescue a piece without it
+    pub fn find_best_move_with_stats(&mut self) -> (Move, SearchStats) {
+        let start = Instant::now();
+        let nodes_before = SEARCH_TABLES.with_borrow(|tables| tables.nodes);
+
+        let (mut depth_reached, mut score) = (0, 0.0);
+        let move_ = self.iterative_deepening(None, |depth, _, best| {
+            depth_reached = depth;
+            score = best;
+        });
+        let elapsed = start.elapsed();
+        let nodes_visited = SEARCH_TABLES.with_borrow(|tables| tables.nodes) - nodes_before;
+
+        let stats = SearchStats {
+            nodes_visited,
+            depth_reached,
+            score,
+            elapsed,
+            pv: self.principal_variation_from(move_),
+        };
+        (move_, stats)
+    }
+
+    /// Best move of the deepest search finished within `budget`, at least 1 turn deep
+    /// and at most `max_depth` turns deep
+    pub fn find_best_move_timed(&mut self, budget: Duration) -> Move {
+        self.iterative_deepening(Some(budget), |_, _, _| {})
+    }
+
+    /// Searches 1 turn deeper at a time up to `max_depth`, until `budget` runs out,
+    /// calling `on_depth` with the depth, best move and its score after every iteration
+    ///
+    /// Every iteration starts with the best move of the previous one, the rest of its line
+    /// is tried first thanks to the best moves remembered in `SearchTables`
+    fn iterative_deepening(
+        &mut self,
+        budget: Option<Duration>,
+        mut on_depth: impl FnMut(usize, Move, f32),
+    ) -> Move {
+        let start = Instant::now();
+        let mut best = None;
+
+        for depth in 1..=self.rating.max_depth {
+            let (move_, score) = self.with_max_depth(depth).find_best_move_scored_from(best);
+            on_depth(depth, move_, score);
+            best = Some(move_);
+
+            if budget.is_some_and(|budget| start.elapsed() >= budget) {
+                break;
+            }
+        }
+
+        best.expect("`max_depth` must be > 0")
+    }
+
+    /// `find_best_move_scored` with every root move searched on its own thread, on a clone
+    /// of the board, all threads with one `SharedTable`
+    ///
+    /// Root moves don't share the alpha bound, so ties may be broken differently
+    pub fn find_best_move_parallel(&self) -> Move {
+        let moves = self.find_all_current_moves();
+        let table = Arc::new(SharedTable::default());
+        let scores: Vec<_> = thread::scope(|scope| {
+            let searches: Vec<_> = moves
+                .iter()
+                .map(|&move_| {
+                    let (mut board, table) = (self.clone(), &table);
+                    scope.spawn(move || {
+                        table.with_table(|| board.rate_root_move_window(move_, f32::NEG_INFINITY))
+                    })
+                })
+                .collect();
+            searches
+                .into_iter()
+                .map(|search| search.join().expect("Search thread panicked"))
+                .collect()
+        });
+
+        let mut best: Option<(Move, f32)> = None;
+        for (move_, score) in moves.into_iter().zip(scores) {
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((move_, score));
+            }
+        }
+        best.expect("No moves").0
+    }
+
+    pub fn find_best_move_scored(&mut self) -> (Move, f32) {
+        self.find_best_move_scored_from(None)
+    }
+
+    /// `find_best_move_scored` that searches `first` before the other moves
+    fn find_best_move_scored_from(&mut self, first: Option<Move>) -> (Move, f32) {
+        let mut moves = self.find_all_current_moves();
+        if let Some(i) = moves.iter().position(|&m| Some(m) == first) {
+            moves[..=i].rotate_right(1);
+        }
+
+        let mut best: Option<(Move, f32)> = None;
+        for move_ in moves {
+            let alpha = best.map_or(f32::NEG_INFINITY, |(_, score)| score);
+            let score = self.rate_root_move_window(move_, alpha);
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((move_, score));
+            }
+        }
+        best.expect("No moves")
+    }
+
+    /// Score `find_best_move` gives to playing `move_` in the current position
+    pub fn rate_root_move(&mut self, move_: Move) -> f32 {
+        self.rate_root_move_window(move_, f32::NEG_INFINITY)
+    }
+
+    /// `rate_root_move` that is only exact when it's above `alpha`
+    fn rate_root_move_window(&mut self, move_: Move, alpha: f32) -> f32 {
+        let mut score =
+            self.with_move_unchecked(move_, |b| b.rate_window(move_.color, alpha, f32::INFINITY));
+        if self.rating.avoid_repetition
+            && score >= self.get_winning_threshold_score()
+            && self.move_causes_repetition(move_)
+        {
+            score -= self.rating.win / 2.0;
+        }
+        score
+    }
+
+    /// Whether the current player should accept a draw, i.e. isn't doing better than
+    /// `RateConfig::draw_acceptance_threshold`
+    pub fn draw_offer_should_accept(&mut self) -> bool {
+        self.search_score() <= self.rating.draw_acceptance_threshold
+    }
+
+    /// Whether the current player is behind, but not clearly lost yet
+    pub fn should_offer_draw(&mut self) -> bool {
+        let score = self.search_score();
+        score < self.rating.draw_acceptance_threshold && score > -self.get_winning_threshold_score()
+    }
+
+    /// Picks a root move with probability proportional to `e^((score - best) / temperature)`,
+    /// so 0 always plays the best move and the higher the temperature, the more random it gets
+    pub fn choose_move_with_temperature(&mut self, temperature: f32, rng: &mut impl Rng) -> Move {
+        if temperature <= 0.0 {
+            return self.find_best_move();
+        }
+
+        let moves = self.find_all_current_moves();
+        let scores: Vec<_> = moves.iter().map(|&m| self.rate_root_move(m)).collect();
+        let best = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let weights: Vec<_> = scores
+            .iter()
+            .map(|score| ((score - best) / temperature).exp())
+            .collect();
+
+        let mut pick = rng.gen_range(0.0..weights.iter().sum::<f32>());
+        for (move_, weight) in moves.iter().zip(&weights) {
+            if pick < *weight {
+                return *move_;
+            }
+            pick -= weight;
+        }
+        *moves.last().unwrap()
+    }
+
+    /// Temperature that makes the engine play at roughly `elo_target`
+    ///
+    /// Calibrated by hand against `estimated_elo`: from 1500 up the engine plays its best move,
+    /// at 1250 a move a pawn worse is still picked with weight `1/e` and below 1000 the engine
+    /// regularly plays random moves
+    pub fn elo_temperature(elo_target: f32) -> f32 {
+        ((1500.0 - elo_target) / 250.0).max(0.0).powi(2)
+    }
+
+    pub fn engine_strength_scaling(&mut self, elo_target: f32) -> Move {
+        let temperature = Board::elo_temperature(elo_target);
+        self.choose_move_with_temperature(temperature, &mut rand::thread_rng())
+    }
+
+    /// Elo of the engine with the current config, from the score of a few games
+    /// against the same engine playing at 1000 Elo
+    ///
+    /// Both sides search at most `MAX_SEARCH_DEPTH` turns deep, so that the estimate takes
+    /// seconds and not minutes, deeper configs are rated as if they searched that deep
+    pub fn estimated_elo(&mut self) -> f32 {
+        const GAMES: usize = 10;
+        // unfinished games count as draws
+        const MAX_TURNS: usize = 100;
+        const MAX_SEARCH_DEPTH: usize = 3;
+        const BASELINE_ELO: f32 = 1000.0;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x656c6f);
+        let baseline = Board::elo_temperature(BASELINE_ELO);
+        let mut rates = self.rating;
+        rates.max_depth = rates.max_depth.min(MAX_SEARCH_DEPTH);
+        let mut points = 0.0;
+
+        for game in 0..GAMES {
+            let engine = if game % 2 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let mut board = Board::new(STARTING_LINES, rates);
+
+            while board.game_in_progress() && board.turn < MAX_TURNS {
+                let move_ = if board.current_player() == engine {
+                    board.find_best_move()
+                } else {
+                    board.choose_move_with_temperature(baseline, &mut rng)
+                };
+                board.push(move_).expect("Legal move");
+            }
+
+            points += match board.winner() {
+                Some(winner) if winner == engine => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+
+        // keep the logistic curve finite for a perfect score
+        let half_game = 0.5 / GAMES as f32;
+        let score = (points / GAMES as f32).clamp(half_game, 1.0 - half_game);
+        BASELINE_ELO + 400.0 * (score / (1.0 - score)).log10()
+    }
+
+    /// `iterative_deepening` without a time limit
+    pub fn find_best_move_with_callback<F: FnMut(usize, Move, f32)>(
+        &mut self,
+        on_depth: F,
+    ) -> Move {
+        self.iterative_deepening(None, on_depth)
+    }
+
+    /// Starts searching the position after the opponent plays `expected`, on the opponent's time
+    ///
+    /// The search goes deeper than `max_depth`, by up to `MAX_EXTRA_DEPTH` turns, until
+    /// `find_best_move_pondering` collects it
+    pub fn start_pondering(&mut self, expected: Move) {
+        const MAX_EXTRA_DEPTH: usize = 4;
+
+        self.ponder.cancel();
+        if !self.is_valid_move(expected) {
+            return;
+        }
+
+        let mut board = self.clone();
+        board.push(expected).expect("Legal move");
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new((Mutex::new(PonderProgress::default()), Condvar::new()));
+        let (cancelled, shared) = (cancel.clone(), progress.clone());
+
+        thread::spawn(move || {
+            let (progress, changed) = &*shared;
+            let max_depth = board.rating.max_depth;
+
+            'deepening: for depth in max_depth..=max_depth + MAX_EXTRA_DEPTH {
+                board.rating.max_depth = depth;
+                let mut iteration: Option<(Move, f32)> = None;
+
+                for move_ in board.find_all_current_moves() {
+                    if cancelled.load(Ordering::Relaxed) {
+                        break 'deepening;
+                    }
+
+                    let score = board.rate_root_move(move_);
+                    if iteration.is_none_or(|(_, best)| score > best) {
+                        iteration = Some((move_, score));
+                    }
+                }
+
+                let Some((move_, _)) = iteration else {
+                    break;
+                };
+                progress.lock().unwrap().best = Some((move_, depth));
+                changed.notify_all();
+            }
+
+            progress.lock().unwrap().finished = true;
+            changed.notify_all();
+        });
+
+        self.ponder.job = Some(PonderJob {
+            expected,
+            cancel,
+            progress,
+        });
+    }
+
+    /// Result of the pondering, once the opponent played their move,
+    /// `None` if they played anything else than the expected move
+    ///
+    /// Returns the deepest finished iteration straight away, or waits for the first one
+    pub fn find_best_move_pondering(&mut self, expected: Move) -> Option<Move> {
+        let job = self.ponder.job.take()?;
+        if job.expected != expected || self.last_move() != Some(expected) {
+            job.cancel.store(true, Ordering::Relaxed);
+            return None;
+        }
+
+        let (progress, changed) = &*job.progress;
+        let progress = changed
+            .wait_while(progress.lock().unwrap(), |p| {
+                p.best.is_none() && !p.finished
+            })
+            .unwrap();
+        job.cancel.store(true, Ordering::Relaxed);
+        progress.best.map(|(move_, _)| move_)
+    }
+
+    /// Depth of the deepest iteration the pondering finished so far
+    pub fn ponder_depth(&self) -> Option<usize> {
+        let job = self.ponder.job.as_ref()?;
+        let progress = *job.progress.0.lock().unwrap();
+        progress.best.map(|(_, depth)| depth)
+    }
+
+    /// Waits until the pondering finishes an iteration deeper than `depth`, at most `timeout`,
+    /// `None` if it doesn't, or if the board doesn't ponder
+    pub fn wait_for_ponder_depth(&self, depth: usize, timeout: Duration) -> Option<usize> {
+        let job = self.ponder.job.as_ref()?;
+        let (progress, changed) = &*job.progress;
+        let (progress, _) = changed
+            .wait_timeout_while(progress.lock().unwrap(), timeout, |p| {
+                !p.finished && p.best.is_none_or(|(_, reached)| reached <= depth)
+            })
+            .unwrap();
+        progress
+            .best
+            .map(|(_, reached)| reached)
+            .filter(|&reached| reached > depth)
+    }
+
+    /// `first` followed by the best replies, keeping the search horizon at `max_depth` turns
+    pub fn principal_line(&mut self, first: Move) -> Vec<Move> {
+        let mut line = vec![first];
+        self.push_unsafe(first);
+
+        let mut depth = self.rating.max_depth;
+        while depth > 1 && self.winner().is_none() {
+            if self.current_player() != self.last_player().unwrap() {
+                depth -= 1;
+            }
+
+            let move_ = self.with_max_depth(depth).find_best_move();
+            self.push_unsafe(move_);
+            line.push(move_);
+        }
+
+        for _ in 0..line.len() {
+            self.pop();
+        }
+        line
+    }
+
+    /// Best move followed by the best replies the search found, at most `max_depth` turns
+    ///
+    /// The replies are read from the transposition table, so they're as deep as it remembers
+    pub fn principal_variation(&mut self) -> Vec<Move> {
+        let first = self.find_best_move();
+        self.principal_variation_from(first)
+    }
+
+    /// `principal_variation` of a search that has just chosen `first`
+    fn principal_variation_from(&mut self, first: Move) -> Vec<Move> {
+        let mut line = vec![first];
+        self.push_unsafe(first);
+
+        let mut turns = 1;
+        while self.winner().is_none() {
+            let Some(move_) = self.hash_move().filter(|&m| self.is_valid_move(m)) else {
+                break;
+            };
+            if move_.color != self.last_player().unwrap() {
+                if turns == self.rating.max_depth {
+                    break;
+                }
+                turns += 1;
+            }
+
+            self.push_unsafe(move_);
+            line.push(move_);
+        }
+
+        for _ in 0..line.len() {
+            self.pop();
+        }
+        line
+    }
+
+    /// `principal_variation` in the notation of `print_algebraic_game_log`
+    pub fn display_principal_variation(&mut self) -> String {
+        Board::algebraic_turns(&self.principal_variation()).join(" ")
+    }
+
+    /// The `num_pvs` best lines with distinct first moves, best first
+    pub fn multi_pv_search(&mut self, num_pvs: usize) -> Vec<(Vec<Move>, f32)> {
+        let mut scored: Vec<_> = self
+            .find_all_current_moves()
+            .into_iter()
+            .map(|m| (m, self.rate_root_move(m)))
+            .collect();
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(OrderedFloat(score)));
+
+        scored
+            .into_iter()
+            .take(num_pvs)
+            .map(|(m, score)| (self.principal_line(m), score))
+            .collect()
+    }
+
+    /// Score above which the current player is considered winning
+    ///
+    /// With many pieces on the board even a small advantage counts, while in the endgame
+    /// only a large advantage is decisive
+    pub fn get_winning_threshold_score(&self) -> f32 {
+        let pieces = self.total_piece_count(Color::White) + self.total_piece_count(Color::Black);
+        self.rating.win / pieces.max(1) as f32
+    }
+
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// `zobrist_hash` that also tells apart a capture in progress, when only the capturing
+    /// piece can move, from a fresh turn in the same position
+    fn search_hash(&self) -> u64 {
+        match self.last_move() {
+            Some(move_) if move_.color == self.current_player() => {
+                self.hash ^ zobrist_keys().capturing(move_.to)
+            }
+            _ => self.hash,
+        }
+    }
+
+    /// Hashes of all positions of the game, from the starting one to the current one
+    pub fn position_hashes(&self) -> Vec<u64> {
+        let mut hashes = self.hash_history.clone();
+        hashes.push(self.hash);
+        hashes
+    }
+
+    /// Whether playing `move_` reaches a position for the third time
+    pub fn move_causes_repetition(&mut self, move_: Move) -> bool {
+        self.with_move_unchecked(move_, |board| board.is_draw_by_repetition())
+    }
+
+    /// Minimax score of the current position, or the win rate if the game is already over
+    pub fn search_score(&mut self) -> f32 {
+        match self.winner() {
+            Some(winner) if winner == self.current_player() => self.rating.win,
+            Some(_) => -self.rating.win,
+            None => self.find_best_move_scored().1,
+        }
+    }
+
+    /// Whether some line of at most `plies` quiet moves leads to a capture
+    pub fn capture_within(&mut self, plies: usize) -> bool {
+        if self.winner().is_some() {
+            return false;
+        }
+
+        let moves = self.find_all_current_moves();
+        if Move::contains_killer_move(&moves) {
+            return true;
+        }
+
+        plies > 0
+            && moves
+                .into_iter()
+                .any(|m| self.with_move_unchecked(m, |b| b.capture_within(plies - 1)))
+    }
+
+    /// Least number of quiet plies before a capture can be played, searching up to `max_depth` plies
+    ///
+    /// `Some(0)` means a capture is available right now, `None` that no capture was found
+    pub fn time_to_first_capture(&mut self) -> Option<usize> {
+        (0..=self.rating.max_depth).find(|&plies| self.capture_within(plies))
+    }
+
+    fn complexity_at(&self, depth: usize) -> f64 {
+        let branching = self.find_all_current_moves().len().max(1) as f64;
+        let material = (self.total_piece_count(Color::White) + self.total_piece_count(Color::Black))
+            as f64
+            / 24.0;
+
+        branching.powi(depth as i32) * material
+    }
+
+    /// Estimate of the work a search to `max_depth` needs,
+    /// `branching_factor^max_depth` scaled by the material left relative to a full board
+    pub fn compute_game_complexity(&mut self) -> f64 {
+        self.complexity_at(self.rating.max_depth)
+    }
+
+    /// Depth between 1 and 10 whose complexity is closest to `RateConfig::target_complexity`
+    pub fn adapt_depth(&mut self) -> usize {
+        let target = self.rating.target_complexity.max(f64::MIN_POSITIVE).ln();
+        (1..=10)
+            .min_by_key(|&depth| {
+                let complexity = self.complexity_at(depth).max(f64::MIN_POSITIVE).ln();
+                OrderedFloat((complexity - target).abs())
+            })
+            .unwrap()
+    }
+
+    pub fn search_profile(&mut self) -> SearchProfile {
+        let moves = self.find_all_current_moves();
+        SearchProfile {
+            branching_factor: moves.len(),
+            captures: Move::filter_killer_moves(moves).len(),
+            time_to_first_capture: self.time_to_first_capture(),
+        }
+    }
+
+    /// Lazily yields every game continuing from the current position, each ending either
+    /// with a winner or after `max_plies` half-moves
+    pub fn enumerate_all_games(&self, max_plies: usize) -> impl Iterator<Item = Vec<Move>> {
+        GameEnumerator {
+            root_len: self.moves.len(),
+            board: self.clone(),
+            max_plies,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Number of games `enumerate_all_games` would yield, without building them
+    pub fn count_all_games(&mut self, max_plies: usize) -> u64 {
+        if max_plies == 0 || self.winner().is_some() {
+            return 1;
+        }
+
+        self.find_all_current_moves()
+            .into_iter()
+            .map(|m| self.with_move_unchecked(m, |b| b.count_all_games(max_plies - 1)))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Half-moves to the end of the game under perfect play, looking at most `max_depth`
+    /// half-moves ahead: positive when the current player wins, negative when the opponent
+    /// does and 0 when the game is already over
+    pub fn endgame_distance(&mut self) -> Option<i32> {
+        if self.is_game_over() {
+            return Some(0);
+        }
+
+        (1..=self.rating.max_depth).find_map(|plies| match self.forced_outcome(plies) {
+            Some(true) => Some(plies as i32),
+            Some(false) => Some(-(plies as i32)),
+            None => None,
+        })
+    }
+
+    /// Whether the current player forces a win (`true`) or a loss (`false`) within `plies`
+    pub fn forced_outcome(&mut self, plies: usize) -> Option<bool> {
+        let player = self.current_player();
+        if let Some(winner) = self.winner() {
+            return Some(winner == player);
+        }
+        if plies == 0 || self.is_draw() {
+            return None;
+        }
+
+        let mut all_lose = true;
+        for move_ in self.find_all_current_moves() {
+            let outcome = self.with_move_unchecked(move_, |b| {
+                let outcome = b.forced_outcome(plies - 1);
+                if b.current_player() == player {
+                    outcome
+                } else {
+                    outcome.map(|win| !win)
+                }
+            });
+
+            match outcome {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => all_lose = false,
+            }
+        }
+
+        all_lose.then_some(false)
+    }
+
+    /// Whether every move makes the current player's position worse than its static rating
+    /// by more than `RateConfig::tempo_loss_threshold`, searching 1 half-move after each
+    ///
+    /// Unlike zugzwang, the moves don't have to lose, just to worsen the position
+    pub fn detect_tempo_loss(&mut self) -> bool {
+        fn best_reply(board: &mut Board) -> f32 {
+            if let Some(winner) = board.winner() {
+                let win = board.rating.win;
+                return if winner == board.current_player() {
+                    win
+                } else {
+                    -win
+                };
+            }
+
+            let replies = board.find_all_current_moves();
+            replies
+                .into_iter()
+                .map(|r| board.with_move_for_mover(r, |b| b.rate_current_board()))
+                .fold(f32::NEG_INFINITY, f32::max)
+        }
+
+        let threshold = self.rate_current_board() - self.rating.tempo_loss_threshold;
+        let moves = self.find_all_current_moves();
+        !moves.is_empty()
+            && moves
+                .into_iter()
+                .all(|m| self.with_move_for_mover(m, best_reply) < threshold)
+    }
+
+    /// Number of positions exactly `depth` half-moves away
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.winner().is_some() {
+            return 0;
+        }
+
+        self.find_all_current_moves()
+            .into_iter()
+            .map(|m| self.with_move_unchecked(m, |b| b.perft(depth - 1)))
+            .sum()
+    }
+
+    /// `perft` split by the current moves, for finding where move generation goes wrong
+    pub fn perft_divide(&mut self, depth: usize) -> Vec<(Move, u64)> {
+        if depth == 0 || self.winner().is_some() {
+            return vec![];
+        }
+
+        self.find_all_current_moves()
+            .into_iter()
+            .map(|m| (m, self.with_move_unchecked(m, |b| b.perft(depth - 1))))
+            .collect()
+    }
+
+    /// Label without the game outcome, which isn't known until the game is over
+    pub fn score_position_for_training(&mut self) -> TrainingLabel {
+        TrainingLabel::new(self.search_score(), None, self.rating.training_blend)
+    }
+
+    /// Labels every position of the played game with its score and the final outcome
+    pub fn annotate_game(&mut self) -> Vec<TrainingLabel> {
+        self.annotate_positions()
+            .into_iter()
+            .map(|(_, _, label)| label)
+            .collect()
+    }
+
+    /// Same as `annotate_game`, keeping the encoded position and the player on move with each label
+    pub fn annotate_positions(&mut self) -> Vec<([u8; 64], Color, TrainingLabel)> {
+        let winner = self.winner();
+        let moves = self.undo_history_to_ply(0);
+
+        let mut labels = Vec::with_capacity(moves.len());
+        for move_ in moves {
+            let player = self.current_player();
+            let outcome = match winner {
+                Some(winner) if winner == player => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+            let label = TrainingLabel::new(
+                self.search_score(),
+                Some(outcome),
+                self.rating.training_blend,
+            );
+            labels.push((self.encode_board(), player, label));
+            self.push_unsafe(move_);
+        }
+
+        labels
+    }
+
+    /// Post-game report of the played game, the board ends up in the same position
+    pub fn report_game_summary(&mut self) -> GameSummary {
+        const BLUNDER_DROP: f32 = 0.5;
+
+        let white = |player| if player == Color::White { 1.0 } else { -1.0 };
+
+        let final_score = white(self.current_player()) * self.search_score();
+        let mut evaluation: Vec<_> = self
+            .annotate_positions()
+            .into_iter()
+            .map(|(_, player, label)| white(player) * label.minimax_score)
+            .collect();
+        evaluation.push(final_score);
+
+        let moves = self.undo_history_to_ply(0);
+        let mut complexity = Vec::with_capacity(moves.len());
+        let mut players = Vec::with_capacity(moves.len());
+        for &move_ in &moves {
+            complexity.push(self.compute_game_complexity());
+            players.push(self.current_player());
+            self.push_unsafe(move_);
+        }
+
+        let blunders = moves
+            .iter()
+            .enumerate()
+            .filter(|&(i, m)| (evaluation[i] - evaluation[i + 1]) * white(m.color) > BLUNDER_DROP)
+            .map(|(i, &m)| (i, m))
+            .collect();
+
+        let average = |player| {
+            let scores: Vec<_> = players
+                .iter()
+                .zip(&evaluation)
+                .filter(|&(&p, _)| p == player)
+                .map(|(_, score)| white(player) * score)
+                .collect();
+            scores.iter().sum::<f32>() / scores.len().max(1) as f32
+        };
+        let average_evaluation = (average(Color::White), average(Color::Black));
+
+        GameSummary {
+            winner: self.winner(),
+            total_turns: self.turn,
+            moves: moves
+                .iter()
+                .map(|m| {
+                    let separator = if m.kill.is_some() { "x" } else { "-" };
+                    format!(
+                        "{}{}{}",
+                        square_number(m.from),
+                        separator,
+                        square_number(m.to)
+                    )
+                })
+                .collect(),
+            evaluation,
+            blunders,
+            average_evaluation,
+            complexity,
+        }
+    }
+
+    /// One byte per square, row by row: 0 empty, 1 white pawn, 2 white queen,
+    /// 3 black pawn and 4 black queen, custom pieces are stored as pawns
+    pub fn encode_board(&self) -> [u8; 64] {
+        let mut encoded = [0; 64];
+        for (i, square) in self.board.iter().flatten().enumerate() {
+            encoded[i] = match square {
+                None => 0,
+                Some(PlayersPiece { color, piece }) => {
+                    let piece = match piece {
+                        Piece::Pawn | Piece::Custom(_) => 1,
+                        Piece::Queen => 2,
+                    };
+                    match color {
+                        Color::White => piece,
+                        Color::Black => piece + 2,
+                    }
+                }
+            };
+        }
+        encoded
+    }
+
+    /// Run-length encoded `Move::to_bytes` of the history
+    ///
+    /// Every run is `u8` count, `u8` length and `length` moves repeated `count` times.
+    /// The same move can't be played twice in a row, so runs repeat cycles of up to 4 moves,
+    /// like queens going back and forth, and the moves in between are stored as runs of 1
+    pub fn history_compression(&self) -> Vec<u8> {
+        const MAX_CYCLE: usize = 4;
+
+        fn flush(data: &mut Vec<u8>, count: usize, moves: &[Move]) {
+            data.push(count as u8);
+            data.push(moves.len() as u8);
+            data.extend(moves.iter().flat_map(|m| m.to_bytes()));
+        }
+
+        let moves = &self.moves;
+        let mut data = Vec::new();
+        let mut literal_start = 0;
+        let mut i = 0;
+
+        while i < moves.len() {
+            let repeats = |len: usize| {
+                let cycle = &moves[i..i + len];
+                (1..u8::MAX as usize)
+                    .take_while(|k| moves[i + k * len..].starts_with(cycle))
+                    .count()
+                    + 1
+            };
+            let run = (1..=MAX_CYCLE.min(moves.len() - i))
+                .map(|len| (len, repeats(len)))
+                .filter(|&(_, count)| count > 1)
+                .max_by_key(|&(len, count)| (len * count, usize::MAX - len));
+
+            match run {
+                Some((len, count)) => {
+                    if literal_start < i {
+                        flush(&mut data, 1, &moves[literal_start..i]);
+                    }
+                    flush(&mut data, count, &moves[i..i + len]);
+                    i += len * count;
+                    literal_start = i;
+                }
+                None => {
+                    i += 1;
+                    if i - literal_start == u8::MAX as usize {
+                        flush(&mut data, 1, &moves[literal_start..i]);
+                        literal_start = i;
+                    }
+                }
+            }
+        }
+        if literal_start < moves.len() {
+            flush(&mut data, 1, &moves[literal_start..]);
+        }
+
+        data
+    }
+
+    pub fn history_decompression(data: &[u8]) -> Result<Vec<Move>, DecodeError> {
+        let mut moves = Vec::new();
+        let mut rest = data;
+
+        while let [count, len, tail @ ..] = rest {
+            let (count, len) = (*count as usize, *len as usize);
+            if count == 0 || len == 0 {
+                return Err(DecodeError::EmptyRun);
+            }
+            if tail.len() < len * 4 {
+                return Err(DecodeError::UnexpectedEnd);
+            }
+
+            let cycle = tail[..len * 4]
+                .chunks(4)
+                .map(|bytes| Move::from_bytes(bytes.try_into().unwrap()))
+                .collect::<Result<Vec<_>, _>>()?;
+            for _ in 0..count {
+                moves.extend_from_slice(&cycle);
+            }
+            rest = &tail[len * 4..];
+        }
+
+        if !rest.is_empty() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(moves)
+    }
+
+    /// Writes every position of the played game with its label for neural network training
+    ///
+    /// Little endian binary format, header is the magic `CKTG`, `u32` position count,
+    /// `u8` board size and the engine version as `u8` length and bytes. Each position is then
+    /// the 64 bytes of `encode_board`, `u8` player on move (0 white, 1 black),
+    /// `f32` minimax score and `i8` game outcome
+    pub fn export_training_game(&mut self, path: &Path) -> io::Result<()> {
+        let positions = self.annotate_positions();
+        let version = env!("CARGO_PKG_VERSION").as_bytes();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(TRAINING_MAGIC);
+        data.extend_from_slice(&(positions.len() as u32).to_le_bytes());
+        data.push(8);
+        data.push(version.len() as u8);
+        data.extend_from_slice(version);
+
+        for (board, player, label) in positions {
+            data.extend_from_slice(&board);
+            data.push(match player {
+                Color::White => 0,
+                Color::Black => 1,
+            });
+            data.extend_from_slice(&label.minimax_score.to_le_bytes());
+            data.push(label.game_outcome.unwrap_or(0.0) as i8 as u8);
+        }
+
+        fs::write(path, data)
+    }
+
+    /// Reads labels written by `export_training_game`, blending them with `rates`
+    pub fn import_training_game(path: &Path, rates: RateConfig) -> io::Result<Vec<TrainingLabel>> {
+        fn take<'a>(data: &mut &'a [u8], n: usize) -> io::Result<&'a [u8]> {
+            if data.len() < n {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Truncated training game",
+                ));
+            }
+            let (taken, rest) = data.split_at(n);
+            *data = rest;
+            Ok(taken)
+        }
+
+        let data = fs::read(path)?;
+        let mut data = data.as_slice();
+
+        if take(&mut data, 4)? != TRAINING_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a training game",
+            ));
+        }
+        let count = u32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+        if take(&mut data, 1)?[0] != 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unsupported board size",
+            ));
+        }
+        let version_len = take(&mut data, 1)?[0] as usize;
+        take(&mut data, version_len)?;
+
+        (0..count)
+            .map(|_| {
+                take(&mut data, 64 + 1)?;
+                let score = f32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+                let outcome = take(&mut data, 1)?[0] as i8 as f32;
+                Ok(TrainingLabel::new(
+                    score,
+                    Some(outcome),
+                    rates.training_blend,
+                ))
+            })
+            .collect()
+    }
+
+    /// Applies `move_` and calls `f` with the child board, flipping the sign of its result
+    /// when the turn passed to the opponent, so the result is from the mover's perspective
+    fn with_move_for_mover(&mut self, move_: Move, f: impl FnOnce(&mut Self) -> f32) -> f32 {
+        self.with_move_unchecked(move_, |board| {
+            let score = f(board);
+            if board.current_player() == move_.color {
+                score
+            } else {
+                -score
+            }
+        })
+    }
+
+    /// Legal moves sorted by the static rating after playing them, best first,
+    /// keeping at most `beam_width` of them
+    fn get_candidate_moves(&mut self, beam_width: usize) -> Vec<Move> {
+        let mut moves: Vec<_> = self
+            .find_all_current_moves()
+            .into_iter()
+            .map(|m| {
+                let score = self.with_move_for_mover(m, |b| b.rate_current_board());
+                (m, OrderedFloat(score))
+            })
+            .collect();
+
+        moves.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        moves.truncate(beam_width);
+        moves.into_iter().map(|(m, _)| m).collect()
+    }
+
+    /// Rating of the current player, searching only the `beam_width` best candidates at each ply
+    fn rate_beam(&mut self, beam_width: usize, depth: usize) -> f32 {
+        if let Some(winner) = self.winner() {
+            let win = self.rating.win;
+            return if winner == self.current_player() {
+                win
+            } else {
+                -win
+            };
+        }
+
+        if depth == 0 {
+            return self.rate_current_board();
+        }
+
+        self.get_candidate_moves(beam_width)
+            .into_iter()
+            .map(|m| {
+                self.with_move_for_mover(m, |b| {
+                    let continues = b.current_player() == m.color;
+                    b.rate_beam(beam_width, if continues { depth } else { depth - 1 })
+                })
+            })
+            .max_by_key(|&score| OrderedFloat(score))
+            .expect("No moves")
+    }
+
+    pub fn find_best_move_beam(&mut self, beam_width: usize) -> Move {
+        let depth = self.rating.max_depth.saturating_sub(1);
+        self.get_candidate_moves(beam_width)
+            .into_iter()
+            .max_by_key(|&m| {
+                OrderedFloat(self.with_move_for_mover(m, |b| b.rate_beam(beam_width, depth)))
+            })
+            .unwrap()
+    }
+}
+
+/// What the score of a `TTEntry` tells about the real score of the position
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TTFlag {
+    Exact,
+    /// The search failed high, the real score is at least this
+    LowerBound,
+    /// The search failed low, the real score is at most this
+    UpperBound,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TTEntry {
+    pub hash: u64,
+    /// Turns searched below the position
+    pub depth: u8,
+    /// From the point of view of the player on the move
+    pub score: f32,
+    pub flag: TTFlag,
+    pub best_move: Option<Move>,
+}
+
+/// Search results by `zobrist_hash` of the position, one entry per slot
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TTEntry>>,
+
+    /// Rating of the searches the entries come from, see `claim`
+    owner: Option<TableOwner>,
+}
+
+/// What the scores in a `TranspositionTable` depend on besides the position, the depth
+/// left out, since every entry records how deep it was searched
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct TableOwner {
+    rating: RateConfig,
+    evaluator: &'static str,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_SIZE_LOG2: u32 = 16;
+
+    /// Table with `2^size_log2` entries
+    pub fn new(size_log2: u32) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![None; 1 << size_log2],
+            owner: None,
+        }
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        hash as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<TTEntry> {
+        self.entries[self.slot(hash)].filter(|entry| entry.hash == hash)
+    }
+
+    /// Keeps the deeper result when both entries are for the same position
+    pub fn store(&mut self, entry: TTEntry) {
+        let slot = self.slot(entry.hash);
+        let old = &mut self.entries[slot];
+        if old.is_none_or(|old| old.hash != entry.hash || old.depth <= entry.depth) {
+            *old = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Clears the table if its entries were searched with another rating or evaluator,
+    /// their scores and bounds would be wrong for `owner`
+    fn claim(&mut self, owner: TableOwner) {
+        if self.owner != Some(owner) {
+            self.clear();
+            self.owner = Some(owner);
+        }
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(TranspositionTable::DEFAULT_SIZE_LOG2)
+    }
+}
+
+/// Transposition table for `Board::find_best_move_with`,
+/// `()` searches with the table of the thread
+pub trait TableSource {
+    fn with_table<R>(self, search: impl FnOnce() -> R) -> R;
+}
+
+impl TableSource for () {
+    fn with_table<R>(self, search: impl FnOnce() -> R) -> R {
+        search()
+    }
+}
+
+impl TableSource for &mut TranspositionTable {
+    fn with_table<R>(self, search: impl FnOnce() -> R) -> R {
+        SEARCH_TABLES.with_borrow_mut(|tables| std::mem::swap(&mut tables.transpositions, self));
+        let result = search();
+        SEARCH_TABLES.with_borrow_mut(|tables| std::mem::swap(&mut tables.transpositions, self));
+        result
+    }
+}
+
+/// Transposition table shared by the threads of a parallel search
+///
+/// Split into stripes by the hash, each a `TranspositionTable` behind its own `Mutex`, so
+/// that threads probing and storing different positions rarely wait for each other, and
+/// no thread sees an entry half-written
+#[derive(Debug)]
+pub struct SharedTable {
+    stripes: Vec<Mutex<TranspositionTable>>,
+}
+
+impl SharedTable {
+    pub const STRIPES_LOG2: u32 = 6;
+
+    /// Table with `2^size_log2` entries in all stripes, at least 1 per stripe
+    pub fn new(size_log2: u32) -> SharedTable {
+        let stripe_log2 = size_log2.saturating_sub(SharedTable::STRIPES_LOG2);
+        SharedTable {
+            stripes: (0..1 << SharedTable::STRIPES_LOG2)
+                .map(|_| Mutex::new(TranspositionTable::new(stripe_log2)))
+                .collect(),
+        }
+    }
+
+    /// The stripe by the top bits of `hash`, the slot in it is taken from the bottom bits
+    fn stripe(&self, hash: u64) -> std::sync::MutexGuard<'_, TranspositionTable> {
+        let index = (hash >> (u64::BITS - SharedTable::STRIPES_LOG2)) as usize;
+        self.stripes[index].lock().expect("Search thread panicked")
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<TTEntry> {
+        self.stripe(hash).probe(hash)
+    }
+
+    pub fn store(&self, entry: TTEntry) {
+        self.stripe(entry.hash).store(entry)
+    }
+
+    /// Number of positions stored
+    pub fn len(&self) -> usize {
+        self.stripes
+            .iter()
+            .map(|stripe| {
+                let stripe = stripe.lock().expect("Search thread panicked");
+                stripe
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.is_some())
+                    .count()
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn claim(&self, owner: TableOwner) {
+        for stripe in &self.stripes {
+            stripe.lock().expect("Search thread panicked").claim(owner);
+        }
+    }
+}
+
+impl Default for SharedTable {
+    fn default() -> Self {
+        SharedTable::new(TranspositionTable::DEFAULT_SIZE_LOG2)
+    }
+}
+
+/// Searches with a table shared with other threads, like the ones of a `ParallelEngine`
+impl TableSource for &Arc<SharedTable> {
+    fn with_table<R>(self, search: impl FnOnce() -> R) -> R {
+        SEARCH_TABLES.with_borrow_mut(|tables| tables.shared = Some(Arc::clone(self)));
+        let result = search();
+        SEARCH_TABLES.with_borrow_mut(|tables| tables.shared = None);
+        result
+    }
+}
+
+/// Lazy SMP search, helper threads search the same position 1 turn deeper or shallower
+/// than `max_depth`, all threads with one `SharedTable`, so that the primary search
+/// runs into positions the helpers have rated already
+///
+/// Entries keep the full hash of their position, so a thread never takes the result of
+/// another position stored in the same slot, and entries of another depth are only used
+/// as `TTEntry::depth` allows
+pub struct ParallelEngine {
+    board: Board,
+    table: Arc<SharedTable>,
+    threads: usize,
+}
+
+impl ParallelEngine {
+    pub fn new(board: Board, threads: usize) -> ParallelEngine {
+        assert!(threads > 0, "Engine needs at least 1 thread");
+        ParallelEngine {
+            board,
+            table: Arc::new(SharedTable::default()),
+            threads,
+        }
+    }
+
+    /// Best move of the deepest primary search finished within `duration`, see
+    /// `Board::find_best_move_timed`, waits for every helper to finish the depth it was
+    /// searching when `duration` ran out
+    pub fn find_best_move(&self, duration: Duration) -> Move {
+        thread::scope(|scope| {
+            for helper in 1..self.threads {
+                let mut board = self.board.clone();
+                let depth = board.rating.max_depth;
+                board.rating.max_depth = if helper % 2 == 1 {
+                    depth + 1
+                } else {
+                    depth.saturating_sub(1).max(1)
+                };
+                let table = &self.table;
+                scope.spawn(move || table.with_table(|| board.find_best_move_timed(duration)));
+            }
+
+            let mut board = self.board.clone();
+            (&self.table).with_table(|| board.find_best_move_timed(duration))
+        })
+    }
+}
+
+/// Moves to play in known positions instead of searching, by `Board::search_hash`
+///
+/// Little endian binary file, the magic `CKOB` and `u32` entry count, then every entry
+/// as the `u64` hash and the 4 bytes of `Move::to_bytes`, sorted by the hash
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    moves: HashMap<u64, Move>,
+}
+
+impl OpeningBook {
+    /// Half-moves of every game `build` adds to the book
+    pub const BUILD_MOVES: usize = 12;
+
+    /// Random half-moves `build` starts every game but the first with, so that the games differ
+    pub const RANDOM_MOVES: usize = 2;
+
+    pub fn get(&self, hash: u64) -> Option<Move> {
+        self.moves.get(&hash).copied()
+    }
+
+    pub fn insert(&mut self, board: &Board, move_: Move) {
+        self.moves.insert(board.search_hash(), move_);
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Book of `games` games the engine played against itself from `start`,
+    /// searching `depth` turns deep in every position not in the book yet
+    pub fn build(start: &Board, games: usize, depth: usize, rng: &mut impl Rng) -> OpeningBook {
+        let mut book = OpeningBook::default();
+        for game in 0..games {
+            let mut board = start.clone();
+            let random_moves = if game == 0 { 0 } else { Self::RANDOM_MOVES };
+            for _ in 0..random_moves {
+                let Some(move_) = board.random_move(rng) else {
+                    break;
+                };
+                board.push(move_).expect("Legal move");
+            }
+
+            for _ in 0..Self::BUILD_MOVES {
+                if !board.game_in_progress() {
+                    break;
+                }
+                let move_ = board.book_move(&book).unwrap_or_else(|| {
+                    let move_ = board.find_best_move_with_depth(depth);
+                    book.insert(&board, move_);
+                    move_
+                });
+                board.push(move_).expect("Legal move");
+            }
+        }
+        book
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut entries: Vec<_> = self.moves.iter().collect();
+        entries.sort_by_key(|&(&hash, _)| hash);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(BOOK_MAGIC);
+        data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (hash, move_) in entries {
+            data.extend_from_slice(&hash.to_le_bytes());
+            data.extend_from_slice(&move_.to_bytes());
+        }
+
+        fs::write(path, data)
+    }
+
+    pub fn load(path: &Path) -> io::Result<OpeningBook> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let data = fs::read(path)?;
+        let (header, entries) = data
+            .split_at_checked(8)
+            .ok_or(invalid("Not an opening book"))?;
+        if &header[..4] != BOOK_MAGIC {
+            return Err(invalid("Not an opening book"));
+        }
+        let count = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+        if entries.len() != count * 12 {
+            return Err(invalid("Truncated opening book"));
+        }
+
+        let moves = entries
+            .chunks(12)
+            .map(|entry| {
+                let hash = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let move_ = Move::from_bytes(entry[8..].try_into().unwrap())
+                    .map_err(|_| invalid("Malformed move in opening book"))?;
+                Ok((hash, move_))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(OpeningBook { moves })
+    }
+}
+
+/// Terms of `Board::rate_current_board` for one side
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SideEvaluation {
+    pub piece_score: f32,
+    /// Position of the pieces, territory, control and mobility
+    pub position_score: f32,
+    /// Captures available right now
+    pub kill_score: f32,
+    pub total: f32,
+}
+
+impl SideEvaluation {
+    pub fn scaled(self, factor: f32) -> Self {
+        Self {
+            piece_score: self.piece_score * factor,
+            position_score: self.position_score * factor,
+            kill_score: self.kill_score * factor,
+            total: self.total * factor,
+        }
+    }
+}
+
+/// `Board::rate_current_board` split into its terms, see `Board::evaluation_breakdown`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EvalBreakdown {
+    pub white: SideEvaluation,
+    pub black: SideEvaluation,
+    /// Endgame patterns and threats, from the current player's point of view
+    pub other_score: f32,
+    /// The rating, from the current player's point of view
+    pub total: f32,
+}
+
+impl EvalBreakdown {
+    pub fn scaled(self, factor: f32) -> Self {
+        Self {
+            white: self.white.scaled(factor),
+            black: self.black.scaled(factor),
+            other_score: self.other_score * factor,
+            total: self.total * factor,
+        }
+    }
+}
+
+/// Diagnostics of `Board::find_best_move_with_stats`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchStats {
+    pub nodes_visited: u64,
+    pub depth_reached: usize,
+    /// Score of the best move from the mover's point of view
+    pub score: f32,
+    pub elapsed: Duration,
+    /// Best move followed by the expected replies, see `Board::principal_variation`
+    pub pv: Vec<Move>,
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "depth {}, score {:.2}, {} nodes in {:.2?}",
+            self.depth_reached, self.score, self.nodes_visited, self.elapsed
+        )
+    }
+}
+
+/// Last 2 quiet moves that caused a beta cutoff, by the ply from the root of the search
+struct KillerTable {
+    moves: [[Option<Move>; 2]; KillerTable::MAX_DEPTH],
+}
+
+impl KillerTable {
+    /// Deeper plies, only reached by long capture sequences and extensions, have no killers
+    const MAX_DEPTH: usize = 64;
+
+    fn new() -> KillerTable {
+        KillerTable {
+            moves: [[None; 2]; KillerTable::MAX_DEPTH],
+        }
+    }
+
+    fn get(&self, ply: usize) -> [Option<Move>; 2] {
+        self.moves.get(ply).copied().unwrap_or_default()
+    }
+
+    fn store(&mut self, ply: usize, move_: Move) {
+        let Some(killers) = self.moves.get_mut(ply) else {
+            return;
+        };
+        if killers[0] != Some(move_) {
+            *killers = [Some(move_), killers[0]];
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = KillerTable::new();
+    }
+}
+
+/// Score of quiet moves by `[from][to]` square, raised by every cutoff they cause,
+/// the deeper the search was, the more
+struct HistoryTable {
+    scores: [[i32; 64]; 64],
+}
+
+impl HistoryTable {
+    fn new() -> HistoryTable {
+        HistoryTable {
+            scores: [[0; 64]; 64],
+        }
+    }
+
+    fn square(pos: (u8, u8)) -> usize {
+        pos.0 as usize * 8 + pos.1 as usize
+    }
+
+    fn score(&self, move_: Move) -> i32 {
+        self.scores[HistoryTable::square(move_.from)][HistoryTable::square(move_.to)]
+    }
+
+    fn reward(&mut self, move_: Move, depth: u8) {
+        let score =
+            &mut self.scores[HistoryTable::square(move_.from)][HistoryTable::square(move_.to)];
+        *score = score.saturating_add(depth as i32 * depth as i32);
+    }
+
+    fn clear(&mut self) {
+        *self = HistoryTable::new();
+    }
+}
+
+/// Move ordering heuristics collected by the search, shared by all boards of a thread
+struct SearchTables {
+    transpositions: TranspositionTable,
+
+    killers: KillerTable,
+
+    history: HistoryTable,
+
+    /// Positions visited by `Board::rate_window` so far
+    nodes: u64,
+
+    /// Moves generated for the positions visited so far
+    generated: u64,
+
+    /// Table of a `ParallelEngine`, searched instead of `transpositions` while it's set
+    shared: Option<Arc<SharedTable>>,
+}
+
+impl SearchTables {
+    fn new() -> SearchTables {
+        SearchTables {
+            transpositions: TranspositionTable::default(),
+            killers: KillerTable::new(),
+            history: HistoryTable::new(),
+            nodes: 0,
+            generated: 0,
+            shared: None,
+        }
+    }
+
+    /// Prepares the table for a search rated by `owner`, see `TranspositionTable::claim`
+    fn claim(&mut self, owner: TableOwner) {
+        match &self.shared {
+            Some(shared) => shared.claim(owner),
+            None => self.transpositions.claim(owner),
+        }
+    }
+
+    fn probe(&self, hash: u64) -> Option<TTEntry> {
+        match &self.shared {
+            Some(shared) => shared.probe(hash),
+            None => self.transpositions.probe(hash),
+        }
+    }
+
+    /// Remembers the search result of a position at search `ply`, if its best move is
+    /// a quiet one that caused a cutoff, it becomes a killer and gets a history bonus
+    fn record(&mut self, ply: usize, entry: TTEntry) {
+        match &self.shared {
+            Some(shared) => shared.store(entry),
+            None => self.transpositions.store(entry),
+        }
+
+        let Some(move_) = entry.best_move.filter(|m| m.kill.is_none()) else {
+            return;
+        };
+
+        if entry.flag == TTFlag::LowerBound {
+            self.killers.store(ply, move_);
+            self.history.reward(move_, entry.depth);
+        }
+    }
+}
+
+thread_local! {
+    /// Material balance for `[white][black]` material, capped at 8 per side
+    ///
+    /// Balance is relative to the material left, so trading down while ahead pays off
+    static MATERIAL_TABLE: [[f32; 9]; 9] = {
+        let mut table = [[0.0; 9]; 9];
+        for (white, row) in table.iter_mut().enumerate() {
+            for (black, value) in row.iter_mut().enumerate() {
+                if white + black > 0 {
+                    *value = (white as f32 - black as f32) / (white + black) as f32 * 8.0;
+                }
+            }
+        }
+        table
+    };
+
+    static SEARCH_TABLES: RefCell<SearchTables> = RefCell::new(SearchTables::new());
+}
+
+/// Depth first iterator over game continuations, see `Board::enumerate_all_games`
+pub struct GameEnumerator {
+    board: Board,
+    root_len: usize,
+    max_plies: usize,
+    // moves still to explore at every ply of the current path
+    stack: Vec<Vec<Move>>,
+    started: bool,
+}
+
+impl GameEnumerator {
+    pub fn is_leaf(&self) -> bool {
+        self.board.moves.len() - self.root_len >= self.max_plies || self.board.winner().is_some()
+    }
+
+    pub fn children(&self) -> Vec<Move> {
+        let mut moves = self.board.find_all_current_moves();
+        moves.reverse();
+        moves
+    }
+}
+
+impl Iterator for GameEnumerator {
+    type Item = Vec<Move>;
+
+    fn next(&mut self) -> Option<Vec<Move>> {
+        if !self.started {
+            self.started = true;
+            if self.is_leaf() {
+                return Some(Vec::new());
+            }
+            self.stack.push(self.children());
+        }
+
+        loop {
+            let Some(move_) = self.stack.last_mut()?.pop() else {
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    self.board.pop();
+                }
+                continue;
+            };
+
+            self.board.push_unsafe(move_);
+            if self.is_leaf() {
+                let game = self.board.moves[self.root_len..].to_vec();
+                self.board.pop();
+                return Some(game);
+            }
+            self.stack.push(self.children());
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum GeneratorStage {
+    Captures,
+    /// Hash move and killer moves, when there is no capture
+    Early,
+    Quiet,
+    Done,
+}
+
+/// Generates the moves of `find_all_current_moves` as the search asks for them
+///
+/// The capture rules need every capture up front, so all captures are generated at once,
+/// pawn captures before queen captures. Quiet moves are legal only without a capture and
+/// ordered, they are generated after the hash move and the killer moves were searched,
+/// unordered piece by piece. Either way the search often cuts off before they are needed
+struct MoveGenerator {
+    stage: GeneratorStage,
+    ordered: bool,
+    has_captures: bool,
+    /// Moves of the current stage, the next one last
+    buffer: Vec<Move>,
+    /// Moves of the `Early` stage, left out of the quiet moves
+    early: Vec<Move>,
+    /// Pieces without generated quiet moves, the next one last
+    pieces: Vec<(u8, u8)>,
+}
+
+impl MoveGenerator {
+    /// Moves in the order of `Board::order_moves` if the rating orders moves
+    fn new(board: &mut Board, ply: usize) -> MoveGenerator {
+        if !board.rating.use_move_ordering {
+            return MoveGenerator::unordered(board);
+        }
+
+        let captures = board.find_all_captures(board.current_player());
+        generated(captures.len());
+        let mut buffer = board.order_moves(captures, ply);
+        buffer.reverse();
+
+        let mut early = vec![];
+        if buffer.is_empty() {
+            let killers = match board.rating.use_killer_moves {
+                true => board.killer_moves(ply),
+                false => [None; 2],
+            };
+            for move_ in [board.hash_move()].into_iter().chain(killers).flatten() {
+                if !early.contains(&move_) && board.is_quiet_move(move_) {
+                    early.push(move_);
+                }
+            }
+        }
+
+        MoveGenerator {
+            stage: GeneratorStage::Captures,
+            ordered: true,
+            has_captures: !buffer.is_empty(),
+            buffer,
+            early,
+            pieces: vec![],
+        }
+    }
+
+    /// Pawn captures, queen captures, then quiet moves piece by piece
+    fn unordered<const N: usize>(board: &Board<N>) -> MoveGenerator {
+        let player = board.current_player();
+        let mut captures = board.find_all_captures(player);
+        generated(captures.len());
+        captures.sort_by_key(|m| m.piece == Piece::Queen);
+        captures.reverse();
+
+        let mut pieces: Vec<_> = board
+            .all_players_pieces(player)
+            .map(|p| (p.0, p.1))
+            .collect();
+        pieces.reverse();
+
+        MoveGenerator {
+            stage: GeneratorStage::Captures,
+            ordered: false,
+            has_captures: !captures.is_empty(),
+            buffer: captures,
+            early: vec![],
+            pieces,
+        }
+    }
+
+    /// All the moves generated at once by `Board::find_all_current_moves_ordered`
+    fn eager(board: &mut Board, ply: usize) -> MoveGenerator {
+        let mut moves = board.find_all_current_moves_ordered(ply);
+        generated(moves.len());
+        let has_captures = Move::contains_killer_move(&moves);
+        moves.reverse();
+
+        MoveGenerator {
+            stage: GeneratorStage::Done,
+            ordered: false,
+            has_captures,
+            buffer: moves,
+            early: vec![],
+            pieces: vec![],
+        }
+    }
+
+    fn has_captures(&self) -> bool {
+        self.has_captures
+    }
+
+    /// Next move to search, `board` must be in the position the generator was created for
+    fn next_move(&mut self, board: &mut Board) -> Option<Move> {
+        if !self.ordered {
+            return self.next_unordered(board);
+        }
+
+        loop {
+            if let Some(move_) = self.buffer.pop() {
+                return Some(move_);
+            }
+
+            self.buffer = match self.stage {
+                GeneratorStage::Captures if !self.has_captures => {
+                    self.stage = GeneratorStage::Early;
+                    self.early.iter().rev().copied().collect()
+                }
+                GeneratorStage::Early => {
+                    self.stage = GeneratorStage::Quiet;
+                    let mut quiet = board.find_all_current_moves();
+                    generated(quiet.len());
+                    quiet.retain(|m| !self.early.contains(m));
+                    board.order_quiet_moves(&mut quiet);
+                    quiet.reverse();
+                    quiet
+                }
+                _ => {
+                    self.stage = GeneratorStage::Done;
+                    return None;
+                }
+            };
+        }
+    }
+
+    fn next_unordered<const N: usize>(&mut self, board: &Board<N>) -> Option<Move> {
+        loop {
+            if let Some(move_) = self.buffer.pop() {
+                return Some(move_);
+            }
+
+            if self.has_captures || self.stage == GeneratorStage::Done {
+                self.stage = GeneratorStage::Done;
+                return None;
+            }
+
+            self.stage = GeneratorStage::Quiet;
+            let Some((row, col)) = self.pieces.pop() else {
+                self.stage = GeneratorStage::Done;
+                return None;
+            };
+            self.buffer = board.find_moves(row, col, Some(false)).unwrap();
+            generated(self.buffer.len());
+            self.buffer.reverse();
+        }
+    }
+}
+
+/// Counts `count` moves generated for the search
+fn generated(count: usize) {
+    SEARCH_TABLES.with_borrow_mut(|tables| tables.generated += count as u64);
+}
+
+/// Known winning configuration for the current player, rewarded in `rate_current_board`
+#[derive(Copy, Clone, Debug)]
+pub struct Pattern {
+    pub name: &'static str,
+    pub condition: fn(&Board) -> bool,
+    pub eval_bonus: f32,
+}
+
+const WINNING_PATTERNS: [Pattern; 5] = [
+    Pattern {
+        name: "first position",
+        condition: first_position,
+        eval_bonus: 5.0,
+    },
+    Pattern {
+        name: "three queens against one",
+        condition: three_queens_against_one,
+        eval_bonus: 5.0,
+    },
+    Pattern {
+        name: "lone pawn",
+        condition: lone_pawn,
+        eval_bonus: 4.0,
+    },
+    Pattern {
+        name: "bridge",
+        condition: bridge,
+        eval_bonus: 2.0,
+    },
+    Pattern {
+        name: "runaway pawn",
+        condition: runaway_pawn,
+        eval_bonus: 2.0,
+    },
+];
+
+/// Two queens against a queen and a pawn, the pawn can't hide behind its queen forever
+fn first_position(board: &Board) -> bool {
+    let (player, opponent) = (board.current_player(), board.current_player().other());
+    board.total_piece_count(player) == 2
+        && board.count_pieces(player, Piece::Queen) == 2
+        && board.count_pieces(opponent, Piece::Queen) == 1
+        && board.count_pieces(opponent, Piece::Pawn) == 1
+}
+
+/// Three or more queens against a lone queen, which can always be trapped
+fn three_queens_against_one(board: &Board) -> bool {
+    let (player, opponent) = (board.current_player(), board.current_player().other());
+    board.count_pieces(player, Piece::Queen) >= 3
+        && board.total_piece_count(opponent) == 1
+        && board.count_pieces(opponent, Piece::Queen) == 1
+}
+
+/// Any queen against a single pawn, the queen catches it before it promotes
+fn lone_pawn(board: &Board) -> bool {
+    let (player, opponent) = (board.current_player(), board.current_player().other());
+    board.count_pieces(player, Piece::Queen) >= 1
+        && board.total_piece_count(opponent) == 1
+        && board.count_pieces(opponent, Piece::Pawn) == 1
+}
+
+/// Both back rank squares next to the corners are held in an endgame where the opponent
+/// has no queen to break in
+fn bridge(board: &Board) -> bool {
+    let (player, opponent) = (board.current_player(), board.current_player().other());
+    let row = Board::back_rank(player);
+    let cols = match player {
+        Color::White => [2, 6],
+        Color::Black => [1, 5],
+    };
+
+    cols.iter()
+        .all(|&c| board.occupied_by(row, c) == Some(player))
+        && board.count_pieces(opponent, Piece::Queen) == 0
+        && board.total_piece_count(opponent) <= 3
+        && board.total_piece_count(player) >= board.total_piece_count(opponent)
+}
+
+/// A pawn one step from promotion with a free square in front of it and no enemy queen around
+fn runaway_pawn(board: &Board) -> bool {
+    let (player, opponent) = (board.current_player(), board.current_player().other());
+    board.count_pieces(opponent, Piece::Queen) == 0
+        && board.all_players_pieces(player).any(|(r, c, p)| {
+            p == Piece::Pawn
+                && Board::<8>::rows_to_promotion(r, player) == 1
+                && [-1, 1]
+                    .iter()
+                    .any(|dc| board.is_free(r as i8 + player.dir(), c as i8 + dc))
+        })
+}
+
+impl Board {
+    /// Compact board, every character covers 2×2 squares:
+    /// `▀` white pieces only, `▄` black pieces only, `█` both and ` ` none
+    pub fn to_minimap_string(&self) -> String {
+        (0..4)
+            .map(|row| {
+                (0..4)
+                    .map(|col| {
+                        let colors: Vec<_> = (0..2)
+                            .flat_map(|r| (0..2).map(move |c| (row * 2 + r, col * 2 + c)))
+                            .filter_map(|(r, c)| self.occupied_by(r, c))
+                            .collect();
+
+                        match (
+                            colors.contains(&Color::White),
+                            colors.contains(&Color::Black),
+                        ) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `to_minimap_string` in a frame, so that many positions can be logged side by side
+    pub fn display_ascii_minimap(&self) -> String {
+        let mut out = String::from("┌────┐\n");
+        for line in self.to_minimap_string().lines() {
+            out += &format!("│{}│\n", line);
+        }
+        out += "└────┘";
+        out
+    }
+
+    /// Move history as `1. 11-15 23-19 2. ...`, squares are numbered 1-32,
+    /// jumps of one turn are joined as `11x18x27` and promotions end with `(king)`
+    pub fn print_algebraic_game_log(&self) -> String {
+        Board::numbered_algebraic_turns(&self.moves).join(" ")
+    }
+
+    /// `algebraic_turns` with a move number before every white turn
+    pub fn numbered_algebraic_turns(moves: &[Move]) -> Vec<String> {
+        let mut out = Vec::new();
+        for (i, turn) in Board::algebraic_turns(moves).into_iter().enumerate() {
+            if i % 2 == 0 {
+                out.push(format!("{}.", i / 2 + 1));
+            }
+            out.push(turn);
+        }
+        out
+    }
+
+    /// Game from the starting position as PDN, the headers of `meta` and the moves
+    /// in the notation of `print_algebraic_game_log`, ended by the result
+    pub fn to_pdn(moves: &[Move], meta: &GameMetadata) -> String {
+        let mut out = String::new();
+        let headers = [
+            ("Event", meta.event.as_str()),
+            ("Date", &meta.date),
+            ("White", &meta.white),
+            ("Black", &meta.black),
+            ("Result", meta.result.to_pdn()),
+        ];
+        for (name, value) in headers
+            .into_iter()
+            .chain(meta.tags.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+        {
+            out += &format!("[{} \"{}\"]\n", name, value);
+        }
+
+        let mut movetext = Board::numbered_algebraic_turns(moves);
+        movetext.push(meta.result.to_pdn().to_string());
+        out + "\n" + &movetext.join(" ") + "\n"
+    }
+
+    /// Final position and the moves of a game in the format of `to_pdn`, see `Game::from_pdn`
+    pub fn from_pdn(pdn: &str, rates: RateConfig) -> Result<(Board, Vec<Move>), PdnError> {
+        Game::from_pdn(pdn, rates).map(|game| (game.board, game.moves))
+    }
+
+    /// `moves` grouped by turns in the notation of `print_algebraic_game_log`, without numbers
+    pub fn algebraic_turns(moves: &[Move]) -> Vec<String> {
+        let mut turns: Vec<Vec<Move>> = Vec::new();
+        for (i, &move_) in moves.iter().enumerate() {
+            let continued = i > 0 && {
+                let last = moves[i - 1];
+                !last.is_null && !move_.is_null && last.continues() && last.color == move_.color
+            };
+
+            match turns.last_mut() {
+                Some(turn) if continued => turn.push(move_),
+                _ => turns.push(vec![move_]),
+            }
+        }
+
+        let mut out = Vec::new();
+        for turn in turns {
+            let first = turn[0];
+            if first.is_null {
+                out.push("--".to_string());
+                continue;
+            }
+
+            let separator = if first.kill.is_some() { "x" } else { "-" };
+            let mut text = square_number(first.from).to_string();
+            for move_ in &turn {
+                text += &format!("{}{}", separator, square_number(move_.to));
+            }
+            if turn.iter().any(|m| m.is_upgrade()) {
+                text += "(king)";
+            }
+            out.push(text);
+        }
+
+        out
+    }
+
+    /// Fields of every half-move for `json_game_log`, values are already JSON encoded
+    fn json_entries(&self) -> Vec<[(&'static str, String); 7]> {
+        let mut board = self.clone();
+        let moves = board.undo_history_to_ply(0);
+
+        let mut entries = Vec::with_capacity(moves.len());
+        let mut turn = 1;
+        for (i, &move_) in moves.iter().enumerate() {
+            if i > 0 && moves[i - 1].color != move_.color {
+                turn += 1;
+            }
+
+            let score_after = board.with_move_for_mover(move_, |b| b.rate_current_board());
+            board.push_unsafe(move_);
+
+            let text = if move_.is_null {
+                "--".to_string()
+            } else {
+                format!("{}->{}", format_pos(move_.from), format_pos(move_.to))
+            };
+            entries.push([
+                ("turn", turn.to_string()),
+                ("player", format!("\"{}\"", move_.color)),
+                ("move", format!("\"{}\"", text)),
+                ("score_after", format!("{:.2}", score_after)),
+                ("piece_moved", format!("\"{}\"", move_.piece)),
+                ("is_capture", move_.kill.is_some().to_string()),
+                ("is_promotion", move_.is_upgrade().to_string()),
+            ]);
+        }
+        entries
+    }
+
+    /// JSON array with an object for every half-move, `score_after` is the static rating
+    /// from the mover's point of view
+    pub fn json_game_log(&self) -> String {
+        let entries: Vec<_> = self
+            .json_entries()
+            .iter()
+            .map(|fields| {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(key, value)| format!("\"{}\":{}", key, value))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+
+    /// `json_game_log` indented by 2 spaces
+    pub fn json_game_log_pretty(&self) -> String {
+        let entries: Vec<_> = self
+            .json_entries()
+            .iter()
+            .map(|fields| {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(key, value)| format!("    \"{}\": {}", key, value))
+                    .collect();
+                format!("  {{\n{}\n  }}", fields.join(",\n"))
+            })
+            .collect();
+
+        if entries.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n]", entries.join(",\n"))
+        }
+    }
+
+    /// Position as `W:W1,2,K3:B30,31:4:12`, the side to move, white and black pieces by
+    /// square number, queens marked with `K`, the fifty move clock and the move number
+    ///
+    /// The move history isn't kept, so a capture in progress looks like a fresh turn
+    pub fn to_fen(&self) -> String {
+        let side = |color: Color| color.to_char().to_ascii_uppercase();
+        let pieces = |color| {
+            let mut squares: Vec<_> = self
+                .all_players_pieces(color)
+                .map(|(r, c, p)| (square_number((r, c)), p))
+                .collect();
+            squares.sort_by_key(|&(n, _)| n);
+            squares
+                .into_iter()
+                .map(|(n, p)| match p {
+                    // custom pieces can't be written down, so they're saved as pawns
+                    Piece::Pawn | Piece::Custom(_) => n.to_string(),
+                    Piece::Queen => format!("K{}", n),
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        format!(
+            "{}:W{}:B{}:{}:{}",
+            side(self.current_player()),
+            pieces(Color::White),
+            pieces(Color::Black),
+            self.fifty_move_clock,
+            self.turn / 2 + 1
+        )
+    }
+
+    /// Position from `to_fen`, without any move history
+    ///
+    /// The fifty move clock may be left out, it then starts from 0
+    pub fn from_fen(s: &str, rates: RateConfig) -> Result<Board, FenError> {
+        let fields: Vec<_> = s.trim().split(':').collect();
+        let (side, white, black, clock, number) = match fields[..] {
+            [side, white, black, number] => (side, white, black, "0", number),
+            [side, white, black, clock, number] => (side, white, black, clock, number),
+            _ => return Err(FenError::BadFormat),
+        };
+
+        let mut side = side.chars();
+        let side = match (side.next().and_then(Color::from_char), side.next()) {
+            (Some(side), None) => side,
+            _ => return Err(FenError::BadSide),
+        };
+        let number = match number.parse::<usize>() {
+            Ok(number) if number > 0 => number,
+            _ => return Err(FenError::BadMoveNumber),
+        };
+        let clock = clock.parse::<usize>().map_err(|_| FenError::BadClock)?;
+
+        let mut pieces = vec![];
+        for (color, field, prefix) in [(Color::White, white, 'W'), (Color::Black, black, 'B')] {
+            let squares = field.strip_prefix(prefix).ok_or(FenError::BadFormat)?;
+            for square in squares.split(',').filter(|s| !s.is_empty()) {
+                let (piece, square) = match square.strip_prefix('K') {
+                    Some(square) => (Piece::Queen, square),
+                    None => (Piece::Pawn, square),
+                };
+                let (row, col) = square
+                    .parse::<u8>()
+                    .map_err(|_| FenError::BadFormat)
+                    .and_then(|n| square_pos(n).map_err(|_| FenError::BadSquare))?;
+                pieces.push((row, col, PlayersPiece::new(color, piece)));
+            }
+        }
+
+        let mut board = Board::empty_with_rates(rates);
+        board.set_position(&pieces).map_err(FenError::Placement)?;
+        board.first_player = side;
+        board.turn = (number - 1) * 2 + side as usize;
+        board.fifty_move_clock = clock;
+        board.hash = board.compute_zobrist_hash();
+
+        Ok(board)
+    }
+
+    /// Replays log from `print_algebraic_game_log` from the starting position
+    pub fn parse_algebraic_game_log(s: &str, rates: RateConfig) -> Result<Board, ParseError> {
+        let mut board = Board::new(STARTING_LINES, rates);
+
+        for token in s.split_whitespace() {
+            if let Some(number) = token.strip_suffix('.') {
+                number.parse::<usize>().map_err(|_| ParseError::BadFormat)?;
+                continue;
+            }
+
+            board.push_algebraic_turn(token)?;
+        }
+
+        Ok(board)
+    }
+
+    /// Plays one turn like `11-15` or `11x18x27(king)` from `print_algebraic_game_log`
+    fn push_algebraic_turn(&mut self, token: &str) -> Result<(), ParseError> {
+        if token == "--" {
+            self.apply_null_move();
+            return Ok(());
+        }
+
+        let squares = token.strip_suffix("(king)").unwrap_or(token);
+        let squares = squares
+            .split(['-', 'x'])
+            .map(|n| {
+                n.parse::<u8>()
+                    .map_err(|_| ParseError::BadFormat)
+                    .and_then(square_pos)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if squares.len() < 2 {
+            return Err(ParseError::BadFormat);
+        }
+
+        for step in squares.windows(2) {
+            let (from, to) = (step[0], step[1]);
+            let move_ = Move::from_coords(from.0, from.1, to.0, to.1, self)
+                .map_err(|_| ParseError::IllegalMove)?;
+            self.push(move_).expect("Legal move");
+        }
+
+        Ok(())
+    }
+
+    /// Game against the engine on stdin and stdout, see `play_against_engine`
+    pub fn human_play_mode(&mut self, human: Color) {
+        let stdin = io::stdin();
+        self.play_against_engine(human, stdin.lock(), &mut io::stdout())
+            .expect("Couldn't access the terminal");
+    }
+
+    /// Reads moves of `human` line by line in any format of `parse_move` and answers them
+    /// with the engine's moves, until the game is over, `quit` or the end of input,
+    /// `draw` offers the engine a draw
+    fn play_against_engine(
+        &mut self,
+        human: Color,
+        input: impl io::BufRead,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        writeln!(out, "{}", self)?;
+
+        let mut lines = input.lines();
+        while self.game_in_progress() {
+            if self.current_player() != human {
+                let pondered = self
+                    .last_move()
+                    .and_then(|last| self.find_best_move_pondering(last));
+                let move_ = pondered.unwrap_or_else(|| self.find_best_move());
+                writeln!(out, "Player {} played {}", self.current_player(), move_)?;
+                self.push(move_).expect("Legal move");
+                writeln!(out, "{}", self)?;
+
+                // think about the answer to the reply the search expects while the human thinks
+                if self.current_player() == human {
+                    if let Some(expected) = self.hash_move() {
+                        self.start_pondering(expected);
+                    }
+                }
+                continue;
+            }
+
+            write!(out, "Your move: ")?;
+            out.flush()?;
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line = line?;
+            match line.trim() {
+                "quit" => break,
+                "draw" => {
+                    // the engine answers the offer from its own point of view
+                    let engine = human.other();
+                    if self.after_null_move().draw_offer_should_accept() {
+                        self.agree_draw();
+                        writeln!(out, "Player {} accepted the draw", engine)?;
+                    } else {
+                        writeln!(out, "Player {} declined the draw", engine)?;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            match self.parse_move(&line) {
+                Ok(move_) => match self.push(move_) {
+                    Ok(_) => writeln!(out, "{}", self)?,
+                    Err(err) => writeln!(out, "{}", err)?,
+                },
+                Err(err) => writeln!(out, "{}", err)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Analysis shell on stdin and stdout, see `analysis_repl`
+    pub fn interactive_analysis_mode(&mut self) {
+        let stdin = io::stdin();
+        self.analysis_repl(stdin.lock(), &mut io::stdout())
+            .expect("Couldn't access the terminal");
+    }
+
+    /// Reads commands line by line until `quit` or the end of input:
+    /// `move A1 B2`, `undo`, `eval`, `best`, `pv`, `perft N`, `divide N` and `quit`
+    fn analysis_repl(
+        &mut self,
+        input: impl io::BufRead,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        writeln!(out, "{}", self)?;
+
+        for line in input.lines() {
+            let line = line?;
+            let words: Vec<_> = line.split_whitespace().collect();
+
+            match words[..] {
+                [] => continue,
+                ["quit"] => break,
+                ["move", from, to] => match (parse_pos(from), parse_pos(to)) {
+                    (Ok(from), Ok(to)) => match Move::from_coords(from.0, from.1, to.0, to.1, self)
+                    {
+                        Ok(move_) => {
+                            self.push(move_).expect("Legal move");
+                        }
+                        Err(err) => writeln!(out, "{}", err)?,
+                    },
+                    (Err(err), _) | (_, Err(err)) => writeln!(out, "{}", err)?,
+                },
+                ["undo"] => match self.last_player() {
+                    Some(player) => {
+                        while self.last_player() == Some(player) {
+                            self.pop();
+                        }
+                    }
+                    None => writeln!(out, "Nothing to undo")?,
+                },
+                ["eval"] => writeln!(out, "Evaluation: {:.2}", self.rate_current_board())?,
+                ["best" | "pv"] if self.is_game_over() => writeln!(out, "The game is over")?,
+                ["best"] => writeln!(out, "Best move: {}", self.find_best_move())?,
+                ["pv"] => writeln!(
+                    out,
+                    "Principal variation: {}",
+                    self.display_principal_variation()
+                )?,
+                ["perft", depth] => match depth.parse() {
+                    Ok(depth) => writeln!(out, "Perft {}: {}", depth, self.perft(depth))?,
+                    Err(_) => writeln!(out, "Depth must be a number")?,
+                },
+                ["divide", depth] => match depth.parse() {
+                    Ok(depth) => {
+                        for (move_, nodes) in self.perft_divide(depth) {
+                            writeln!(out, "{}: {}", move_, nodes)?;
+                        }
+                    }
+                    Err(_) => writeln!(out, "Depth must be a number")?,
+                },
+                _ => writeln!(out, "Unknown command")?,
+            }
+
+            writeln!(out, "{}", self)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> Display for Board<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        //   1 2 3 4 5 6 7 8
+        // A P . P . P . P .
+        // B . P . P . P . P
+        // C . . . . . . . .
+        // D . . . . . . . .
+        // E . . . . . . . .
+        // F . . . . . . . .
+        // G P . P . P . P .
+        // H . P . P . P . P
+
+        writeln!(
+            f,
+            "#{} - Player {} is on the move",
+            self.turn + 1,
+            self.current_player(),
+        )?;
+
+        // the evaluation is only tuned for 8×8
+        if let Some(board) = (self as &dyn Any).downcast_ref::<Board>() {
+            writeln!(
+                f,
+                "Rating for {} - {}",
+                self.current_player(),
+                board.rate_current_board()
+            )?;
+        }
+
+        // for move_ in self.find_all_current_moves() {
+        //     write!(f, "{} {}\n", "-".color(move_.color.colored()), move_)?;
+        // }
+
+        let moves = self
+            .show_moves_for
+            .and_then(|(r, c)| self.find_moves(r, c, None));
+
+        write!(f, "# ")?;
+        let columns: Vec<_> = (1..=N).map(|col| col.to_string()).collect();
+        writeln!(f, "{}", columns.join(" ").underline().bold())?;
+
+        for row in 0..N as u8 {
+            write!(f, "{}|", ((row + b'A') as char).to_string().bold())?;
+            for col in 0..N as u8 {
+                if let Some(piece) = self.get_ref(row, col) {
+                    let piece = piece.to_string();
+                    if let Some(move_pos) = self.show_moves_for {
+                        if move_pos == (row, col) {
+                            write!(f, "{} ", piece.underline().italic())?;
+                            continue;
+                        }
+                    }
+
+                    write!(f, "{} ", piece)?;
+                    continue;
+                } else if let Some(moves) = &moves {
+                    if let Some(move_) = moves.iter().find(|m| m.to == (row, col)) {
+                        let moving = self.get_ref(move_.from.0, move_.from.1).unwrap();
+                        let piece = PlayersPiece::new(moving.color, move_.future_piece_on(N));
+                        write!(f, "{} ", piece.to_string().dimmed())?;
+                        continue;
+                    }
+                }
+
+                match self.show_moves_for {
+                    Some((r, c)) if r == row && c == col => write!(f, "{} ", ".".underline())?,
+                    _ => write!(f, ". ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_rates() -> RateConfig {
+    let position = PositionRates {
+        pawn: 0.0,
+        queen: 0.0,
+        isolated: 0.0,
+        back_rank_hole: 0.0,
+        column_gap: 0.0,
+        low_material: 0.0,
+        queen_deficit: 0.0,
+        territory: 0.0,
+        promo_threat: 0.0,
+        control: 0.0,
+        back_rank: 0.0,
+        trapped: 0.0,
+        mobility: 0.0,
+        tension: 0.0,
+    };
+    let rates = RateConfigBuilder::new()
+        .piece_pawn(1.0)
+        .piece_queen(3.0)
+        .kill_pawn(10.0)
+        .kill_queen(30.0)
+        .win(1000.0)
+        .draw_moves(50)
+        .max_depth(2)
+        .build()
+        .unwrap();
+
+    // material only, and none of the extra search that would make small trees deep
+    RateConfig {
+        position,
+        endgame: position,
+        target_complexity: 1e4,
+        avoid_repetition: false,
+        use_extensions: false,
+        quiescence_depth: 0,
+        use_null_move: false,
+        ..rates
+    }
+}
+
+/// `test_rates` searching deep enough for node counts to compare search heuristics by,
+/// positional rates make the scores differ, with the material only most moves tie
+#[cfg(test)]
+fn benchmark_rates() -> RateConfig {
+    let rates = test_rates();
+    RateConfig {
+        max_depth: 7,
+        position: PositionRates {
+            isolated: 0.5,
+            back_rank_hole: 0.5,
+            column_gap: 0.5,
+            low_material: 2.0,
+            queen_deficit: 3.0,
+            territory: 0.1,
+            promo_threat: 0.5,
+            control: 0.1,
+            back_rank: 0.2,
+            trapped: 0.3,
+            ..rates.position
+        },
+        ..rates
+    }
+}
+
+/// Plain negamax of the same tree `rate` searches, without pruning or tables
+#[cfg(test)]
+fn reference_minimax(board: &mut Board, depth: usize) -> f32 {
+    let player = board.current_player();
+    if let Some(winner) = board.winner() {
+        return if winner == player {
+            board.rating.win
+        } else {
+            -board.rating.win
+        };
+    }
+    if depth >= board.rating.max_depth {
+        return board.rate_current_board();
+    }
+
+    board
+        .find_all_current_moves()
+        .into_iter()
+        .map(|m| {
+            board.with_move_unchecked(m, |b| {
+                if b.current_player() == player {
+                    reference_minimax(b, depth)
+                } else {
+                    -reference_minimax(b, depth + 1)
+                }
+            })
+        })
+        .fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// `reference_minimax` score of every legal move from the mover's point of view
+#[cfg(test)]
+fn reference_root_scores(board: &mut Board) -> Vec<(Move, f32)> {
+    let moves = board.find_all_current_moves();
+    moves
+        .into_iter()
+        .map(|m| {
+            let score = board.with_move_unchecked(m, |b| {
+                let score = reference_minimax(b, 0);
+                if b.current_player() == m.color {
+                    score
+                } else {
+                    -score
+                }
+            });
+            (m, score)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod null_move_tests {
+    use super::*;
+
+    #[test]
+    fn test_null_move() {
+        let mut board: Board = Board::new(2, test_rates());
+        let before = board.clone();
+
+        assert_eq!(board.current_player(), Color::White);
+        board.apply_null_move();
+        assert_eq!(board.current_player(), Color::Black);
+        assert_eq!(board.turn, 1);
+
+        board.undo_null_move();
+        assert_eq!(board.current_player(), Color::White);
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_null_move_after_move() {
+        let mut board: Board = Board::new(2, test_rates());
+        let move_ = board.find_all_current_moves()[0];
+        board.push(move_).unwrap();
+
+        assert_eq!(board.current_player(), Color::Black);
+        board.apply_null_move();
+        assert_eq!(board.current_player(), Color::White);
+        board.undo_null_move();
+        assert_eq!(board.current_player(), Color::Black);
+        assert_eq!(board.last_move(), Some(move_));
+    }
+}
+
+#[cfg(test)]
+mod best_capture_tests {
+    use super::*;
+
+    #[test]
+    fn test_prefers_queen_capture() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(2, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        let capture = board.find_best_capture().unwrap();
+        assert_eq!(capture.from, (2, 4));
+        assert_eq!(capture.kill.unwrap().piece, Piece::Queen);
+    }
+
+    #[test]
+    fn test_no_captures() {
+        let mut board = Board::new(2, test_rates());
+        assert_eq!(board.find_best_capture(), None);
+    }
+}
+
+#[cfg(test)]
+mod weakness_tests {
+    use super::*;
+
+    fn weakness_rates() -> RateConfig {
+        let mut rates = test_rates();
+        rates.position.isolated = 1.0;
+        rates.position.back_rank_hole = 1.0;
+        rates.position.column_gap = 1.0;
+        rates.position.low_material = 1.0;
+        rates.position.queen_deficit = 1.0;
+        rates
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(2, weakness_rates());
+        assert_eq!(board.strategic_weakness_score(Color::White), 0.0);
+        assert_eq!(board.strategic_weakness_score(Color::Black), 0.0);
+    }
+
+    #[test]
+    fn test_weak_position() {
+        let mut board = Board::new(2, weakness_rates());
+        let strong = board.strategic_weakness_score(Color::White);
+
+        for (r, c, _) in board.all_players_pieces(Color::White).collect::<Vec<_>>() {
+            *board.get_mut(r, c) = None;
+        }
+        *board.get_mut(2, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 6) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        // 2 isolated pawns, 4 back rank holes, gap of 5 columns, 2 missing pieces, 1 queen behind
+        assert_eq!(board.strategic_weakness_score(Color::White), -14.0);
+        assert!(board.strategic_weakness_score(Color::White) < strong);
+        assert!(board.strategic_report(Color::White).contains("total"));
+    }
+}
+
+#[cfg(test)]
+mod parse_move_tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_move() {
+        let move_ = Move::try_from("C3 -> D4").unwrap();
+        assert_eq!(move_.from, (2, 2));
+        assert_eq!(move_.to, (3, 3));
+        assert_eq!(move_.piece, Piece::Pawn);
+        assert_eq!(move_.color, Color::White);
+        assert_eq!(move_.kill, None);
+
+        let move_ = Move::try_from("F2 -> E1").unwrap();
+        assert_eq!(move_.color, Color::Black);
+    }
+
+    #[test]
+    fn test_kill() {
+        let move_ = Move::try_from("C3 -> E5 # D4 Queen").unwrap();
+        assert_eq!(move_.to, (4, 4));
+        assert_eq!(
+            move_.kill,
+            Some(PosUncolorPiece {
+                piece: Piece::Queen,
+                row: 3,
+                col: 3,
+            })
+        );
+        assert_eq!(move_.piece, Piece::Pawn);
+
+        let move_ = Move::try_from("A1 -> E5 # C3 Pawn").unwrap();
+        assert_eq!(move_.piece, Piece::Queen);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let move_ = Move::try_from("G1 -> H2 @@").unwrap();
+        assert!(move_.is_upgrade());
+        assert_eq!(move_.future_piece(), Piece::Queen);
+
+        let move_ = Move::try_from("C1 -> A3 # B2 Pawn @@").unwrap();
+        assert!(move_.is_upgrade());
+        assert_eq!(move_.color, Color::Black);
+
+        let move_ = Move::try_from("G1 -> H2").unwrap();
+        assert_eq!(move_.piece, Piece::Queen);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let board: Board = Board::new(3, test_rates());
+        for move_ in board.find_all_current_moves() {
+            assert_eq!(Move::try_from(move_.to_string().as_str()), Ok(move_));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        use ParseMoveError::*;
+
+        assert_eq!(Move::try_from(""), Err(BadFormat));
+        assert_eq!(Move::try_from("C3 D4"), Err(BadFormat));
+        assert_eq!(Move::try_from("C3 -> C4"), Err(BadFormat));
+        assert_eq!(Move::try_from("C3 -> D4 extra"), Err(BadFormat));
+        assert_eq!(Move::try_from("C3 -> D4 @@"), Err(BadFormat));
+        assert_eq!(Move::try_from("C3 -> I9"), Err(OutOfBounds));
+        assert_eq!(Move::try_from("C0 -> D1"), Err(OutOfBounds));
+        assert_eq!(Move::try_from("C3 -> E5 # D4 King"), Err(UnknownPiece));
+    }
+}
+
+#[cfg(test)]
+mod breakthrough_tests {
+    use super::*;
+
+    #[test]
+    fn test_immediate_promotion() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let (move_, turns) = board.evaluate_pawn_breakthrough().unwrap();
+        assert_eq!(turns, 1);
+        assert!(move_.is_upgrade());
+    }
+
+    #[test]
+    fn test_clear_path() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let (move_, turns) = board.evaluate_pawn_breakthrough().unwrap();
+        assert_eq!(turns, 2);
+        assert_eq!(move_.from, (5, 1));
+    }
+
+    #[test]
+    fn test_blocked() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(5, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        for col in [1, 3, 5] {
+            *board.get_mut(7, col) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        }
+
+        assert_eq!(board.evaluate_pawn_breakthrough(), None);
+    }
+}
+
+#[cfg(test)]
+mod territory_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        assert_eq!(
+            board.count_squares_controlled_by_side(Color::White),
+            board.count_squares_controlled_by_side(Color::Black)
+        );
+        assert_eq!(board.territory_advantage(), 0);
+    }
+
+    #[test]
+    fn test_advanced_side() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        // the advanced white pawn reaches two squares, both black pawns are stuck on an edge
+        assert_eq!(board.count_squares_controlled_by_side(Color::White), 5);
+        assert_eq!(board.count_squares_controlled_by_side(Color::Black), 4);
+        assert_eq!(board.territory_advantage(), 1);
+    }
+}
+
+#[cfg(test)]
+mod beam_tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_moves() {
+        let mut board = Board::new(3, test_rates());
+        let all = board.find_all_current_moves();
+
+        assert_eq!(board.get_candidate_moves(3).len(), 3);
+        assert_eq!(board.get_candidate_moves(100).len(), all.len());
+        assert!(board
+            .get_candidate_moves(100)
+            .iter()
+            .all(|m| all.contains(m)));
+    }
+
+    #[test]
+    fn test_candidates_sorted() {
+        let mut board = Board::new(3, test_rates());
+        board.rating.position.pawn = 1.0;
+
+        let scores: Vec<_> = board
+            .get_candidate_moves(100)
+            .into_iter()
+            .map(|m| board.with_move_for_mover(m, |b| b.rate_current_board()))
+            .collect();
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_find_best_move_beam() {
+        let mut board = Board::new(3, test_rates());
+        let move_ = board.find_best_move_beam(3);
+        assert!(board.is_valid_move(move_));
+    }
+}
+
+#[cfg(test)]
+mod pattern_tests {
+    use super::*;
+
+    fn board_with(pieces: &[(u8, u8, Color, Piece)]) -> Board {
+        let mut board = Board::empty_with_rates(test_rates());
+        for &(r, c, color, piece) in pieces {
+            *board.get_mut(r, c) = Some(PlayersPiece::new(color, piece));
+        }
+        board
+    }
+
+    fn pattern_names(board: &Board) -> Vec<&'static str> {
+        board
+            .winning_endgame_patterns()
+            .iter()
+            .map(|p| p.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        assert!(pattern_names(&board).is_empty());
+    }
+
+    #[test]
+    fn test_first_position() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[
+            (3, 3, White, Queen),
+            (0, 6, White, Queen),
+            (7, 7, Black, Queen),
+            (6, 0, Black, Pawn),
+        ]);
+        assert_eq!(pattern_names(&board), ["first position"]);
+    }
+
+    #[test]
+    fn test_three_queens_against_one() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[
+            (0, 0, White, Queen),
+            (0, 2, White, Queen),
+            (0, 4, White, Queen),
+            (7, 7, Black, Queen),
+        ]);
+        assert_eq!(pattern_names(&board), ["three queens against one"]);
+    }
+
+    #[test]
+    fn test_lone_pawn() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[(0, 0, White, Queen), (6, 0, Black, Pawn)]);
+        assert_eq!(pattern_names(&board), ["lone pawn"]);
+    }
+
+    #[test]
+    fn test_bridge() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[
+            (0, 2, White, Pawn),
+            (0, 6, White, Pawn),
+            (7, 1, Black, Pawn),
+            (7, 3, Black, Pawn),
+        ]);
+        assert_eq!(pattern_names(&board), ["bridge"]);
+    }
+
+    #[test]
+    fn test_runaway_pawn() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[
+            (6, 2, White, Pawn),
+            (7, 7, Black, Pawn),
+            (5, 7, Black, Pawn),
+        ]);
+        assert_eq!(pattern_names(&board), ["runaway pawn"]);
+    }
+
+    #[test]
+    fn test_bonus_in_rating() {
+        use {Color::*, Piece::*};
+        let board = board_with(&[(0, 0, White, Queen), (6, 0, Black, Pawn)]);
+
+        // queen against pawn 3 - 1 and the lone pawn bonus
+        assert_eq!(board.rate_current_board(), 2.0 + 4.0);
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::*;
+
+    fn play(board: &mut Board, plies: usize) {
+        for _ in 0..plies {
+            let move_ = board.find_all_current_moves()[0];
+            board.push(move_).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_undo_to_ply() {
+        let mut board = Board::new(3, test_rates());
+        play(&mut board, 2);
+        let at_two = board.clone();
+        play(&mut board, 6);
+        let at_eight = board.clone();
+
+        let undone = board.undo_history_to_ply(2);
+        assert_eq!(undone.len(), 6);
+        assert_eq!(board, at_two);
+        assert_eq!(board.turn, at_two.turn);
+
+        for move_ in undone {
+            board.push(move_).unwrap();
+        }
+        assert_eq!(board, at_eight);
+    }
+
+    #[test]
+    fn test_undo_to_later_ply() {
+        let mut board = Board::new(3, test_rates());
+        play(&mut board, 3);
+        let before = board.clone();
+
+        assert!(board.undo_history_to_ply(3).is_empty());
+        assert!(board.undo_history_to_ply(10).is_empty());
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_undo_to_start() {
+        let mut board = Board::new(3, test_rates());
+        let start = board.clone();
+        play(&mut board, 5);
+
+        board.undo_history_to_ply(0);
+        assert_eq!(board, start);
+        assert_eq!(board.turn, 0);
+    }
+}
+
+#[cfg(test)]
+mod training_tests {
+    use super::*;
+
+    #[test]
+    fn test_label_blend() {
+        let label = TrainingLabel::new(4.0, Some(-1.0), 0.25);
+        assert_eq!(label.blended, 0.25 * 4.0 - 0.75);
+
+        let label = TrainingLabel::new(4.0, None, 0.25);
+        assert_eq!(label.blended, 4.0);
+    }
+
+    #[test]
+    fn test_score_position() {
+        let mut board = Board::new(2, test_rates());
+        let label = board.score_position_for_training();
+        assert_eq!(label.game_outcome, None);
+        assert!(label.minimax_score.is_finite());
+    }
+
+    #[test]
+    fn test_annotate_game() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        board.push(Move::try_from("C3 -> D4").unwrap()).unwrap();
+        board.push(Move::try_from("F6 -> E5").unwrap()).unwrap();
+        board
+            .push(Move::try_from("D4 -> F6 # E5 Pawn").unwrap())
+            .unwrap();
+        assert_eq!(board.winner(), Some(Color::White));
+        let end = board.clone();
+
+        let labels = board.annotate_game();
+        assert_eq!(board, end);
+        assert_eq!(labels.len(), 3);
+
+        let outcomes: Vec<_> = labels.iter().map(|l| l.game_outcome).collect();
+        assert_eq!(outcomes, [Some(1.0), Some(-1.0), Some(1.0)]);
+        assert_eq!(labels[2].minimax_score, 1000.0);
+        assert_eq!(labels[2].blended, 0.5 * 1000.0 + 0.5);
+    }
+}
+
+#[cfg(test)]
+mod first_capture_tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_available() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.time_to_first_capture(), Some(0));
+        assert_eq!(board.search_profile().captures, 1);
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(board.time_to_first_capture(), Some(2));
+
+        let profile = board.search_profile();
+        assert_eq!(profile.branching_factor, 7);
+        assert_eq!(profile.captures, 0);
+    }
+
+    #[test]
+    fn test_no_capture() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.time_to_first_capture(), None);
+    }
+}
+
+#[cfg(test)]
+mod material_table_tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        MATERIAL_TABLE.with(|table| {
+            for (white, row) in table.iter().enumerate() {
+                for (black, value) in row.iter().enumerate() {
+                    assert_eq!(*value, -table[black][white]);
+                }
+                assert_eq!(row[white], 0.0);
+            }
+            // being a pawn up is worth more with less material on the board
+            assert!(table[2][1] > table[8][7]);
+        });
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let mut board = Board::new(2, test_rates());
+        assert_eq!(board.material_counting_heuristic(), 0.0);
+
+        *board.get_mut(6, 0) = None;
+        assert!(board.material_counting_heuristic() > 0.0);
+
+        board.apply_null_move();
+        assert!(board.material_counting_heuristic() < 0.0);
+    }
+
+    #[test]
+    fn test_queens_count_as_three() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.material_counting_heuristic(), 0.0);
+    }
+}
+
+#[cfg(test)]
+mod fortress_tests {
+    use super::*;
+
+    #[test]
+    fn test_fortress() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(0, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+
+        assert!(board.detect_fortress_position(Color::Black));
+        assert!(!board.detect_fortress_position(Color::White));
+        // a queen and a pawn against a queen, damped toward a draw
+        assert!((board.rate_current_board() - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_no_damping_before_endgame() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        for (r, c) in [(0, 0), (0, 2), (1, 1), (1, 3), (1, 5), (1, 7)] {
+            *board.get_mut(r, c) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        }
+
+        assert!(!board.is_endgame());
+        assert!(board.detect_fortress_position(Color::Black));
+        assert_eq!(board.rate_current_board(), 3.0);
+    }
+
+    #[test]
+    fn test_no_damping_when_ahead() {
+        // two cornered queens are fewer pieces, but more material than three pawns
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        for (r, c) in [(0, 0), (0, 2), (1, 1)] {
+            *board.get_mut(r, c) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        }
+
+        assert!(board.detect_fortress_position(Color::Black));
+        assert_eq!(board.rate_current_board(), -3.0);
+    }
+
+    #[test]
+    fn test_queen_outside_corner() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(0, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+
+        assert!(!board.detect_fortress_position(Color::Black));
+    }
+
+    #[test]
+    fn test_weak_pawn_breaks_fortress() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(0, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+
+        assert!(!board.detect_fortress_position(Color::Black));
+    }
+}
+
+#[cfg(test)]
+mod complexity_tests {
+    use super::*;
+
+    #[test]
+    fn test_complexity() {
+        let mut board = Board::new(3, test_rates());
+        // 7 moves, 24 pieces
+        assert_eq!(board.compute_game_complexity(), 49.0);
+    }
+
+    #[test]
+    fn test_adapt_depth() {
+        let mut board = Board::new(3, test_rates());
+        let depth = board.adapt_depth();
+        assert!((1..=10).contains(&depth));
+        // 7^5 = 16807 is the closest to 10^4
+        assert_eq!(depth, 5);
+
+        board.rating.target_complexity = 0.0;
+        assert_eq!(board.adapt_depth(), 1);
+
+        board.rating.target_complexity = f64::MAX;
+        assert_eq!(board.adapt_depth(), 10);
+    }
+}
+
+#[cfg(test)]
+mod flip_turn_tests {
+    use super::*;
+
+    #[test]
+    fn test_flip_turn() {
+        let mut board: Board = Board::new(2, test_rates());
+        let move_ = board.find_all_current_moves()[0];
+        board.push(move_).unwrap();
+        let before = board.clone();
+
+        board.flip_turn();
+        board.assert_consistent();
+        assert_eq!(board.current_player(), Color::White);
+
+        board.unflip_turn();
+        board.assert_consistent();
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_double_flip() {
+        let mut board: Board = Board::new(2, test_rates());
+        board.flip_turn();
+        board.flip_turn();
+        board.assert_consistent();
+        assert_eq!(board.current_player(), Color::White);
+        assert_eq!(board.turn, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Turn doesn't match the move history")]
+    fn test_inconsistent_turn() {
+        let mut board: Board = Board::new(2, test_rates());
+        board.turn = 3;
+        board.assert_consistent();
+    }
+}
+
+#[cfg(test)]
+mod promo_threat_tests {
+    use super::*;
+
+    #[test]
+    fn test_close_pawn() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(6, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.count_reachable_promotion_squares(Color::White), 1);
+        assert_eq!(board.count_reachable_promotion_squares(Color::Black), 0);
+    }
+
+    #[test]
+    fn test_guarded_path() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        // (6, 0) leads only to the occupied (7, 1) and (6, 2) can be captured
+        let before = board.clone();
+        assert_eq!(board.count_reachable_promotion_squares(Color::White), 0);
+        assert_eq!(board, before);
+
+        *board.get_mut(7, 1) = None;
+        assert_eq!(board.count_reachable_promotion_squares(Color::White), 1);
+    }
+}
+
+#[cfg(test)]
+mod training_file_tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::new(2, test_rates());
+        for _ in 0..6 {
+            let move_ = board.find_all_current_moves()[0];
+            board.push(move_).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("checkengine-{}.bin", std::process::id()));
+        board.export_training_game(&path).unwrap();
+        let imported = Board::import_training_game(&path, test_rates()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(imported, board.annotate_game());
+        assert_eq!(imported.len(), 6);
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let path =
+            std::env::temp_dir().join(format!("checkengine-invalid-{}.bin", std::process::id()));
+        fs::write(&path, b"nope").unwrap();
+        let imported = Board::import_training_game(&path, test_rates());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(imported.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[cfg(test)]
+mod repetition_tests {
+    use super::*;
+
+    fn oscillating_board() -> (Board, [Move; 4]) {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        let colors = [Color::White, Color::Black];
+        let mut moves = ["A3 -> B4", "H6 -> G5", "B4 -> A3", "G5 -> H6"].map(|m| {
+            let mut move_ = Move::try_from(m).unwrap();
+            move_.piece = Piece::Queen;
+            move_
+        });
+        for (i, move_) in moves.iter_mut().enumerate() {
+            move_.color = colors[i % 2];
+        }
+        (board, moves)
+    }
+
+    #[test]
+    fn test_hash() {
+        let (mut board, moves) = oscillating_board();
+        let start = board.zobrist_hash();
+
+        board.push(moves[0]).unwrap();
+        assert_ne!(board.zobrist_hash(), start);
+        board.push(moves[1]).unwrap();
+        board.push(moves[2]).unwrap();
+        board.push(moves[3]).unwrap();
+        assert_eq!(board.zobrist_hash(), start);
+        assert_eq!(board.position_hashes().len(), 5);
+    }
+
+    #[test]
+    fn test_repetition() {
+        let (mut board, moves) = oscillating_board();
+
+        for move_ in &moves[..3] {
+            assert!(!board.move_causes_repetition(*move_));
+            board.push(*move_).unwrap();
+        }
+        // second occurrence of the starting position is fine
+        assert!(!board.move_causes_repetition(moves[3]));
+        board.push(moves[3]).unwrap();
+
+        for move_ in &moves[..3] {
+            board.push(*move_).unwrap();
+        }
+        assert!(board.move_causes_repetition(moves[3]));
+    }
+
+    /// Rates the position better for White by `advantage`, whatever the pieces
+    #[derive(Debug)]
+    struct WhiteAdvantage(f32);
+
+    impl Evaluator for WhiteAdvantage {
+        fn name(&self) -> &'static str {
+            if self.0 > 0.0 {
+                "White ahead"
+            } else {
+                "White behind"
+            }
+        }
+
+        fn evaluate(&self, board: &Board) -> f32 {
+            match board.current_player() {
+                Color::White => self.0,
+                Color::Black => -self.0,
+            }
+        }
+    }
+
+    /// Black to move, `G5 -> H6` repeats the starting position for the third time
+    fn before_third_repetition(evaluator: &'static WhiteAdvantage) -> (Board, Move) {
+        let (mut board, moves) = oscillating_board();
+        board.rating.max_depth = 1;
+        board.set_evaluator(evaluator);
+        for move_ in moves.iter().chain(&moves[..3]) {
+            board.push(*move_).unwrap();
+        }
+        (board, moves[3])
+    }
+
+    #[test]
+    fn test_search_rates_repetition_as_draw() {
+        static AHEAD: WhiteAdvantage = WhiteAdvantage(1.0);
+        let (mut board, repetition) = before_third_repetition(&AHEAD);
+        assert_eq!(board.rate_root_move(repetition), 0.0);
+
+        board.push(repetition).unwrap();
+        assert!(board.is_draw());
+        assert_eq!(board.winner(), None);
+    }
+
+    #[test]
+    fn test_losing_side_seeks_repetition() {
+        static AHEAD: WhiteAdvantage = WhiteAdvantage(1.0);
+        let (mut board, repetition) = before_third_repetition(&AHEAD);
+        assert_eq!(board.find_best_move(), repetition);
+    }
+
+    #[test]
+    fn test_winning_side_avoids_repetition() {
+        static BEHIND: WhiteAdvantage = WhiteAdvantage(-1.0);
+        let (mut board, repetition) = before_third_repetition(&BEHIND);
+        assert_ne!(board.find_best_move(), repetition);
+    }
+}
+
+#[cfg(test)]
+mod winning_threshold_tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold() {
+        let board = Board::new(2, test_rates());
+        assert_eq!(board.get_winning_threshold_score(), 1000.0 / 16.0);
+
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        assert_eq!(board.get_winning_threshold_score(), 500.0);
+
+        assert_eq!(
+            Board::empty_with_rates(test_rates()).get_winning_threshold_score(),
+            1000.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod minimap_tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap() {
+        let board = Board::new(2, test_rates());
+        let minimap = board.to_minimap_string();
+
+        assert_eq!(minimap.lines().count(), 4);
+        assert!(minimap.lines().all(|l| l.chars().count() == 4));
+        assert_eq!(minimap, "▀▀▀▀\n    \n    \n▄▄▄▄");
+    }
+
+    #[test]
+    fn test_mixed_cell() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.to_minimap_string(), "    \n █  \n    \n   ▄");
+        assert_eq!(board.display_ascii_minimap().lines().count(), 6);
+    }
+}
+
+#[cfg(test)]
+mod multi_pv_tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_pv() {
+        let mut board = Board::new(2, test_rates());
+        let before = board.clone();
+        let pvs = board.multi_pv_search(3);
+        assert_eq!(board, before);
+
+        assert_eq!(pvs.len(), 3);
+        assert_eq!(pvs[0].1, board.find_best_move_scored().1);
+        assert!(pvs.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let firsts: Vec<_> = pvs.iter().map(|(pv, _)| pv[0]).collect();
+        assert!(firsts
+            .iter()
+            .enumerate()
+            .all(|(i, m)| !firsts[..i].contains(m)));
+    }
+
+    #[test]
+    fn test_lines_are_legal() {
+        let mut board = Board::new(2, test_rates());
+        for (pv, _) in board.clone().multi_pv_search(2) {
+            let mut line = board.clone();
+            for move_ in pv {
+                assert!(line.is_valid_move(move_));
+                line.push(move_).unwrap();
+            }
+        }
+        assert!(board.multi_pv_search(100).len() <= 7);
+    }
+}
+
+#[cfg(test)]
+mod draw_offer_tests {
+    use super::*;
+
+    #[test]
+    fn test_winning() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(6, 6) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert!(!board.draw_offer_should_accept());
+        assert!(!board.should_offer_draw());
+    }
+
+    #[test]
+    fn test_slightly_behind() {
+        let mut board = Board::new(2, test_rates());
+        *board.get_mut(0, 0) = None;
+
+        assert!(board.draw_offer_should_accept());
+        assert!(board.should_offer_draw());
+    }
+
+    #[test]
+    fn test_equal() {
+        let mut board = Board::new(2, test_rates());
+
+        assert!(board.draw_offer_should_accept());
+        assert!(!board.should_offer_draw());
+    }
+
+    #[test]
+    fn test_lost() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(4, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        board.apply_null_move();
+
+        assert!(board.draw_offer_should_accept());
+        assert!(!board.should_offer_draw());
+    }
+}
+
+#[cfg(test)]
+mod enumerate_games_tests {
+    use super::*;
+
+    #[test]
+    fn test_one_ply() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(
+            board.count_all_games(1),
+            board.find_all_current_moves().len() as u64
+        );
+        assert_eq!(board.enumerate_all_games(1).count(), 7);
+        assert_eq!(board.enumerate_all_games(0).collect::<Vec<_>>(), [vec![]]);
+    }
+
+    #[test]
+    fn test_games_are_legal() {
+        let mut board = Board::new(3, test_rates());
+        let before = board.clone();
+
+        let games: Vec<_> = board.enumerate_all_games(3).collect();
+        assert_eq!(games.len() as u64, board.count_all_games(3));
+        assert_eq!(board, before);
+
+        for game in &games {
+            assert_eq!(game.len(), 3);
+            let mut replay = board.clone();
+            for &move_ in game {
+                assert!(replay.is_valid_move(move_));
+                replay.push(move_).unwrap();
+            }
+        }
+        assert!(games
+            .iter()
+            .enumerate()
+            .all(|(i, g)| !games[..i].contains(g)));
+    }
+
+    #[test]
+    fn test_game_ending_early() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let games: Vec<_> = board.enumerate_all_games(5).collect();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].len(), 1);
+        assert_eq!(board.count_all_games(5), 1);
+    }
+}
+
+#[cfg(test)]
+mod fifty_move_tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        board.push(Move::try_from("C3 -> D4").unwrap()).unwrap();
+        board.push(Move::try_from("F6 -> E5").unwrap()).unwrap();
+        assert_eq!(board.check_fifty_move_clock(), 2);
+
+        board
+            .push(Move::try_from("D4 -> F6 # E5 Pawn").unwrap())
+            .unwrap();
+        assert_eq!(board.check_fifty_move_clock(), 0);
+
+        board.pop();
+        assert_eq!(board.check_fifty_move_clock(), 2);
+        board.apply_null_move();
+        assert_eq!(board.check_fifty_move_clock(), 2);
+        board.undo_null_move();
+        board.pop();
+        assert_eq!(board.check_fifty_move_clock(), 1);
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut rates = test_rates();
+        rates.draw_after_turns = 4;
+        let mut board: Board = Board::empty_with_rates(rates);
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        for _ in 0..3 {
+            let move_ = board.find_all_current_moves()[0];
+            board.push(move_).unwrap();
+            assert!(!board.is_draw());
+        }
+
+        let move_ = board.find_all_current_moves()[0];
+        assert_eq!(board.push(move_), Ok(None));
+        assert!(board.is_draw());
+        assert!(!board.find_all_current_moves().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod from_coords_tests {
+    use super::*;
+
+    #[test]
+    fn test_legal_move() {
+        let board = Board::new(2, test_rates());
+        let move_ = Move::from_coords(1, 1, 2, 2, &board).unwrap();
+        assert_eq!(move_.piece, Piece::Pawn);
+        assert_eq!(move_.color, Color::White);
+        assert_eq!(move_.kill, None);
+    }
+
+    #[test]
+    fn test_kill() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let move_ = Move::from_coords(2, 2, 6, 6, &board).unwrap();
+        assert_eq!(move_.piece, Piece::Queen);
+        assert_eq!(move_.kill.map(|k| (k.row, k.col)), Some((4, 4)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let board = Board::new(2, test_rates());
+        assert_eq!(
+            Move::from_coords(3, 3, 4, 4, &board),
+            Err(MoveError::NoPieceAtSource)
+        );
+        assert_eq!(
+            Move::from_coords(9, 3, 4, 4, &board),
+            Err(MoveError::NoPieceAtSource)
+        );
+        assert_eq!(
+            Move::from_coords(6, 0, 5, 1, &board),
+            Err(MoveError::WrongColor)
+        );
+        assert_eq!(
+            Move::from_coords(1, 1, 3, 3, &board),
+            Err(MoveError::NotInLegalSet)
+        );
+        // blocked by an own piece
+        assert_eq!(
+            Move::from_coords(0, 0, 1, 1, &board),
+            Err(MoveError::NotInLegalSet)
+        );
+    }
+}
+
+#[cfg(test)]
+mod control_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        let matrix = board.compute_control_matrix();
+
+        for r in 0..8 {
+            for c in 0..8 {
+                assert_eq!(matrix[r][c], -matrix[7 - r][7 - c]);
+                if (r + c) % 2 == 1 {
+                    assert_eq!(matrix[r][c], 0);
+                }
+            }
+        }
+        assert_eq!(matrix[3][3], 1);
+        assert_eq!(board.control_advantage(), 0);
+    }
+
+    #[test]
+    fn test_contested() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 2) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let matrix = board.compute_control_matrix();
+        assert_eq!(matrix[3][1], 0);
+        assert_eq!(matrix[3][3], 0);
+        assert_eq!(board.control_advantage(), 0);
+
+        *board.get_mut(4, 2) = None;
+        assert_eq!(board.control_advantage(), 2);
+    }
+}
+
+#[cfg(test)]
+mod extension_tests {
+    use super::*;
+
+    fn extension_rates() -> RateConfig {
+        let mut rates = test_rates();
+        rates.use_extensions = true;
+        rates
+    }
+
+    #[test]
+    fn test_quiet_move() {
+        let mut board = Board::new(3, extension_rates());
+        let move_ = board.find_all_current_moves()[0];
+        assert_eq!(board.search_extensions(move_, 0), 0);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut board = Board::empty_with_rates(extension_rates());
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 6) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let move_ = Move::try_from("G1 -> H2 @@").unwrap();
+        assert_eq!(board.search_extensions(move_, 0), 1);
+        // too deep already
+        assert_eq!(board.search_extensions(move_, 4), 0);
+
+        board.rating.use_extensions = false;
+        assert_eq!(board.search_extensions(move_, 0), 0);
+    }
+
+    #[test]
+    fn test_forced_reply() {
+        let mut board = Board::empty_with_rates(extension_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        // black has to take the pawn
+        let move_ = Move::try_from("C3 -> D4").unwrap();
+        assert_eq!(board.search_extensions(move_, 0), 1);
+        let move_ = Move::try_from("C3 -> D2").unwrap();
+        assert_eq!(board.search_extensions(move_, 0), 0);
+    }
+
+    #[test]
+    fn test_rate_with_extensions() {
+        let mut board = Board::new(2, extension_rates());
+        let move_ = board.find_best_move();
+        assert!(board.is_valid_move(move_));
+    }
+}
+
+#[cfg(test)]
+mod game_log_tests {
+    use super::*;
+
+    #[test]
+    fn test_square_numbers() {
+        assert_eq!(square_number((0, 0)), 1);
+        assert_eq!(square_number((2, 4)), 11);
+        assert_eq!(square_number((7, 7)), 32);
+        for n in 1..=32 {
+            let pos = square_pos(n).unwrap();
+            assert_eq!((pos.0 + pos.1) % 2, 0);
+            assert_eq!(square_number(pos), n);
+        }
+        assert_eq!(square_pos(0), Err(ParseError::BadSquare));
+        assert_eq!(square_pos(33), Err(ParseError::BadSquare));
+    }
+
+    #[test]
+    fn test_print() {
+        let mut board = Board::new(3, test_rates());
+        board.push(Move::try_from("C5 -> D6").unwrap()).unwrap();
+        board.push(Move::try_from("F4 -> E5").unwrap()).unwrap();
+        board
+            .push(Move::try_from("D6 -> F4 # E5 Pawn").unwrap())
+            .unwrap();
+        assert_eq!(board.print_algebraic_game_log(), "1. 11-15 22-19 2. 15x22");
+    }
+
+    #[test]
+    fn test_multi_jump_and_king() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        board
+            .push(Move::try_from("C3 -> E5 # D4 Pawn").unwrap())
+            .unwrap();
+        board
+            .push(Move::try_from("E5 -> G7 # F6 Pawn").unwrap())
+            .unwrap();
+        assert_eq!(board.print_algebraic_game_log(), "1. 10x19x28");
+
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        board.push(Move::try_from("G1 -> H2 @@").unwrap()).unwrap();
+        assert_eq!(board.print_algebraic_game_log(), "1. 25-29(king)");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::new(3, test_rates());
+        for _ in 0..30 {
+            if board.winner().is_some() {
+                break;
+            }
+            let move_ = board.find_all_current_moves()[0];
+            board.push(move_).unwrap();
+        }
+
+        let log = board.print_algebraic_game_log();
+        let parsed = Board::parse_algebraic_game_log(&log, test_rates()).unwrap();
+        assert_eq!(parsed.moves, board.moves);
+        assert_eq!(parsed.print_algebraic_game_log(), log);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let rates = test_rates();
+        assert_eq!(
+            Board::parse_algebraic_game_log("1. 11-15 x.", rates),
+            Err(ParseError::BadFormat)
+        );
+        assert_eq!(
+            Board::parse_algebraic_game_log("1. 11-40", rates),
+            Err(ParseError::BadSquare)
+        );
+        assert_eq!(
+            Board::parse_algebraic_game_log("1. 11-20", rates),
+            Err(ParseError::IllegalMove)
+        );
+    }
+}
+
+#[cfg(test)]
+mod edit_tests {
+    use super::*;
+
+    const WHITE_PAWN: PlayersPiece = PlayersPiece {
+        color: Color::White,
+        piece: Piece::Pawn,
+    };
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut board: Board = Board::new(3, test_rates());
+        let move_ = board.find_all_current_moves()[0];
+        board.push(move_).unwrap();
+
+        assert_eq!(board.add_piece(3, 5, WHITE_PAWN), Ok(()));
+        assert!(board.moves.is_empty());
+        board.assert_consistent();
+
+        assert_eq!(board.remove_piece(3, 5), Ok(WHITE_PAWN));
+        assert_eq!(board.piece_at(3, 5), None);
+        board.assert_consistent();
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut board: Board = Board::new(3, test_rates());
+        let black_queen = PlayersPiece::new(Color::Black, Piece::Queen);
+
+        assert_eq!(
+            board.add_piece(8, 0, WHITE_PAWN),
+            Err(BoardError::OutOfBounds)
+        );
+        assert_eq!(
+            board.add_piece(3, 4, WHITE_PAWN),
+            Err(BoardError::LightSquare)
+        );
+        assert_eq!(board.add_piece(0, 0, WHITE_PAWN), Err(BoardError::Occupied));
+        assert_eq!(
+            board.add_piece(7, 7, black_queen),
+            Err(BoardError::Occupied)
+        );
+        assert_eq!(board.remove_piece(3, 3), Err(BoardError::EmptySquare));
+        assert_eq!(board.remove_piece(0, 8), Err(BoardError::OutOfBounds));
+
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        assert_eq!(
+            board.add_piece(7, 1, WHITE_PAWN),
+            Err(BoardError::PawnOnLastRow)
+        );
+        assert_eq!(
+            board.add_piece(7, 1, PlayersPiece::new(Color::White, Piece::Queen)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_set_position() {
+        let mut board: Board = Board::new(3, test_rates());
+        let black_queen = PlayersPiece::new(Color::Black, Piece::Queen);
+
+        assert_eq!(
+            board.set_position(&[(2, 2, WHITE_PAWN), (5, 5, black_queen)]),
+            Ok(())
+        );
+        assert_eq!(board.all_players_pieces(Color::White).count(), 1);
+        assert_eq!(board.all_players_pieces(Color::Black).count(), 1);
+        board.assert_consistent();
+
+        assert_eq!(
+            board.set_position(&[(2, 2, WHITE_PAWN), (2, 2, black_queen)]),
+            Err(PlacementError::SameSquare { row: 2, col: 2 })
+        );
+        assert_eq!(board.piece_at(5, 5), Some(black_queen));
+    }
+}
+
+#[cfg(test)]
+mod defensive_tests {
+    use super::*;
+
+    fn threatened_queen() -> Board {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(1, 5) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        board
+    }
+
+    #[test]
+    fn test_threats() {
+        let board = threatened_queen();
+        let threats = board.threats_to(Color::White);
+        assert_eq!(threats.len(), 1);
+        assert_eq!(
+            (threats[0].row, threats[0].col, threats[0].piece),
+            (3, 3, Piece::Queen)
+        );
+        assert!(board.threats_to(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_saves_queen() {
+        let mut board = threatened_queen();
+        let move_ = board.find_defensive_move().unwrap();
+        board.push(move_).unwrap();
+        assert!(board.threats_to(Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_no_threats() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(board.find_defensive_move(), None);
+    }
+
+    #[test]
+    fn test_fast_path() {
+        let mut board = threatened_queen();
+        board.rating.prefer_defensive = true;
+        let move_ = board.find_best_move();
+        board.push(move_).unwrap();
+        assert!(board.threats_to(Color::White).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod game_status_tests {
+    use super::*;
+
+    #[test]
+    fn test_in_progress() {
+        let board: Board = Board::new(3, test_rates());
+        assert!(board.game_in_progress());
+        assert!(!board.is_game_over());
+        assert_eq!(board.game_status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn test_won() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        assert!(board.is_game_over());
+        assert_eq!(board.game_status(), GameStatus::Won(Color::White));
+    }
+
+    #[test]
+    fn test_drawn() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        for move_ in ["A3 -> B4", "H6 -> G5", "B4 -> A3", "G5 -> H6"]
+            .into_iter()
+            .cycle()
+            .take(8)
+        {
+            assert!(board.game_in_progress());
+            let mut move_ = Move::try_from(move_).unwrap();
+            move_.piece = Piece::Queen;
+            move_.color = board.current_player();
+            board.push(move_).unwrap();
+        }
+        assert_eq!(
+            board.game_status(),
+            GameStatus::Drawn(DrawReason::Repetition)
+        );
+
+        board.rating.draw_after_turns = 8;
+        assert_eq!(
+            board.game_status(),
+            GameStatus::Drawn(DrawReason::FiftyMove)
+        );
+    }
+
+    #[test]
+    fn test_draw_reason_decides_every_check() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        assert_eq!(board.draw_reason(), None);
+        assert!(!board.is_draw());
+
+        board.fifty_move_clock = board.rating.draw_after_turns;
+        assert_eq!(board.draw_reason(), Some(DrawReason::FiftyMove));
+        assert!(board.is_draw());
+        assert!(board.is_game_over());
+        assert_eq!(board.winner(), None);
+        assert_eq!(
+            board.game_status(),
+            GameStatus::Drawn(DrawReason::FiftyMove)
+        );
+    }
+}
+
+#[cfg(test)]
+mod move_generator_tests {
+    use super::*;
+
+    fn assert_same_moves<const N: usize>(board: &Board<N>) {
+        assert_same_set(board.lazy_move_generator().collect(), board);
+    }
+
+    fn assert_same_set<const N: usize>(mut lazy: Vec<Move>, board: &Board<N>) {
+        let mut eager = board.find_all_current_moves();
+        let key = |m: &Move| (m.from, m.to);
+        lazy.sort_by_key(key);
+        eager.sort_by_key(key);
+        assert_eq!(lazy, eager);
+    }
+
+    /// Nodes visited and moves generated by a search of the starting position
+    fn search_cost(use_lazy_moves: bool) -> (u64, u64, Move) {
+        let mut rates = benchmark_rates();
+        rates.use_lazy_moves = use_lazy_moves;
+
+        SEARCH_TABLES.set(SearchTables::new());
+        let best = Board::new(3, rates).find_best_move();
+        SEARCH_TABLES.with_borrow(|tables| (tables.nodes, tables.generated, best))
+    }
+
+    #[test]
+    fn test_same_as_eager() {
+        let mut board: Board = Board::new(3, test_rates());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for _ in 0..60 {
+            if board.winner().is_some() {
+                break;
+            }
+            assert_same_moves(&board);
+
+            let mut ordered = MoveGenerator::new(&mut board, 0);
+            let moves = std::iter::from_fn(|| ordered.next_move(&mut board)).collect();
+            assert_same_set(moves, &board);
+
+            let moves = board.find_all_current_moves();
+            board.push(moves[rng.gen_range(0..moves.len())]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_queen_captures_only() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(2, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(3, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_same_moves(&board);
+        assert!(board
+            .lazy_move_generator()
+            .all(|m| m.piece == Piece::Queen && m.kill.is_some()));
+    }
+
+    #[test]
+    fn test_pawn_captures_before_queen_captures() {
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board.variant = Variant::International;
+        let piece = |color, piece| PlayersPiece::new(color, piece);
+        board
+            .set_position(&[
+                (0, 0, piece(Color::White, Piece::Queen)),
+                (4, 4, piece(Color::White, Piece::Pawn)),
+                (2, 2, piece(Color::Black, Piece::Pawn)),
+                (5, 5, piece(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+
+        assert_same_moves(&board);
+        let pieces: Vec<_> = board.lazy_move_generator().map(|m| m.piece).collect();
+        assert_eq!(pieces.first(), Some(&Piece::Pawn));
+        assert!(pieces[1..].iter().all(|&piece| piece == Piece::Queen));
+    }
+
+    #[test]
+    fn test_longest_capture_only() {
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board.variant = Variant::International;
+        let pawn = |color| PlayersPiece::new(color, Piece::Pawn);
+        board
+            .set_position(&[
+                (0, 0, pawn(Color::White)),
+                (0, 6, pawn(Color::White)),
+                (1, 1, pawn(Color::Black)),
+                (3, 1, pawn(Color::Black)),
+                (1, 5, pawn(Color::Black)),
+            ])
+            .unwrap();
+
+        assert_same_moves(&board);
+        let moves: Vec<_> = board.lazy_move_generator().collect();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, (0, 0));
+    }
+
+    #[test]
+    fn test_custom_pieces() {
+        #[derive(Debug)]
+        struct Jumper;
+
+        impl PieceKind for Jumper {
+            fn name(&self) -> &'static str {
+                "Jumper"
+            }
+
+            fn symbol(&self) -> char {
+                'J'
+            }
+
+            fn value(&self) -> f32 {
+                1.0
+            }
+
+            // captures backwards too
+            fn generate_moves(
+                &self,
+                (row, col): (u8, u8),
+                color: Color,
+                board: &dyn BoardView,
+            ) -> Vec<Move> {
+                let mut moves = vec![];
+                for (dr, dc) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+                    let (r, c) = (row as i8 + dr, col as i8 + dc);
+                    let (to_r, to_c) = (r + dr, c + dc);
+                    let Some(PlayersPiece {
+                        color: other,
+                        piece,
+                    }) = board
+                        .in_bounds(r, c)
+                        .then(|| board.piece_at(r as u8, c as u8))
+                        .flatten()
+                    else {
+                        if board.is_free(r, c) {
+                            moves.push(Move {
+                                from: (row, col),
+                                to: (r as u8, c as u8),
+                                piece: Piece::Custom(&Jumper),
+                                kill: None,
+                                color,
+                                is_null: false,
+                            });
+                        }
+                        continue;
+                    };
+                    if other != color && board.is_free(to_r, to_c) {
+                        moves.push(Move {
+                            from: (row, col),
+                            to: (to_r as u8, to_c as u8),
+                            piece: Piece::Custom(&Jumper),
+                            kill: Some(PosUncolorPiece {
+                                piece,
+                                row: r as u8,
+                                col: c as u8,
+                            }),
+                            color,
+                            is_null: false,
+                        });
+                    }
+                }
+                moves
+            }
+        }
+
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::White, Piece::Custom(&Jumper)));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_same_moves(&board);
+        let moves: Vec<_> = board.lazy_move_generator().collect();
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].from, moves[0].to), ((4, 4), (2, 2)));
+    }
+
+    #[test]
+    fn test_captures_first() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let first = board.lazy_move_generator().next().unwrap();
+        assert!(first.kill.is_some());
+        assert_same_moves(&board);
+    }
+
+    #[test]
+    fn test_fewer_moves_generated() {
+        let (lazy_nodes, lazy_generated, lazy_best) = search_cost(true);
+        let (eager_nodes, eager_generated, eager_best) = search_cost(false);
+        assert_eq!(lazy_best, eager_best);
+
+        // the quiet moves are often left out by a cutoff on the hash move or a killer move,
+        // ordering the rest later by the history of the searched moves cuts off earlier
+        assert!(
+            lazy_nodes <= eager_nodes,
+            "{} vs {} nodes",
+            lazy_nodes,
+            eager_nodes
+        );
+        assert!(
+            lazy_generated * 3 < eager_generated * 2,
+            "{} vs {} moves",
+            lazy_generated,
+            eager_generated
+        );
+    }
+}
+
+#[cfg(test)]
+mod back_rank_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        for player in [Color::White, Color::Black] {
+            assert_eq!(board.count_back_rank_pieces(player), 4);
+            assert!(board.has_full_back_rank(player));
+        }
+    }
+
+    #[test]
+    fn test_after_moves() {
+        let mut board = Board::new(1, test_rates());
+        board.push(Move::try_from("A1 -> B2").unwrap()).unwrap();
+        assert_eq!(board.count_back_rank_pieces(Color::White), 3);
+        assert!(!board.has_full_back_rank(Color::White));
+        assert!(board.has_full_back_rank(Color::Black));
+    }
+
+    #[test]
+    fn test_bonus_in_rating() {
+        let mut board = Board::new(1, test_rates());
+        board.push(Move::try_from("A1 -> B2").unwrap()).unwrap();
+        let without = board.rate_current_board();
+
+        // black to move, with one more piece at home
+        board.rating.position.back_rank = 1.0;
+        assert_eq!(board.rate_current_board(), without + 1.0);
+    }
+}
+
+#[cfg(test)]
+mod analysis_tests {
+    use super::*;
+
+    fn run(board: &mut Board, script: &str) -> String {
+        let mut out = Vec::new();
+        board.analysis_repl(script.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_perft() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(board.perft(0), 1);
+        assert_eq!(board.perft(1), 7);
+        assert_eq!(board.perft(2), 49);
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let mut board = Board::new(3, test_rates());
+        let divide = board.perft_divide(3);
+        assert_eq!(divide.len(), 7);
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), board.perft(3));
+        assert!(board.perft_divide(0).is_empty());
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut board = Board::new(3, test_rates());
+        let out = run(&mut board, "move C3 D4\neval\nbest\npv\nperft 1\n");
+        assert_eq!(board.moves.len(), 1);
+        assert!(out.contains("Evaluation: "));
+        assert!(out.contains("Best move: "));
+        assert!(out.contains("Principal variation: "));
+        assert!(out.contains("Perft 1: 7"));
+    }
+
+    #[test]
+    fn test_undo_and_quit() {
+        let mut board = Board::new(3, test_rates());
+        run(&mut board, "move C3 D4\nundo\nmove C3 D4\nquit\nundo\n");
+        assert_eq!(board.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut board = Board::new(3, test_rates());
+        let out = run(&mut board, "move D4 E5\nmove XX D4\nperft x\nundo\nfly\n");
+        assert!(out.contains(&MoveError::NoPieceAtSource.to_string()));
+        assert!(out.contains(&ParseMoveError::BadFormat.to_string()));
+        assert!(out.contains("Depth must be a number"));
+        assert!(out.contains("Nothing to undo"));
+        assert!(out.contains("Unknown command"));
+        assert!(board.moves.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod trapped_tests {
+    use super::*;
+
+    fn cornered_pawn() -> Board {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(6, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(7, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        board
+    }
+
+    #[test]
+    fn test_cornered_pawn() {
+        let board = cornered_pawn();
+        assert_eq!(board.fully_trapped_pieces(Color::White), vec![(6, 0)]);
+        assert_eq!(board.count_mobility_restricted_pieces(Color::White), 1);
+        assert_eq!(board.count_mobility_restricted_pieces(Color::Black), 0);
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        // only the front row can move
+        assert_eq!(board.count_mobility_restricted_pieces(Color::White), 8);
+        assert_eq!(board.count_mobility_restricted_pieces(Color::Black), 8);
+    }
+
+    #[test]
+    fn test_penalty_in_rating() {
+        let mut board = cornered_pawn();
+        let without = board.rate_current_board();
+        assert!(board.is_endgame());
+        board.rating.endgame.trapped = 1.0;
+        assert_eq!(board.rate_current_board(), without - 1.0);
+    }
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use super::*;
+
+    #[test]
+    fn test_move_bytes() {
+        let moves = [
+            Move::try_from("C3 -> D4").unwrap(),
+            Move::try_from("D4 -> F6 # E5 Queen").unwrap(),
+            Move::try_from("G1 -> H2 @@").unwrap(),
+        ];
+        for move_ in moves {
+            assert_eq!(Move::from_bytes(move_.to_bytes()), Ok(move_));
+        }
+        assert_eq!(Move::from_bytes([0x80, 0, 0, 0]), Err(DecodeError::BadMove));
+        assert_eq!(
+            Move::from_bytes([0, 0x11, 0, 0x22]),
+            Err(DecodeError::BadMove)
+        );
+    }
+
+    #[test]
+    fn test_repeated_queen_moves() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        *board.get_mut(2, 6) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+
+        board.push(Move::try_from("C7 -> D8").unwrap()).unwrap();
+        for _ in 0..10 {
+            for move_ in ["H6 -> G5", "A3 -> B4", "G5 -> H6", "B4 -> A3"] {
+                let mut move_ = Move::try_from(move_).unwrap();
+                move_.piece = Piece::Queen;
+                move_.color = board.current_player();
+                board.push(move_).unwrap();
+            }
+        }
+
+        let data = board.history_compression();
+        assert!(data.len() * 2 < board.moves.len() * 4);
+        assert_eq!(Board::history_decompression(&data), Ok(board.moves.clone()));
+    }
+
+    #[test]
+    fn test_lossless() {
+        let mut board = Board::new(3, test_rates());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        while board.winner().is_none() && board.moves.len() < 80 {
+            let moves = board.find_all_current_moves();
+            board.push(moves[rng.gen_range(0..moves.len())]).unwrap();
+        }
+
+        let data = board.history_compression();
+        assert_eq!(Board::history_decompression(&data), Ok(board.moves.clone()));
+        assert_eq!(Board::history_decompression(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Board::history_decompression(&[1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Board::history_decompression(&[1, 1, 0]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Board::history_decompression(&[0, 1, 0, 0, 0, 0]),
+            Err(DecodeError::EmptyRun)
+        );
+    }
+}
+
+#[cfg(test)]
+mod strength_tests {
+    use super::*;
+
+    #[test]
+    fn test_temperature() {
+        assert_eq!(Board::elo_temperature(2000.0), 0.0);
+        assert_eq!(Board::elo_temperature(1500.0), 0.0);
+        assert_eq!(Board::elo_temperature(1250.0), 1.0);
+        assert!(Board::elo_temperature(500.0) > Board::elo_temperature(1000.0));
+    }
+
+    #[test]
+    fn test_zero_temperature_plays_best() {
+        let mut board = Board::new(3, test_rates());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        // all moves score the same, so every search has to start with empty tables
+        let best = board.find_best_move();
+        SEARCH_TABLES.set(SearchTables::new());
+        assert_eq!(board.choose_move_with_temperature(0.0, &mut rng), best);
+        SEARCH_TABLES.set(SearchTables::new());
+        assert_eq!(board.engine_strength_scaling(2000.0), best);
+    }
+
+    #[test]
+    fn test_high_temperature_varies() {
+        let mut board = Board::new(3, test_rates());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut moves: Vec<_> = (0..30)
+            .map(|_| board.choose_move_with_temperature(1e6, &mut rng))
+            .collect();
+        moves.dedup();
+        assert!(moves.len() > 1);
+        assert!(moves.iter().all(|&m| board.is_valid_move(m)));
+    }
+
+    #[test]
+    fn test_estimated_elo() {
+        let mut rates = test_rates();
+        rates.max_depth = 1;
+        let mut board = Board::new(3, rates);
+        let elo = board.estimated_elo();
+        assert!((400.0..=1600.0).contains(&elo));
+    }
+}
+
+#[cfg(test)]
+mod move_ordering_tests {
+    use super::*;
+
+    fn clear_tables() {
+        SEARCH_TABLES.set(SearchTables::new());
+    }
+
+    #[test]
+    fn test_captures_first() {
+        clear_tables();
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 6) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        // ignoring the forced capture, so that there is something to sort
+        let moves: Vec<_> = board
+            .all_current_pieces()
+            .flat_map(|(r, c, _)| board.find_moves(r, c, None).unwrap())
+            .collect();
+        let ordered = board.order_moves(moves.clone(), 0);
+        assert_eq!(ordered.len(), moves.len());
+        assert!(ordered[0].kill.is_some());
+        assert!(ordered[1..].iter().all(|m| m.kill.is_none()));
+    }
+
+    #[test]
+    fn test_hash_and_killer_moves() {
+        clear_tables();
+        let mut board = Board::new(3, test_rates());
+        let moves = board.find_all_current_moves();
+        let (hashed, killer) = (moves[3], moves[5]);
+
+        let entry = |hash, move_| TTEntry {
+            hash,
+            depth: 1,
+            score: 0.0,
+            flag: TTFlag::LowerBound,
+            best_move: Some(move_),
+        };
+        SEARCH_TABLES.with_borrow_mut(|tables| {
+            tables.record(0, entry(!board.zobrist_hash(), killer));
+            tables.record(0, entry(board.zobrist_hash(), hashed));
+        });
+
+        let ordered = board.find_all_current_moves_ordered(0);
+        assert_eq!(ordered[0], hashed);
+        assert_eq!(ordered[1], killer);
+        assert_eq!(ordered.len(), moves.len());
+    }
+
+    #[test]
+    fn test_history() {
+        clear_tables();
+        let mut board = Board::new(3, test_rates());
+        let moves = board.find_all_current_moves();
+        SEARCH_TABLES.with_borrow_mut(|tables| tables.history.reward(moves[6], 2));
+        assert_eq!(board.find_all_current_moves_ordered(0)[0], moves[6]);
+    }
+
+    #[test]
+    fn test_search_fills_tables() {
+        clear_tables();
+        let mut board = Board::new(3, test_rates());
+        let move_ = board.find_best_move();
+        // the root itself isn't searched by `rate`, only the positions after each root move
+        board.push(move_).unwrap();
+        assert!(board.hash_move().is_some());
+    }
+}
+
+#[cfg(test)]
+mod threat_score_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        assert_eq!(board.compute_threat_score(Color::White), 0.0);
+        assert_eq!(board.compute_threat_score(Color::Black), 0.0);
+    }
+
+    #[test]
+    fn test_capture_and_double_attack() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        // 2 captures of the queen, which is also under double attack
+        assert_eq!(board.compute_threat_score(Color::White), 3.0 * 30.0);
+        // the queen can take both pawns back
+        assert_eq!(board.compute_threat_score(Color::Black), 2.0 * 10.0);
+    }
+
+    #[test]
+    fn test_promotion() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(4, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert_eq!(board.compute_threat_score(Color::White), 30.0);
+        assert_eq!(board.compute_threat_score(Color::Black), 30.0);
+    }
+
+    #[test]
+    fn test_in_rating() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(2, 4) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+
+        let without = board.rate_current_board();
+        board.rating.use_threat_score = true;
+        assert_eq!(board.rate_current_board(), without + 90.0 - 20.0);
+    }
+}
+
+#[cfg(test)]
+mod piece_type_moves_tests {
+    use super::*;
+
+    fn mixed_board() -> Board {
+        let mut board = Board::new(3, test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(1, 1) = None;
+        board
+    }
+
+    #[test]
+    fn test_union() {
+        let board = mixed_board();
+        for player in [Color::White, Color::Black] {
+            let mut union = board.find_moves_for_piece_type(Piece::Pawn, player);
+            union.extend(board.find_moves_for_piece_type(Piece::Queen, player));
+            let mut all = board.find_all_moves_for(player);
+
+            let key = |m: &Move| (m.from, m.to);
+            union.sort_by_key(key);
+            all.sort_by_key(key);
+            assert_eq!(union, all);
+        }
+    }
+
+    #[test]
+    fn test_has_moves() {
+        let board = mixed_board();
+        assert!(board.has_moves_for_piece_type(Piece::Queen, Color::White));
+        assert!(board.has_moves_for_piece_type(Piece::Pawn, Color::White));
+        assert!(!board.has_moves_for_piece_type(Piece::Queen, Color::Black));
+        assert!(board
+            .find_moves_for_piece_type(Piece::Queen, Color::White)
+            .iter()
+            .all(|m| m.piece == Piece::Queen));
+    }
+}
+
+#[cfg(test)]
+mod game_summary_tests {
+    use super::*;
+
+    #[test]
+    fn test_full_game() {
+        let mut board = Board::new(1, test_rates());
+        while board.game_in_progress() && board.turn < 40 {
+            let move_ = board.find_best_move();
+            board.push(move_).unwrap();
+        }
+        let moves = board.moves.clone();
+
+        let summary = board.report_game_summary();
+        assert_eq!(board.moves, moves);
+        assert_eq!(summary.moves.len(), moves.len());
+        assert_eq!(summary.evaluation.len(), moves.len() + 1);
+        assert_eq!(summary.complexity.len(), moves.len());
+        assert_eq!(summary.total_turns, board.turn);
+        assert_eq!(summary.winner, board.winner());
+        assert!(summary.to_string().contains(&summary.moves.join(" ")));
+    }
+
+    #[test]
+    fn test_blunder() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        // walks right into the black pawn
+        board.push(Move::try_from("C3 -> D4").unwrap()).unwrap();
+        board.push(Move::try_from("F6 -> E5").unwrap()).unwrap();
+
+        let summary = board.report_game_summary();
+        assert_eq!(summary.moves, ["10-14", "23-19"]);
+        assert!(summary.blunders.iter().any(|&(i, _)| i == 1));
+    }
+}
+
+#[cfg(test)]
+mod placement_tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        let pieces = [
+            (0, 0, Color::White, Piece::Pawn),
+            (7, 1, Color::White, Piece::Queen),
+            (5, 5, Color::Black, Piece::Pawn),
+        ];
+        assert_eq!(Board::<8>::validate_piece_placement(&pieces), Ok(()));
+    }
+
+    #[test]
+    fn test_all_errors_reported() {
+        let pieces = [
+            (8, 0, Color::White, Piece::Pawn),
+            (0, 1, Color::White, Piece::Pawn),
+            (2, 2, Color::White, Piece::Pawn),
+            (2, 2, Color::Black, Piece::Pawn),
+            (0, 0, Color::Black, Piece::Pawn),
+        ];
+        assert_eq!(
+            Board::<8>::validate_piece_placement(&pieces),
+            Err(vec![
+                PlacementError::OutOfBounds { row: 8, col: 0 },
+                PlacementError::LightSquare { row: 0, col: 1 },
+                PlacementError::SameSquare { row: 2, col: 2 },
+                PlacementError::PawnOnLastRow { row: 0, col: 0 },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_too_many_pieces() {
+        let pieces: Vec<_> = (0..8u8)
+            .flat_map(|row| (0..8u8).map(move |col| (row, col)))
+            .filter(|(row, col)| (row + col) % 2 == 0)
+            .take(13)
+            .map(|(row, col)| (row, col, Color::White, Piece::Queen))
+            .collect();
+        assert_eq!(
+            Board::<8>::validate_piece_placement(&pieces),
+            Err(vec![
+                PlacementError::TooManyPieces {
+                    color: Color::White,
+                    count: 13
+                },
+                PlacementError::TooManyQueens {
+                    color: Color::White,
+                    count: 13
+                },
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod endgame_distance_tests {
+    use super::*;
+
+    #[test]
+    fn test_game_over() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        assert_eq!(board.endgame_distance(), Some(0));
+    }
+
+    #[test]
+    fn test_win() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        assert_eq!(board.endgame_distance(), Some(1));
+    }
+
+    #[test]
+    fn test_loss() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(4, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(6, 2) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        assert_eq!(board.endgame_distance(), Some(-2));
+    }
+
+    #[test]
+    fn test_out_of_reach() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(board.endgame_distance(), None);
+    }
+}
+
+#[cfg(test)]
+mod pondering_tests {
+    use super::*;
+
+    fn ponder_rates() -> RateConfig {
+        let mut rates = test_rates();
+        rates.max_depth = 3;
+        rates
+    }
+
+    #[test]
+    fn test_ponder_hit() {
+        let mut board = Board::new(3, ponder_rates());
+        board.push(board.find_all_current_moves()[0]).unwrap();
+        let expected = board.find_all_current_moves()[0];
+
+        board.start_pondering(expected);
+        // however slow the machine, wait for an iteration deeper than a cold search
+        let reached = board.wait_for_ponder_depth(3, Duration::from_secs(60));
+        assert!(reached.is_some(), "pondering didn't get past depth 3");
+        board.push(expected).unwrap();
+
+        let depth = board.ponder_depth().unwrap();
+        let move_ = board.find_best_move_pondering(expected).unwrap();
+        assert!(depth > board.rating.max_depth);
+        assert!(board.is_valid_move(move_));
+    }
+
+    #[test]
+    fn test_ponder_miss() {
+        let mut board = Board::new(3, ponder_rates());
+        board.push(board.find_all_current_moves()[0]).unwrap();
+        let moves = board.find_all_current_moves();
+
+        board.start_pondering(moves[0]);
+        board.push(moves[1]).unwrap();
+        assert_eq!(board.find_best_move_pondering(moves[1]), None);
+        assert_eq!(board.find_best_move_pondering(moves[0]), None);
+    }
+
+    #[test]
+    fn test_clone_doesnt_ponder() {
+        let mut board = Board::new(3, ponder_rates());
+        board.push(board.find_all_current_moves()[0]).unwrap();
+        let expected = board.find_all_current_moves()[0];
+
+        board.start_pondering(expected);
+        let mut clone = board.clone();
+        assert_eq!(clone, board);
+        clone.push(expected).unwrap();
+        assert_eq!(clone.find_best_move_pondering(expected), None);
+    }
+}
+
+#[cfg(test)]
+mod tempo_loss_tests {
+    use super::*;
+
+    #[test]
+    fn test_every_move_loses_the_pawn() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(5, 5) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        assert!(board.detect_tempo_loss());
+    }
+
+    #[test]
+    fn test_safe_move_left() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(5, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        assert!(!board.detect_tempo_loss());
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let mut board = Board::new(3, test_rates());
+        assert!(!board.detect_tempo_loss());
+    }
+}
+
+#[cfg(test)]
+mod json_log_tests {
+    use super::*;
+
+    fn played_game() -> Board {
+        let mut board = Board::new(3, test_rates());
+        board.push(Move::try_from("C5 -> D6").unwrap()).unwrap();
+        board.push(Move::try_from("F4 -> E5").unwrap()).unwrap();
+        board
+            .push(Move::try_from("D6 -> F4 # E5 Pawn").unwrap())
+            .unwrap();
+        board
+    }
+
+    fn assert_balanced(json: &str) {
+        let mut depth = 0i32;
+        for c in json.chars() {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0);
+        }
+        assert_eq!(depth, 0);
+        assert_eq!(json.matches('"').count() % 2, 0);
+    }
+
+    #[test]
+    fn test_json() {
+        let json = played_game().json_game_log();
+        assert_balanced(&json);
+        assert!(json.starts_with('[') && json.ends_with(']'));
+        assert_eq!(json.matches("\"turn\":").count(), 3);
+        assert!(json.starts_with(r#"[{"turn":1,"player":"White","move":"C5->D6","score_after":"#));
+        assert!(json.contains(r#""turn":3,"player":"White","move":"D6->F4""#));
+        assert!(json.contains(r#""is_capture":true,"is_promotion":false}]"#));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let board = played_game();
+        let pretty = board.json_game_log_pretty();
+        assert_balanced(&pretty);
+        assert!(pretty.starts_with("[\n  {\n    \"turn\": 1,\n"));
+        assert_eq!(pretty.matches("\"piece_moved\": \"Pawn\"").count(), 3);
+
+        let compact: String = pretty.split_whitespace().collect();
+        assert_eq!(compact, board.json_game_log());
+    }
+
+    #[test]
+    fn test_empty() {
+        let board = Board::new(3, test_rates());
+        assert_eq!(board.json_game_log(), "[]");
+        assert_eq!(board.json_game_log_pretty(), "[]");
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    fn round_trip<const N: usize>(board: &Board<N>) -> Board<N> {
+        let text = board.to_json().to_string();
+        let loaded = Board::from_json(&Json::parse(&text).unwrap()).unwrap();
+        assert_eq!(&loaded, board);
+        assert_eq!(loaded.hash_history, board.hash_history);
+        assert_eq!(loaded.clock_history, board.clock_history);
+        loaded
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = r#" {"a": [1, -2.5e1, true, false, null], "b\"\u00e9\ud83d\ude00": {}} "#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(
+            json,
+            Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(-25.0),
+                        Json::Bool(true),
+                        Json::Bool(false),
+                        Json::Null,
+                    ])
+                ),
+                ("b\"é😀".to_string(), Json::Object(vec![])),
+            ])
+        );
+        assert_eq!(
+            json.to_string(),
+            r#"{"a":[1,-25,true,false,null],"b\"é😀":{}}"#
+        );
+        assert_eq!(Json::parse(&json.to_string()), Ok(json));
+
+        let control = Json::String("tab\tbell\u{7}".to_string());
+        assert_eq!(control.to_string(), r#""tab\tbell\u0007""#);
+        assert_eq!(Json::parse(&control.to_string()), Ok(control));
+
+        assert_eq!(Json::parse(""), Err(JsonError::Syntax(0)));
+        assert_eq!(Json::parse("[1,]"), Err(JsonError::Syntax(3)));
+        assert_eq!(Json::parse("[1] 2"), Err(JsonError::Syntax(4)));
+        assert_eq!(Json::parse(r#"{"a" 1}"#), Err(JsonError::Syntax(5)));
+        assert_eq!(Json::parse(r#""\x""#), Err(JsonError::Syntax(2)));
+        assert_eq!(Json::parse("\"open"), Err(JsonError::Syntax(5)));
+        assert_eq!(Json::parse("1.2.3"), Err(JsonError::Syntax(0)));
+    }
+
+    #[test]
+    fn test_move() {
+        let move_ = Move {
+            from: (2, 2),
+            to: (4, 4),
+            piece: Piece::Pawn,
+            kill: Some(PosUncolorPiece {
+                piece: Piece::Queen,
+                row: 3,
+                col: 3,
+            }),
+            color: Color::White,
+            is_null: false,
+        };
+        let json = move_.to_json();
+        assert_eq!(
+            json.to_string(),
+            r#"{"from":[2,2],"to":[4,4],"piece":"Pawn","kill":{"row":3,"col":3,"piece":"Queen"},"color":"White"}"#
+        );
+        assert_eq!(Move::from_json(&json), Ok(move_));
+
+        let quiet = Move {
+            kill: None,
+            to: (3, 3),
+            ..move_
+        };
+        assert!(quiet.to_json().to_string().contains(r#""kill":null"#));
+        assert_eq!(Move::from_json(&quiet.to_json()), Ok(quiet));
+
+        let null = Move::null(Color::Black);
+        assert!(null.to_json().to_string().ends_with(r#""is_null":true}"#));
+        assert_eq!(Move::from_json(&null.to_json()), Ok(null));
+
+        let off_board =
+            Json::parse(r#"{"from":[2,20],"to":[3,3],"piece":"Pawn","kill":null,"color":"White"}"#)
+                .unwrap();
+        assert_eq!(
+            Move::from_json(&off_board),
+            Err(JsonError::BadField("from"))
+        );
+    }
+
+    #[test]
+    fn test_rate_config() {
+        for rates in [test_rates(), RateConfigBuilder::new().build().unwrap()] {
+            let text = rates.to_json().to_string();
+            assert_eq!(
+                RateConfig::from_json(&Json::parse(&text).unwrap()),
+                Ok(rates)
+            );
+        }
+
+        let mut rates = test_rates();
+        rates.tempo_loss_threshold = 0.1;
+        let json = rates.to_json();
+        assert!(json.to_string().contains(r#""tempo_loss_threshold":0.1,"#));
+        assert_eq!(RateConfig::from_json(&json), Ok(rates));
+
+        let Json::Object(mut fields) = json else {
+            unreachable!()
+        };
+        fields.retain(|(key, _)| key != "use_null_move");
+        assert_eq!(
+            RateConfig::from_json(&Json::Object(fields)),
+            Err(JsonError::BadField("use_null_move"))
+        );
+    }
+
+    #[test]
+    fn test_played_game() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut board: Board = Board::new(3, test_rates());
+        round_trip(&board);
+        while board.winner().is_none() && board.moves.len() < 80 {
+            let moves = board.find_all_current_moves();
+            board.push(moves[rng.gen_range(0..moves.len())]).unwrap();
+        }
+        assert!(board.moves.iter().any(|m| m.kill.is_some()));
+
+        let mut loaded = round_trip(&board);
+        assert!(loaded.to_json().get("show_moves_for").is_none());
+        assert_eq!(loaded.pop_all(), board.pop_all());
+        assert_eq!(loaded, board);
+    }
+
+    #[test]
+    fn test_international_board() {
+        let mut board = Board::new_international(test_rates());
+        for _ in 0..6 {
+            board.push(board.find_all_current_moves()[0]).unwrap();
+        }
+        round_trip(&board);
+
+        let standard: Board = Board::new(3, test_rates());
+        assert_eq!(
+            Board::<10>::from_json(&standard.to_json()),
+            Err(JsonError::BadField("board"))
+        );
+    }
+
+    #[test]
+    fn test_loaded_position() {
+        let mut board = Board::from_fen("B:WK1:BK32:12:30", test_rates()).unwrap();
+        board.push(board.find_all_current_moves()[0]).unwrap();
+        board.agree_draw();
+
+        let loaded = round_trip(&board);
+        assert_eq!(loaded.check_fifty_move_clock(), 13);
+        assert_eq!(loaded.turn, board.turn);
+        assert_eq!(loaded.draw_reason(), Some(DrawReason::Agreement));
+    }
+
+    #[test]
+    fn test_inconsistent_moves() {
+        let mut board: Board = Board::new(3, test_rates());
+        board.push(board.find_all_current_moves()[0]).unwrap();
+
+        let Json::Object(mut fields) = board.to_json() else {
+            unreachable!()
+        };
+        let moves = fields.iter_mut().find(|(key, _)| key == "moves").unwrap();
+        let Json::Array(moves) = &mut moves.1 else {
+            unreachable!()
+        };
+        moves.push(moves[0].clone());
+        assert_eq!(
+            Board::<8>::from_json(&Json::Object(fields)),
+            Err(JsonError::BadField("moves"))
+        );
+    }
+}
+
+#[cfg(test)]
+mod callback_tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_order() {
+        let mut rates = test_rates();
+        rates.max_depth = 3;
+        let mut board = Board::new(3, rates);
+
+        let mut calls = Vec::new();
+        let move_ = board
+            .find_best_move_with_callback(|depth, move_, score| calls.push((depth, move_, score)));
+
+        assert_eq!(calls.iter().map(|c| c.0).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(calls.iter().all(|&(_, m, _)| board.is_valid_move(m)));
+        assert_eq!(calls.last().unwrap().1, move_);
+        assert_eq!(board.rating.max_depth, 3);
+        assert_eq!(move_, board.find_best_move());
+    }
+}
+
+#[cfg(test)]
+mod alpha_beta_tests {
+    use super::*;
+
+    fn assert_same_as_minimax(board: &mut Board) {
+        let (move_, score) = board.find_best_move_scored();
+        let scores = reference_root_scores(board);
+        let best = scores
+            .iter()
+            .map(|&(_, s)| s)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        assert_eq!(score, best);
+        assert!(scores.contains(&(move_, best)));
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let mut rates = test_rates();
+        rates.max_depth = 5;
+        let mut board = Board::new(1, rates);
+        assert_same_as_minimax(&mut board);
+    }
+
+    #[test]
+    fn test_tactical_position() {
+        let mut rates = test_rates();
+        rates.max_depth = 4;
+        let mut board = Board::empty_with_rates(rates);
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(1, 3) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(4, 4) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(5, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(6, 6) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        assert_same_as_minimax(&mut board);
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        let mut board = Board::new(3, test_rates());
+        let exact = board.rate(Color::White);
+        assert_eq!(
+            board.rate_window(Color::White, exact - 1.0, exact + 1.0),
+            exact
+        );
+        assert!(board.rate_window(Color::White, exact + 1.0, exact + 2.0) <= exact + 1.0);
+        assert!(board.rate_window(Color::White, exact - 2.0, exact - 1.0) >= exact - 1.0);
+        assert_eq!(board.rate(Color::Black), -exact);
+    }
+}
+
+#[cfg(test)]
+mod iterative_deepening_tests {
+    use super::*;
+
+    #[test]
+    fn test_same_as_fixed_depth() {
+        let mut board = Board::new(3, test_rates());
+        let (_, score) = board.find_best_move_scored();
+        let move_ = board.find_best_move();
+        assert_eq!(board.rate_root_move(move_), score);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut rates = test_rates();
+        rates.max_depth = 20;
+        let mut board = Board::new(3, rates);
+
+        let start = Instant::now();
+        let move_ = board.find_best_move_timed(Duration::ZERO);
+        assert!(board.is_valid_move(move_));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(board.rating.max_depth, 20);
+    }
+
+    #[test]
+    fn test_previous_best_first() {
+        let mut board = Board::new(3, test_rates());
+        let moves = board.find_all_current_moves();
+        let (_, score) = board.find_best_move_scored();
+        for first in moves {
+            assert_eq!(board.find_best_move_scored_from(Some(first)).1, score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod zobrist_tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for m in moves {
+            let Move { from, to, .. } = Move::try_from(*m).unwrap();
+            let move_ = board
+                .find_all_current_moves()
+                .into_iter()
+                .find(|m| (m.from, m.to) == (from, to))
+                .unwrap();
+            board.push(move_).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut board = Board::new(3, test_rates());
+        let start = board.zobrist_hash();
+        for move_ in board.find_all_current_moves() {
+            board.push_unsafe(move_);
+            assert_ne!(board.zobrist_hash(), start);
+            assert_eq!(board.zobrist_hash(), board.compute_zobrist_hash());
+            board.pop();
+            assert_eq!(board.zobrist_hash(), start);
+        }
+    }
+
+    #[test]
+    fn test_transposition() {
+        let mut first = Board::new(3, test_rates());
+        play(
+            &mut first,
+            &["C1 -> D2", "F8 -> E7", "C7 -> D8", "F6 -> E5"],
+        );
+        let mut second = Board::new(3, test_rates());
+        play(
+            &mut second,
+            &["C7 -> D8", "F6 -> E5", "C1 -> D2", "F8 -> E7"],
+        );
+
+        assert_eq!(first.board, second.board);
+        assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+        assert_eq!(first.zobrist_hash(), first.compute_zobrist_hash());
+    }
+
+    #[test]
+    fn test_capture_and_null_move() {
+        let mut board = Board::empty_with_rates(test_rates());
+        board
+            .add_piece(2, 2, PlayersPiece::new(Color::White, Piece::Pawn))
+            .unwrap();
+        board
+            .add_piece(3, 3, PlayersPiece::new(Color::Black, Piece::Pawn))
+            .unwrap();
+        let start = board.zobrist_hash();
+
+        let capture = board.find_all_current_moves()[0];
+        assert!(capture.kill.is_some());
+        board.push_unsafe(capture);
+        assert_eq!(board.zobrist_hash(), board.compute_zobrist_hash());
+
+        board.apply_null_move();
+        assert_eq!(board.zobrist_hash(), board.compute_zobrist_hash());
+        board.undo_null_move();
+        board.pop();
+        assert_eq!(board.zobrist_hash(), start);
+    }
+}
+
+#[cfg(test)]
+mod transposition_tests {
+    use super::*;
+
+    fn entry(hash: u64, depth: u8, flag: TTFlag) -> TTEntry {
+        TTEntry {
+            hash,
+            depth,
+            score: depth as f32,
+            flag,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let mut table = TranspositionTable::new(4);
+        assert_eq!(table.entries.len(), 16);
+        assert_eq!(table.probe(3), None);
+
+        table.store(entry(3, 2, TTFlag::Exact));
+        assert_eq!(table.probe(3), Some(entry(3, 2, TTFlag::Exact)));
+        // same slot, other position
+        assert_eq!(table.probe(3 + 16), None);
+
+        // shallower result of the same position doesn't replace the deeper one
+        table.store(entry(3, 1, TTFlag::LowerBound));
+        assert_eq!(table.probe(3), Some(entry(3, 2, TTFlag::Exact)));
+        table.store(entry(3 + 16, 1, TTFlag::UpperBound));
+        assert_eq!(table.probe(3), None);
+        assert_eq!(
+            table.probe(3 + 16),
+            Some(entry(3 + 16, 1, TTFlag::UpperBound))
+        );
+
+        table.clear();
+        assert_eq!(table.probe(3 + 16), None);
+    }
+
+    /// Searches with a fresh table and a table warmed up by a search of the same position,
+    /// both have to find a move `reference_minimax` rates the best
+    fn assert_same_as_without_table(board: &mut Board) {
+        let start = board.clone();
+        let scores = reference_root_scores(board);
+        let best = scores
+            .iter()
+            .map(|&(_, s)| s)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        let mut table = TranspositionTable::new(16);
+        for _ in 0..2 {
+            let move_ = board.find_best_move_with(&mut table);
+            assert!(scores.contains(&(move_, best)));
+            assert_eq!(*board, start);
+        }
+        assert!(table.entries.iter().any(Option::is_some));
+    }
+
+    #[test]
+    fn test_same_best_move_as_without_table() {
+        let mut rates = test_rates();
+        rates.max_depth = 5;
+        let mut board = Board::new(2, rates);
+        assert_same_as_without_table(&mut board);
+
+        // a middlegame position, where more lines transpose into each other
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        board.random_game(8, &mut rng);
+        assert!(board.game_in_progress());
+        assert_same_as_without_table(&mut board);
+    }
+
+    /// White is a pawn up, so the score depends on the material rates
+    fn pawn_up(rates: RateConfig) -> Board {
+        let mut board = Board::empty_with_rates(rates);
+        let pawn = |color| PlayersPiece::new(color, Piece::Pawn);
+        board
+            .set_position(&[
+                (0, 0, pawn(Color::White)),
+                (0, 2, pawn(Color::White)),
+                (7, 7, pawn(Color::Black)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_other_rates_dont_reuse_scores() {
+        let rates = RateConfig {
+            pieces: PieceRates {
+                pawn: 5.0,
+                ..test_rates().pieces
+            },
+            ..test_rates()
+        };
+
+        SEARCH_TABLES.set(SearchTables::new());
+        let fresh = pawn_up(rates).rate(Color::White);
+
+        SEARCH_TABLES.set(SearchTables::new());
+        let other = pawn_up(test_rates()).rate(Color::White);
+        assert_ne!(other, fresh);
+        assert_eq!(pawn_up(rates).rate(Color::White), fresh);
+    }
+
+    #[test]
+    fn test_other_evaluator_doesnt_reuse_scores() {
+        #[derive(Debug)]
+        struct Constant;
+
+        impl Evaluator for Constant {
+            fn name(&self) -> &'static str {
+                "Constant"
+            }
+
+            fn evaluate(&self, _: &Board) -> f32 {
+                0.5
+            }
+        }
+
+        let mut board = pawn_up(test_rates());
+        SEARCH_TABLES.set(SearchTables::new());
+        let default = board.rate(Color::White);
+
+        board.set_evaluator(&Constant);
+        let constant = board.rate(Color::White);
+        assert_ne!(constant, default);
+        assert_eq!(constant.abs(), 0.5);
+
+        board.set_evaluator(&DefaultEvaluator);
+        assert_eq!(board.rate(Color::White), default);
+    }
+
+    #[test]
+    fn test_thread_table_untouched() {
+        let mut board = Board::new(2, test_rates());
+        board.find_best_move_with(&mut TranspositionTable::new(12));
+        SEARCH_TABLES.with_borrow(|tables| {
+            assert_eq!(
+                tables.transpositions.entries.len(),
+                1 << TranspositionTable::DEFAULT_SIZE_LOG2
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod principal_variation_tests {
+    use super::*;
+
+    #[test]
+    fn test_legal_line() {
+        let mut rates = test_rates();
+        rates.max_depth = 3;
+        let mut board = Board::new(3, rates);
+        let start = board.clone();
+
+        let line = board.principal_variation();
+        assert_eq!(board, start);
+        assert_eq!(line[0], board.find_best_move());
+        assert!(line.len() >= 2);
+
+        let mut turns = 0;
+        for &move_ in &line {
+            assert!(board.is_valid_move(move_), "Illegal {}", move_);
+            if board.last_player() != Some(move_.color) {
+                turns += 1;
+            }
+            board.push_unsafe(move_);
+        }
+        assert!(turns <= 3);
+    }
+
+    #[test]
+    fn test_follows_captures() {
+        let mut board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 0) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(1, 1) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        *board.get_mut(7, 7) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+        board.clear_history();
+
+        let line = board.principal_variation();
+        assert_eq!(line[0].to_string(), "A1 -> C3 # B2 Pawn");
+        assert_eq!(line[1].to_string(), "C3 -> E5 # D4 Pawn");
+        assert_eq!(
+            board.display_principal_variation().split(' ').next(),
+            Some("1x10x19")
+        );
+    }
+}
+
+#[cfg(test)]
+mod ordering_benchmark_tests {
+    use super::*;
+
+    fn nodes_searched(use_move_ordering: bool) -> u64 {
+        let mut rates = benchmark_rates();
+        rates.use_move_ordering = use_move_ordering;
+
+        SEARCH_TABLES.set(SearchTables::new());
+        Board::new(3, rates).find_best_move();
+        SEARCH_TABLES.with_borrow(|tables| tables.nodes)
+    }
+
+    #[test]
+    fn test_fewer_nodes() {
+        let ordered = nodes_searched(true);
+        let unordered = nodes_searched(false);
+        assert!(ordered > 0);
+        assert!(
+            ordered * 2 <= unordered,
+            "{} vs {} nodes",
+            ordered,
+            unordered
+        );
+    }
+
+    #[test]
+    fn test_unordered_keeps_moves() {
+        let mut rates = test_rates();
+        rates.use_move_ordering = false;
+        let mut board = Board::new(3, rates);
+        let moves = board.find_all_current_moves();
+        assert_eq!(board.find_all_current_moves_ordered(0), moves);
+    }
+}
+
+#[cfg(test)]
+mod legal_moves_from_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board: Board = Board::new(3, test_rates());
+        assert_eq!(board.legal_moves_from(2, 2).len(), 2);
+        // blocked by own pieces, empty square, opponent's piece
+        assert!(board.legal_moves_from(1, 1).is_empty());
+        assert!(board.legal_moves_from(3, 3).is_empty());
+        assert!(board.legal_moves_from(5, 1).is_empty());
+        assert!(board.legal_moves_from(9, 9).is_empty());
+    }
+
+    #[test]
+    fn test_forced_capture() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(0, 6) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        assert!(!board.find_moves(0, 6, None).unwrap().is_empty());
+        assert!(board.legal_moves_from(0, 6).is_empty());
+
+        let captures = board.legal_moves_from(2, 2);
+        assert_eq!(captures.len(), 1);
+        assert!(captures[0].kill.is_some());
+    }
+}
+
+#[cfg(test)]
+mod board_parse_move_tests {
+    use super::*;
+
+    #[test]
+    fn test_short_notation() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(2, 2) = Some(PlayersPiece::new(Color::White, Piece::Pawn));
+        *board.get_mut(3, 3) = Some(PlayersPiece::new(Color::Black, Piece::Pawn));
+
+        let capture = board.parse_move("c3xe5").unwrap();
+        assert_eq!(
+            capture.kill.map(|k| (k.row, k.col, k.piece)),
+            Some((3, 3, Piece::Pawn))
+        );
+        assert_eq!((capture.piece, capture.color), (Piece::Pawn, Color::White));
+        assert_eq!(board.parse_move(" C3xE5 "), Ok(capture));
+    }
+
+    #[test]
+    fn test_errors() {
+        use ParseMoveError::*;
+
+        let board: Board = Board::new(3, test_rates());
+        assert_eq!(board.parse_move("C3 D4"), Err(BadFormat));
+        assert_eq!(board.parse_move("C3-D"), Err(BadFormat));
+        assert_eq!(board.parse_move("C3-I9"), Err(OutOfBounds));
+        assert_eq!(board.parse_move("D4-E5"), Err(NoPieceAtSource));
+        assert_eq!(board.parse_move("C3-E5"), Err(IllegalMove));
+        assert_eq!(board.parse_move("C3xD4"), Err(IllegalMove));
+        assert_eq!(board.parse_move("F2-E1"), Err(IllegalMove));
+        assert!(board.parse_move("C3-D4").is_ok());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for _ in 0..5 {
+            let mut board: Board = Board::new(3, test_rates());
+            while board.winner().is_none() && board.moves.len() < 80 {
+                let moves = board.find_all_current_moves();
+                for &move_ in &moves {
+                    assert_eq!(board.parse_move(&move_.to_string()), Ok(move_));
+
+                    let (from, to) = (format_pos(move_.from), format_pos(move_.to));
+                    let separator = if move_.kill.is_some() { "x" } else { "-" };
+                    let short = format!("{}{}{}", from, separator, to);
+                    assert_eq!(board.parse_move(&short), Ok(move_));
+                }
+                board.push(moves[rng.gen_range(0..moves.len())]).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_against_engine() {
+        let mut board = Board::new(3, test_rates());
+        let mut out = Vec::new();
+        board
+            .play_against_engine(Color::White, "C3-E5\nC3-D4\nquit\n".as_bytes(), &mut out)
+            .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&ParseMoveError::IllegalMove.to_string()));
+        assert!(out.contains("Player Black played"));
+        assert_eq!(board.moves.len(), 2);
+
+        // thinking about the answer to the expected reply
+        assert!(board.ponder.job.is_some());
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        let mut board = Board::new(3, test_rates());
+        let mut out = Vec::new();
+        board
+            .play_against_engine(Color::White, "draw\nC3-D4\n".as_bytes(), &mut out)
+            .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("accepted the draw"));
+        assert!(board.moves.is_empty());
+        assert_eq!(
+            board.game_status(),
+            GameStatus::Drawn(DrawReason::Agreement)
+        );
+
+        // a pawn up, the engine plays on
+        let mut board = Board::new(2, test_rates());
+        *board.get_mut(0, 0) = None;
+        let mut out = Vec::new();
+        board
+            .play_against_engine(Color::White, "draw\nquit\n".as_bytes(), &mut out)
+            .unwrap();
+
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("declined the draw"));
+        assert!(board.game_in_progress());
+    }
+}
+
+#[cfg(test)]
+mod fen_tests {
+    use super::*;
+
+    fn round_trip(fen: &str) -> Board {
+        let board = Board::from_fen(fen, test_rates()).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        board
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::new(3, test_rates());
+        let fen = board.to_fen();
+        assert_eq!(
+            fen,
+            "W:W1,2,3,4,5,6,7,8,9,10,11,12:B21,22,23,24,25,26,27,28,29,30,31,32:0:1"
+        );
+        assert_eq!(round_trip(&fen).board, board.board);
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let empty = round_trip("W:W:B:0:1");
+        assert_eq!(empty.all_current_pieces().count(), 0);
+
+        let queens = round_trip("W:WK1,K2,K3:BK30,K31,K32:0:40");
+        assert!(queens
+            .board
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|p| p.piece == Piece::Queen));
+
+        let black = round_trip("B:W9,K12:B21:0:3");
+        assert_eq!(black.current_player(), Color::Black);
+        assert_eq!(black.find_all_current_moves()[0].color, Color::Black);
+        assert!(black.moves.is_empty());
+        assert!(black.hash_history.is_empty());
+        assert_eq!(black.hash, black.compute_zobrist_hash());
+
+        let late = round_trip("B:W5:B28:0:123");
+        assert_eq!(late.turn, 245);
+    }
+
+    #[test]
+    fn test_fifty_move_clock() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        *board.get_mut(0, 2) = Some(PlayersPiece::new(Color::White, Piece::Queen));
+        *board.get_mut(7, 5) = Some(PlayersPiece::new(Color::Black, Piece::Queen));
+        for move_ in ["A3 -> B4", "H6 -> G5", "B4 -> C5"] {
+            let mut move_ = Move::try_from(move_).unwrap();
+            move_.piece = Piece::Queen;
+            move_.color = board.current_player();
+            board.push(move_).unwrap();
+        }
+
+        let loaded = round_trip(&board.to_fen());
+        assert_eq!(loaded.check_fifty_move_clock(), 3);
+
+        let near_draw = round_trip("W:WK1:BK32:49:70");
+        assert!(!near_draw.is_draw());
+        let mut near_draw = near_draw;
+        near_draw
+            .push(near_draw.find_all_current_moves()[0])
+            .unwrap();
+        assert_eq!(near_draw.draw_reason(), Some(DrawReason::FiftyMove));
+
+        let old = Board::from_fen("W:WK1:BK32:70", test_rates()).unwrap();
+        assert_eq!(old.check_fifty_move_clock(), 0);
+        assert_eq!(old.to_fen(), "W:WK1:BK32:0:70");
+    }
+
+    #[test]
+    fn test_played_game() {
+        let mut board = Board::new(3, test_rates());
+        while board.winner().is_none() && board.moves.len() < 30 {
+            board.push(board.find_all_current_moves()[0]).unwrap();
+        }
+        let loaded = round_trip(&board.to_fen());
+        assert_eq!(loaded.board, board.board);
+        assert_eq!(loaded.current_player(), board.current_player());
+    }
+
+    #[test]
+    fn test_black_to_move_history() {
+        let mut board = Board::from_fen("B:W9:B21:3", test_rates()).unwrap();
+        assert_eq!(board.undo(), None);
+
+        let move_ = board.find_all_current_moves()[0];
+        board.push(move_).unwrap();
+        assert_eq!(board.current_player(), Color::White);
+        assert_eq!(board.undo(), Some(move_));
+        assert_eq!(board.current_player(), Color::Black);
+        assert!(!board.print_algebraic_game_log().contains("--"));
+    }
+
+    #[test]
+    fn test_errors() {
+        use FenError::*;
+
+        let from_fen = |s| Board::from_fen(s, test_rates()).map(|_| ());
+        assert_eq!(from_fen(""), Err(BadFormat));
+        assert_eq!(from_fen("W:W1:B32"), Err(BadFormat));
+        assert_eq!(from_fen("W:B32:W1:1"), Err(BadFormat));
+        assert_eq!(from_fen("W:W1,x:B32:1"), Err(BadFormat));
+        assert_eq!(from_fen("X:W1:B32:1"), Err(BadSide));
+        assert_eq!(from_fen("W:W33:B32:1"), Err(BadSquare));
+        assert_eq!(from_fen("W:W1:B32:0"), Err(BadMoveNumber));
+        assert_eq!(from_fen("W:W1:B32:x:1"), Err(BadClock));
+        assert_eq!(from_fen("W:W1:B32:0:1:2"), Err(BadFormat));
+        assert_eq!(
+            from_fen("W:W1:B1:1"),
+            Err(Placement(PlacementError::SameSquare { row: 0, col: 0 }))
+        );
+    }
+}
+
+#[cfg(test)]
+mod pdn_tests {
+    use super::*;
+
+    fn random_game(seed: u64) -> Board {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut board = Board::new(3, test_rates());
+        while board.winner().is_none() {
+            let moves = board.find_all_current_moves();
+            board.push(moves[rng.gen_range(0..moves.len())]).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn test_sample_game() {
+        let pdn = "[Event \"Club night\"]\n[White \"Anna\"]\n[Black \"Petr\"]\n\
+                   [Result \"*\"]\n\n1. 9-13 {quiet start} 22-18\n2. 13x22 *\n";
+        let game = Game::from_pdn(pdn, test_rates()).unwrap();
+        assert_eq!(game.metadata.event, "Club night");
+        assert_eq!(game.metadata.white, "Anna");
+        assert_eq!(game.metadata.black, "Petr");
+        assert_eq!(game.metadata.result, GameResult::Unfinished);
+        assert!(game.metadata.tags.is_empty());
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.moves[0].to_string(), "C1 -> D2");
+        assert_eq!(
+            game.to_pdn(),
+            "[Event \"Club night\"]\n[Date \"????.??.??\"]\n[White \"Anna\"]\n\
+             [Black \"Petr\"]\n[Result \"*\"]\n\n1. 9-13 22-18 2. 13x22 *\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_complete_games() {
+        for seed in 0..3 {
+            let played = random_game(seed);
+            let meta = GameMetadata {
+                event: "Test".to_string(),
+                date: "2024.01.0?".to_string(),
+                white: "Engine".to_string(),
+                black: format!("Random {}", seed),
+                result: GameResult::of(&played),
+                tags: vec![("Round".to_string(), seed.to_string())],
+            };
+
+            let pdn = Board::to_pdn(&played.moves, &meta);
+            let (board, moves) = Board::from_pdn(&pdn, test_rates()).unwrap();
+            assert_eq!(moves, played.moves);
+            assert_eq!(board.board, played.board);
+            assert_eq!(board.winner(), played.winner());
+
+            let game = Game::from_pdn(&pdn, test_rates()).unwrap();
+            assert_eq!(game.metadata, meta);
+            assert_eq!(game.to_pdn(), pdn);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let from_pdn = |s| Board::from_pdn(s, test_rates()).map(|_| ());
+        assert_eq!(from_pdn("[White Anna]\n1. 9-13"), Err(PdnError::BadHeader));
+        assert_eq!(from_pdn("1. 9-13 {open"), Err(PdnError::UnclosedComment));
+        assert_eq!(from_pdn("[Result \"2-0\"]"), Err(PdnError::BadResult));
+        assert_eq!(
+            from_pdn("1. 9-14"),
+            Err(PdnError::BadMove(ParseError::IllegalMove))
+        );
+        assert_eq!(
+            from_pdn("1. 9-"),
+            Err(PdnError::BadMove(ParseError::BadFormat))
+        );
+    }
+}
+
+#[cfg(test)]
+mod hash_history_tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut board = Board::new(3, test_rates());
+        let start = board.zobrist_hash();
+        let move_ = board.find_all_current_moves()[0];
+
+        board.push(move_).unwrap();
+        board.apply_null_move();
+        assert_eq!(board.hash_history.len(), 2);
+        assert_eq!(board.hash_history[0], start);
+
+        board.undo_null_move();
+        board.pop();
+        assert!(board.hash_history.is_empty());
+        assert_eq!(board.position_hashes(), [start]);
+    }
+
+    #[test]
+    fn test_third_repetition() {
+        let mut board = Board::empty_with_rates(test_rates());
+        board
+            .add_piece(0, 2, PlayersPiece::new(Color::White, Piece::Queen))
+            .unwrap();
+        board
+            .add_piece(7, 5, PlayersPiece::new(Color::Black, Piece::Queen))
+            .unwrap();
+
+        let cycle = ["A3-B4", "H6-G5", "B4-A3", "G5-H6"];
+        for (i, m) in cycle.iter().cycle().take(8).enumerate() {
+            assert!(!board.is_draw_by_repetition(), "Draw after {} moves", i);
+            board.push(board.parse_move(m).unwrap()).unwrap();
+        }
+        assert!(board.is_draw_by_repetition());
+        assert!(!board.game_in_progress());
+        assert_eq!(board.position_hashes().len(), 9);
+    }
+}
+
+#[cfg(test)]
+mod draw_moves_tests {
+    use super::*;
+
+    fn queens(draw_moves: usize) -> Board {
+        let mut rates = test_rates();
+        rates.draw_after_turns = draw_moves;
+        let mut board = Board::empty_with_rates(rates);
+        board
+            .add_piece(0, 2, PlayersPiece::new(Color::White, Piece::Queen))
+            .unwrap();
+        board
+            .add_piece(7, 5, PlayersPiece::new(Color::Black, Piece::Queen))
+            .unwrap();
+        board
+            .add_piece(2, 0, PlayersPiece::new(Color::White, Piece::Pawn))
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_draw_fires() {
+        let mut board = queens(50);
+        for i in 0..3 {
+            assert_eq!(board.moves_since_capture(), i);
+            board
+                .push(board.parse_move(["A3-B4", "H6-G5", "B4-A3"][i]).unwrap())
+                .unwrap();
+        }
+
+        // as if the queens went on for a long time without repeating
+        board.fifty_move_clock = 49;
+        assert!(board.game_in_progress());
+        board.push(board.parse_move("G5-H6").unwrap()).unwrap();
+
+        assert_eq!(board.moves_since_capture(), 50);
+        assert_eq!(board.winner(), None);
+        assert_eq!(
+            board.game_status(),
+            GameStatus::Drawn(DrawReason::FiftyMove)
+        );
+    }
+
+    #[test]
+    fn test_capture_resets() {
+        let mut board = queens(50);
+        board
+            .add_piece(1, 3, PlayersPiece::new(Color::Black, Piece::Pawn))
+            .unwrap();
+        board.fifty_move_clock = 10;
+
+        board.push(board.parse_move("A3xC5").unwrap()).unwrap();
+        assert_eq!(board.moves_since_capture(), 0);
+        board.push(board.parse_move("H6-G5").unwrap()).unwrap();
+        assert_eq!(board.moves_since_capture(), 1);
+    }
+
+    #[test]
+    fn test_search_sees_draw() {
+        // white is a pawn up, but every line ends in the draw
+        let mut board = queens(1);
+        assert!(board.rate_current_board() > 0.0);
+        assert_eq!(board.find_best_move_scored().1, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod board_size_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_pieces() {
+        let board = Board::<10>::new(4, test_rates());
+        assert_eq!(board.all_players_pieces(Color::White).count(), 20);
+        assert_eq!(board.all_players_pieces(Color::Black).count(), 20);
+        assert_eq!(board.compute_zobrist_hash(), board.hash);
+
+        let board = Board::<6>::new(2, test_rates());
+        assert_eq!(board.all_players_pieces(Color::White).count(), 6);
+        assert_eq!(board.all_players_pieces(Color::Black).count(), 6);
+    }
+
+    #[test]
+    fn test_moves_respect_size() {
+        let board = Board::<10>::new(4, test_rates());
+        // every white pawn on the front row can step forward, except the one on the edge
+        assert_eq!(board.find_all_current_moves().len(), 9);
+        assert!(board.find_all_current_moves().iter().all(|m| m.to.0 == 4));
+
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board
+            .add_piece(0, 0, PlayersPiece::new(Color::White, Piece::Queen))
+            .unwrap();
+        let moves = board.legal_moves_from(0, 0);
+        assert_eq!(moves.len(), 9);
+        assert!(board.is_valid_move(*moves.iter().find(|m| m.to == (9, 9)).unwrap()));
+    }
+
+    #[test]
+    fn test_promotion_on_last_row() {
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board
+            .add_piece(7, 1, PlayersPiece::new(Color::White, Piece::Pawn))
+            .unwrap();
+        board
+            .add_piece(2, 2, PlayersPiece::new(Color::Black, Piece::Pawn))
+            .unwrap();
+        assert_eq!(
+            board.add_piece(9, 1, PlayersPiece::new(Color::White, Piece::Pawn)),
+            Err(BoardError::PawnOnLastRow)
+        );
+
+        let step = |board: &Board<10>, from: (u8, u8), to: (u8, u8)| {
+            *board
+                .legal_moves_from(from.0, from.1)
+                .iter()
+                .find(|m| m.to == to)
+                .unwrap()
+        };
+
+        // row 7 is the last one only on the standard board
+        board.push(step(&board, (7, 1), (8, 0))).unwrap();
+        assert_eq!(board.piece_at(8, 0).unwrap().piece, Piece::Pawn);
+        board.push(step(&board, (2, 2), (1, 1))).unwrap();
+        board.push(step(&board, (8, 0), (9, 1))).unwrap();
+        assert_eq!(board.piece_at(9, 1).unwrap().piece, Piece::Queen);
+
+        board.pop();
+        assert_eq!(board.piece_at(8, 0).unwrap().piece, Piece::Pawn);
+        assert_eq!(board.compute_zobrist_hash(), board.hash);
+    }
+
+    #[test]
+    fn test_display() {
+        let board = Board::<10>::new(4, test_rates());
+        let shown = board.to_string();
+        assert!(shown.contains("1 2 3 4 5 6 7 8 9 10"));
+        // turn, column numbers and a line per row
+        assert_eq!(shown.lines().count(), 12);
+        assert!(!shown.contains("Rating"));
+        assert!(Board::<8>::new(3, test_rates())
+            .to_string()
+            .contains("Rating"));
+    }
+}
+
+#[cfg(test)]
+mod international_tests {
+    use super::*;
+
+    fn pawn(color: Color) -> PlayersPiece {
+        PlayersPiece::new(color, Piece::Pawn)
+    }
+
+    /// The pawn on A1 can take two pieces, the one on A7 only one
+    fn two_captures(variant: Variant) -> Board<10> {
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board.variant = variant;
+        board
+            .set_position(&[
+                (0, 0, pawn(Color::White)),
+                (0, 6, pawn(Color::White)),
+                (1, 1, pawn(Color::Black)),
+                (3, 1, pawn(Color::Black)),
+                (1, 5, pawn(Color::Black)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_new_international() {
+        let board = Board::new_international(test_rates());
+        assert_eq!(board.variant, Variant::International);
+        assert_eq!(board.all_players_pieces(Color::White).count(), 20);
+        assert_eq!(board.all_players_pieces(Color::Black).count(), 20);
+    }
+
+    #[test]
+    fn test_longest_capture_is_forced() {
+        assert_eq!(
+            two_captures(Variant::Standard)
+                .find_all_current_moves()
+                .len(),
+            2
+        );
+
+        let mut board = two_captures(Variant::International);
+        let moves = board.find_all_current_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].from, moves[0].to), ((0, 0), (2, 2)));
+
+        board.push(moves[0]).unwrap();
+        assert_eq!(board.current_player(), Color::White);
+        let moves = board.find_all_current_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].from, moves[0].to), ((2, 2), (4, 0)));
+    }
+}
+
+#[cfg(test)]
+mod quiescence_tests {
+    use super::*;
+
+    /// Moving the pawn from C7 to D6 hands it to the black pawn on E5
+    fn hanging_pawn(quiescence_depth: usize) -> Board {
+        let rates = RateConfig {
+            kills: KillRates {
+                pawn: 0.0,
+                queen: 0.0,
+            },
+            max_depth: 1,
+            quiescence_depth,
+            ..test_rates()
+        };
+        let mut board = Board::empty_with_rates(rates);
+        board
+            .set_position(&[
+                (0, 0, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (2, 6, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (4, 4, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        board
+    }
+
+    fn rate_after(board: &mut Board, move_: &str) -> f32 {
+        let move_ = board.parse_move(move_).unwrap();
+        board.with_move(move_, |board| {
+            board.rating.max_depth = 0;
+            board.rate(Color::White)
+        })
+    }
+
+    #[test]
+    fn test_sees_past_horizon() {
+        let mut board = hanging_pawn(0);
+        assert_eq!(
+            rate_after(&mut board, "C7-D6"),
+            rate_after(&mut board, "A1-B2")
+        );
+
+        let mut board = hanging_pawn(4);
+        assert!(rate_after(&mut board, "C7-D6") < rate_after(&mut board, "A1-B2"));
+    }
+
+    #[test]
+    fn test_avoids_hanging_pawn() {
+        let mut board = hanging_pawn(4);
+        let best = board.find_best_move();
+        assert_ne!(best, board.parse_move("C7-D6").unwrap());
+    }
+
+    #[test]
+    fn test_quiet_position_is_static() {
+        let mut board = hanging_pawn(4);
+        board.rating.max_depth = 0;
+        assert_eq!(board.rate(Color::White), board.rate_current_board());
+    }
+}
+
+#[cfg(test)]
+mod rate_config_builder_tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_one_field() {
+        let default = RateConfigBuilder::new().build().unwrap();
+        let config = RateConfigBuilder::new().kill_queen(12.0).build().unwrap();
+        assert_eq!(config.kills.queen, 12.0);
+        assert_eq!(
+            config,
+            RateConfig {
+                kills: KillRates {
+                    queen: 12.0,
+                    ..default.kills
+                },
+                ..default
+            }
+        );
+    }
+
+    #[test]
+    fn test_all_setters() {
+        let config = RateConfigBuilder::new()
+            .piece_pawn(2.0)
+            .piece_queen(5.0)
+            .position_pawn(0.5)
+            .position_queen(1.5)
+            .kill_pawn(4.0)
+            .kill_queen(8.0)
+            .win(100.0)
+            .mobility(0.2)
+            .draw_moves(60)
+            .max_depth(3)
+            .build()
+            .unwrap();
+        assert_eq!((config.pieces.pawn, config.pieces.queen), (2.0, 5.0));
+        assert_eq!((config.position.pawn, config.position.queen), (0.5, 1.5));
+        assert_eq!((config.kills.pawn, config.kills.queen), (4.0, 8.0));
+        assert_eq!((config.win, config.max_depth), (100.0, 3));
+        assert_eq!(
+            (config.position.mobility, config.draw_after_turns),
+            (0.2, 60)
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        assert_eq!(
+            RateConfigBuilder::new().win(-1.0).build(),
+            Err(ConfigError::NegativeWin)
+        );
+        assert_eq!(
+            RateConfigBuilder::new().max_depth(0).build(),
+            Err(ConfigError::ZeroDepth)
+        );
+    }
+}
+
+#[cfg(test)]
+mod search_stats_tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_are_filled() {
+        let mut board = Board::new(3, test_rates());
+        let (move_, stats) = board.find_best_move_with_stats();
+
+        assert!(stats.nodes_visited > 0);
+        assert!(stats.score.is_finite());
+        assert_eq!(stats.depth_reached, board.rating.max_depth);
+        assert_eq!(stats.pv[0], move_);
+        assert!(board.is_valid_move(move_));
+        assert!(stats.to_string().contains("depth 2"));
+    }
+
+    #[test]
+    fn test_same_move_as_find_best_move() {
+        let mut board = Board::new(3, test_rates());
+        SEARCH_TABLES.set(SearchTables::new());
+        let (move_, _) = board.find_best_move_with_stats();
+        SEARCH_TABLES.set(SearchTables::new());
+        assert_eq!(board.find_best_move(), move_);
+    }
+}
+
+#[cfg(test)]
+mod parallel_search_tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_sequential() {
+        let rates = RateConfig {
+            max_depth: 5,
+            ..test_rates()
+        };
+        let mut board = Board::new(3, rates);
+
+        SEARCH_TABLES.set(SearchTables::new());
+        let (_, score) = board.find_best_move_scored();
+        let parallel = board.find_best_move_parallel();
+        assert!(board.is_valid_move(parallel));
+
+        SEARCH_TABLES.set(SearchTables::new());
+        assert_eq!(
+            board.rate_root_move_window(parallel, f32::NEG_INFINITY),
+            score
+        );
+    }
+}
+
+#[cfg(test)]
+mod random_game_tests {
+    use super::*;
+
+    #[test]
+    fn test_random_move_is_legal() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let board: Board = Board::new(3, test_rates());
+        let move_ = board.random_move(&mut rng).unwrap();
+        assert!(board.is_valid_move(move_));
+
+        assert_eq!(
+            Board::<8>::empty_with_rates(test_rates()).random_move(&mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn test_random_games_terminate() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            let mut board: Board = Board::new(3, test_rates());
+            let moves = board.random_game(100, &mut rng);
+
+            assert!(board.is_game_over() || board.turn == 100);
+            assert_eq!(board.moves, moves);
+            assert_eq!(board.hash, board.compute_zobrist_hash());
+            board.assert_consistent();
+        }
+    }
+
+    #[test]
+    fn test_continues_from_position() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let mut board: Board = Board::new(3, test_rates());
+        let first = board.random_game(1, &mut rng);
+        let rest = board.random_game(usize::MAX, &mut rng);
+        assert_eq!(board.moves, [first, rest].concat());
+        assert!(board.is_game_over());
+    }
+}
+
+#[cfg(test)]
+mod piece_count_tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_count() {
+        let mut board: Board = Board::new(3, test_rates());
+        assert_eq!(board.piece_count(Color::White), (12, 0));
+        assert_eq!(board.total_piece_count(Color::Black), 12);
+
+        board
+            .remove_piece(0, 0)
+            .and_then(|_| board.add_piece(4, 4, PlayersPiece::new(Color::White, Piece::Queen)))
+            .unwrap();
+        assert_eq!(board.piece_count(Color::White), (11, 1));
+        assert_eq!(board.total_piece_count(Color::White), 12);
+    }
+}
+
+#[cfg(test)]
+mod piece_kind_tests {
+    use super::*;
+
+    /// Steps one square diagonally in any direction and never captures
+    #[derive(Debug)]
+    struct BackwardsMan;
+
+    impl PieceKind for BackwardsMan {
+        fn name(&self) -> &'static str {
+            "Backwards man"
+        }
+
+        fn symbol(&self) -> char {
+            'B'
+        }
+
+        fn value(&self) -> f32 {
+            2.0
+        }
+
+        fn generate_moves(
+            &self,
+            (row, col): (u8, u8),
+            color: Color,
+            board: &dyn BoardView,
+        ) -> Vec<Move> {
+            let mut moves = vec![];
+            for (dr, dc) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+                let (r, c) = (row as i8 + dr, col as i8 + dc);
+                if board.is_free(r, c) {
+                    moves.push(Move {
+                        from: (row, col),
+                        to: (r as u8, c as u8),
+                        piece: Piece::Custom(&BackwardsMan),
+                        kill: None,
+                        color,
+                        is_null: false,
+                    });
+                }
+            }
+            moves
+        }
+    }
+
+    #[test]
+    fn test_builtin_kinds_match_find_moves() {
+        let board: Board = Board::new(3, test_rates());
+        let moves = board.find_moves(2, 2, None).unwrap();
+        assert_eq!(PawnKind.generate_moves((2, 2), Color::White, &board), moves);
+        assert_eq!(Piece::Pawn.kind().name(), "Pawn");
+        assert_eq!(Piece::Queen.to_string(), "Queen");
+    }
+
+    #[test]
+    fn test_custom_piece() {
+        let man = PlayersPiece::new(Color::White, Piece::Custom(&BackwardsMan));
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board.set_piece(3, 3, Some(man)).unwrap();
+        board
+            .set_piece(7, 7, Some(PlayersPiece::new(Color::Black, Piece::Pawn)))
+            .unwrap();
+
+        let moves = board.find_all_current_moves();
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().any(|m| m.to == (2, 2)));
+        assert!(board.to_string().contains('B'));
+        assert_eq!(board.rate_current_board(), 2.0 - 1.0);
+
+        let hash = board.hash;
+        let back = *moves.iter().find(|m| m.to == (2, 2)).unwrap();
+        board.push(back).unwrap();
+        assert_eq!(board.piece_at(2, 2), Some(man));
+        assert_eq!(board.hash, board.compute_zobrist_hash());
+        board.pop();
+        assert_eq!(board.hash, hash);
+        assert_eq!(board.piece_at(3, 3), Some(man));
+    }
+
+    #[test]
+    fn test_custom_piece_on_international_board() {
+        let man = PlayersPiece::new(Color::White, Piece::Custom(&BackwardsMan));
+        let mut board = Board::<10>::empty_with_rates(test_rates());
+        board.set_piece(9, 9, Some(man)).unwrap();
+        board
+            .set_piece(0, 0, Some(PlayersPiece::new(Color::Black, Piece::Pawn)))
+            .unwrap();
+
+        let moves = board.find_all_current_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].from, moves[0].to), ((9, 9), (8, 8)));
+
+        board.set_piece(9, 9, None).unwrap();
+        board.set_piece(5, 5, Some(man)).unwrap();
+        assert_eq!(board.find_all_current_moves().len(), 4);
+        assert_eq!(
+            PawnKind.generate_moves((5, 5), Color::White, &board),
+            board.piece_moves((5, 5), Color::White, Piece::Pawn, None)
+        );
+    }
+}
+
+#[cfg(test)]
+mod validate_move_tests {
+    use super::*;
+
+    fn pawn(color: Color) -> PlayersPiece {
+        PlayersPiece::new(color, Piece::Pawn)
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (0, 0, pawn(Color::White)),
+                (2, 2, pawn(Color::White)),
+                (3, 3, pawn(Color::Black)),
+                (5, 5, pawn(Color::Black)),
+            ])
+            .unwrap();
+
+        // has to capture with the other pawn instead
+        let step = Move {
+            from: (0, 0),
+            to: (1, 1),
+            piece: Piece::Pawn,
+            kill: None,
+            color: Color::White,
+            is_null: false,
+        };
+        assert_eq!(board.validate_move(step), Err(MoveError::NotInLegalSet));
+        assert_eq!(
+            board.validate_move(Move {
+                from: (1, 1),
+                ..step
+            }),
+            Err(MoveError::NoPieceAtSource)
+        );
+        assert_eq!(
+            board.validate_move(Move {
+                from: (3, 3),
+                ..step
+            }),
+            Err(MoveError::WrongColor)
+        );
+
+        let capture = board.parse_move("C3xE5").unwrap();
+        assert_eq!(board.validate_move(capture), Ok(()));
+        assert_eq!(board.push(capture), Ok(None));
+        assert_eq!(
+            board.validate_move(step),
+            Err(MoveError::ContinuationRequired)
+        );
+    }
+
+    #[test]
+    fn test_rejected_push_keeps_board() {
+        let mut board: Board = Board::new(3, test_rates());
+        let before = board.clone();
+        let mut move_ = board.find_all_current_moves()[0];
+        move_.to = (4, 4);
+
+        assert!(board.push(move_).is_err());
+        assert_eq!(board, before);
+    }
+}
+
+#[cfg(test)]
+mod char_tests {
+    use super::*;
+
+    #[test]
+    fn test_color() {
+        assert_eq!(Color::from_char('w'), Some(Color::White));
+        assert_eq!(Color::from_char('B'), Some(Color::Black));
+        assert_eq!(Color::from_char('x'), None);
+        for color in [Color::White, Color::Black] {
+            assert_eq!(Color::from_char(color.to_char()), Some(color));
+        }
+    }
+
+    #[test]
+    fn test_piece() {
+        assert_eq!(Piece::from_char('P'), Some(Piece::Pawn));
+        assert_eq!(Piece::from_char('q'), Some(Piece::Queen));
+        assert_eq!(Piece::from_char('k'), None);
+        for piece in [Piece::Pawn, Piece::Queen] {
+            assert_eq!(piece.to_char(), piece.to_char().to_ascii_lowercase());
+            assert_eq!(Piece::from_char(piece.to_char()), Some(piece));
+        }
+    }
+
+    #[test]
+    fn test_fen_side() {
+        let board = Board::from_fen("w:W1:B32:1", test_rates()).unwrap();
+        assert_eq!(board.current_player(), Color::White);
+        assert!(board.to_fen().starts_with("W:"));
+    }
+}
+
+#[cfg(test)]
+mod mobility_tests {
+    use super::*;
+
+    fn board_with_white_pawn(col: u8) -> Board {
+        let mut rates = test_rates();
+        rates.endgame.mobility = 1.0;
+        let mut board = Board::empty_with_rates(rates);
+        board
+            .set_position(&[
+                (0, col, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (7, 7, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_mobility_score() {
+        let board = board_with_white_pawn(0);
+        assert_eq!(board.mobility_score(Color::White), 1);
+        assert_eq!(board.mobility_score(Color::Black), 1);
+        assert_eq!(board_with_white_pawn(2).mobility_score(Color::White), 2);
+
+        let start = Board::new(3, test_rates());
+        assert_eq!(start.mobility_score(Color::White), 7);
+        assert_eq!(start.mobility_score(Color::Black), 7);
+    }
+
+    #[test]
+    fn test_more_moves_rate_higher() {
+        let cornered = board_with_white_pawn(0).rate_current_board();
+        let free = board_with_white_pawn(2).rate_current_board();
+        assert_eq!(free - cornered, 1.0);
+    }
+
+    #[test]
+    fn test_moves_of_waiting_player() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        for turns in [5, 10, 15, 20] {
+            let mut board: Board = Board::new(3, test_rates());
+            board.random_game(turns, &mut rng);
+            let waiting = board.current_player().other();
+            assert_eq!(
+                board.find_all_moves(waiting),
+                board.after_null_move().find_all_current_moves()
+            );
+
+            let mut board = Board::new_international(test_rates());
+            board.random_game(turns, &mut rng);
+            let waiting = board.current_player().other();
+            assert_eq!(
+                board.find_all_moves(waiting),
+                board.after_null_move().find_all_current_moves()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+
+    fn played_game() -> Board {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut board = Board::new(3, test_rates());
+        board.random_game(20, &mut rng);
+        board
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let played = played_game();
+        let mut board = played.clone();
+
+        let moves = board.pop_all();
+        assert_eq!(moves, played.moves);
+        assert_eq!(board, Board::new(3, test_rates()));
+
+        board.replay(&moves).unwrap();
+        assert_eq!(board, played);
+    }
+
+    #[test]
+    fn test_jump_to_move() {
+        let mut board = played_game();
+        let moves = board.pop_all();
+        board.replay(&moves[..5]).unwrap();
+        assert_eq!(board.moves, moves[..5]);
+    }
+
+    #[test]
+    fn test_illegal_move_keeps_board() {
+        let mut board = played_game();
+        let moves = board.pop_all();
+        let before = board.clone();
+
+        let mut broken = moves.clone();
+        broken.swap(0, 1);
+        assert_eq!(board.replay(&broken), Err(MoveError::WrongColor));
+        assert_eq!(board, before);
+    }
+}
+
+#[cfg(test)]
+mod show_moves_tests {
+    use super::*;
+
+    // row D below the turn, rating and column lines
+    fn row_d(board: &Board) -> String {
+        board.to_string().lines().nth(6).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_shows_destinations() {
+        let mut board = Board::new(3, test_rates());
+        assert_eq!(board.show_moves_for(), None);
+        assert!(!row_d(&board).contains('P'));
+
+        board.set_show_moves_for(Some((2, 2)));
+        assert_eq!(board.show_moves_for(), Some((2, 2)));
+        let dimmed = PlayersPiece::new(Color::White, Piece::Pawn)
+            .to_string()
+            .dimmed()
+            .to_string();
+        let row = row_d(&board);
+        assert!(row.contains(&dimmed));
+        assert_eq!(row.matches('P').count(), 2);
+
+        board.set_show_moves_for(None);
+        assert!(!row_d(&board).contains('P'));
+    }
+}
+
+#[cfg(test)]
+mod killer_table_tests {
+    use super::*;
+
+    fn nodes_searched(use_killer_moves: bool) -> u64 {
+        let mut rates = benchmark_rates();
+        rates.use_killer_moves = use_killer_moves;
+
+        SEARCH_TABLES.set(SearchTables::new());
+        Board::new(3, rates).find_best_move();
+        SEARCH_TABLES.with_borrow(|tables| tables.nodes)
+    }
+
+    #[test]
+    fn test_fewer_nodes() {
+        let killers = nodes_searched(true);
+        let captures_first = nodes_searched(false);
+        assert!(
+            killers * 10 < captures_first * 9,
+            "{} vs {} nodes",
+            killers,
+            captures_first
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let moves = Board::<8>::new(3, test_rates()).find_all_current_moves();
+        let mut table = KillerTable::new();
+        table.store(3, moves[0]);
+        table.store(3, moves[1]);
+        table.store(3, moves[1]);
+        assert_eq!(table.get(3), [Some(moves[1]), Some(moves[0])]);
+        assert_eq!(table.get(2), [None; 2]);
+
+        table.store(KillerTable::MAX_DEPTH, moves[0]);
+        assert_eq!(table.get(KillerTable::MAX_DEPTH), [None; 2]);
+
+        table.clear();
+        assert_eq!(table.get(3), [None; 2]);
+    }
+}
+
+#[cfg(test)]
+mod history_table_tests {
+    use super::*;
+
+    fn nodes_searched(use_history_heuristic: bool) -> u64 {
+        let mut rates = benchmark_rates();
+        rates.use_history_heuristic = use_history_heuristic;
+
+        SEARCH_TABLES.set(SearchTables::new());
+        Board::new(3, rates).find_best_move();
+        SEARCH_TABLES.with_borrow(|tables| tables.nodes)
+    }
+
+    #[test]
+    fn test_fewer_nodes() {
+        let history = nodes_searched(true);
+        let without = nodes_searched(false);
+        assert!(history < without, "{} vs {} nodes", history, without);
+    }
+
+    #[test]
+    fn test_reset_by_search() {
+        let mut board = Board::new(3, test_rates());
+        let moves = board.find_all_current_moves();
+        SEARCH_TABLES.with_borrow_mut(|tables| tables.history.reward(moves[0], 100));
+
+        // far more than a shallow search can give back
+        board.find_best_move();
+        SEARCH_TABLES.with_borrow(|tables| assert!(tables.history.score(moves[0]) < 10_000));
+    }
+}
+
+#[cfg(test)]
+mod evaluation_breakdown_tests {
+    use super::*;
+
+    #[test]
+    fn test_breakdown_total_matches_rating() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut board = Board::new(3, test_rates());
+        for _ in 0..30 {
+            let breakdown = board.evaluation_breakdown();
+            assert!((breakdown.total - board.rate_current_board()).abs() < 1e-4);
+            for side in [breakdown.white, breakdown.black] {
+                let sum = side.piece_score + side.position_score + side.kill_score;
+                assert!((side.total - sum).abs() < 1e-4);
+            }
+            let Some(m) = board.random_move(&mut rng) else {
+                break;
+            };
+            board.push(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_breakdown_splits_material() {
+        let board = Board::new(3, test_rates());
+        let breakdown = board.evaluation_breakdown();
+        assert!(breakdown.white.piece_score > 0.0);
+        assert_eq!(breakdown.white.piece_score, breakdown.black.piece_score);
+    }
+}
+
+#[cfg(test)]
+mod endgame_tests {
+    use super::*;
+
+    fn board_with(pieces: &[(u8, u8, Color, Piece)], rates: RateConfig) -> Board {
+        let mut board: Board = Board::empty_with_rates(rates);
+        for &(row, col, color, piece) in pieces {
+            board
+                .set_piece(row, col, Some(PlayersPiece::new(color, piece)))
+                .unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn test_is_endgame() {
+        assert!(!Board::<8>::new(3, test_rates()).is_endgame());
+
+        let few = board_with(
+            &[
+                (0, 0, Color::White, Piece::Pawn),
+                (2, 2, Color::White, Piece::Pawn),
+                (7, 7, Color::Black, Piece::Pawn),
+            ],
+            test_rates(),
+        );
+        assert!(few.is_endgame());
+
+        let mut queens: Board = Board::new(3, test_rates());
+        assert!(!queens.is_endgame());
+        queens
+            .set_piece(3, 3, Some(PlayersPiece::new(Color::White, Piece::Queen)))
+            .unwrap();
+        assert!(!queens.is_endgame());
+        queens
+            .set_piece(4, 4, Some(PlayersPiece::new(Color::Black, Piece::Queen)))
+            .unwrap();
+        assert!(queens.is_endgame());
+    }
+
+    #[test]
+    fn test_endgame_rates_replace_position_rates() {
+        let mut rates = test_rates();
+        rates.endgame.queen = 1.0;
+        let pieces = [
+            (3, 3, Color::White, Piece::Queen),
+            (7, 1, Color::Black, Piece::Pawn),
+        ];
+
+        let endgame = board_with(&pieces, rates);
+        assert!(endgame.is_endgame());
+        assert_eq!(endgame.position_rates(), rates.endgame);
+        assert_eq!(endgame.evaluation_breakdown().white.position_score, 8.0);
+
+        // the same queen next to a full opponent army is rated by `position`
+        let mut middlegame: Board = Board::new(3, rates);
+        middlegame
+            .set_piece(3, 3, Some(PlayersPiece::new(Color::White, Piece::Queen)))
+            .unwrap();
+        assert!(!middlegame.is_endgame());
+        assert_eq!(middlegame.position_rates(), rates.position);
+    }
+}
+
+#[cfg(test)]
+mod null_move_pruning_tests {
+    use super::*;
+
+    // engine against itself, `None` when nobody won in `max_turns`
+    fn self_play(use_null_move: bool, max_turns: usize) -> Option<Color> {
+        let mut rates = test_rates();
+        rates.max_depth = 4;
+        rates.use_null_move = use_null_move;
+        let mut board = Board::new(3, rates);
+        while board.turn < max_turns && board.winner().is_none() && !board.is_draw() {
+            let move_ = board.find_best_move();
+            board.push(move_).unwrap();
+        }
+        board.winner()
+    }
+
+    #[test]
+    fn test_same_result_without_null_move() {
+        let with = self_play(true, 200);
+        assert!(with.is_some());
+        assert_eq!(with, self_play(false, 200));
+    }
+
+    #[test]
+    fn test_null_move_constructor() {
+        let pass = Move::null(Color::Black);
+        assert!(pass.is_null);
+        assert_eq!(pass.from, pass.to);
+
+        let board: Board = Board::new(3, test_rates());
+        let after = board.after_null_move();
+        assert_eq!(after.current_player(), Color::Black);
+        assert_eq!(after.last_move(), Some(Move::null(Color::White)));
+    }
+}
+
+#[cfg(test)]
+mod static_exchange_tests {
+    use super::*;
+
+    // `attacker` of White on C3 can take the piece on D4 and land on E5,
+    // where the pawn on F4 recaptures it, G3 keeps the capture from continuing
+    fn exchange(attacker: Piece, victim: Piece) -> (Board, Move) {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, attacker)),
+                (3, 3, PlayersPiece::new(Color::Black, victim)),
+                (5, 3, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (6, 2, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        let capture = board.find_all_current_moves()[0];
+        assert_eq!(capture.to, (4, 4));
+        (board, capture)
+    }
+
+    #[test]
+    fn test_losing_capture() {
+        let (mut board, capture) = exchange(Piece::Queen, Piece::Pawn);
+        let before = board.clone();
+        assert_eq!(board.static_exchange_evaluation(capture), 1.0 - 3.0);
+        assert_eq!(board, before);
+
+        let (winning, losing) = board.order_captures(vec![capture]);
+        assert!(winning.is_empty());
+        assert_eq!(losing, vec![capture]);
+    }
+
+    #[test]
+    fn test_winning_capture() {
+        let (mut board, capture) = exchange(Piece::Pawn, Piece::Queen);
+        assert_eq!(board.static_exchange_evaluation(capture), 3.0 - 1.0);
+    }
+
+    #[test]
+    fn test_capture_without_recapture() {
+        let (mut board, _) = exchange(Piece::Queen, Piece::Pawn);
+        board.set_piece(5, 3, None).unwrap();
+        let capture = board.find_all_current_moves()[0];
+        assert_eq!(board.static_exchange_evaluation(capture), 1.0);
+    }
+}
+
+#[cfg(test)]
+mod forced_sequence_tests {
+    use super::*;
+
+    fn board_with(pieces: &[(u8, u8, Color)]) -> Board {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        let pieces: Vec<_> = pieces
+            .iter()
+            .map(|&(r, c, color)| (r, c, PlayersPiece::new(color, Piece::Pawn)))
+            .collect();
+        board.set_position(&pieces).unwrap();
+        board
+    }
+
+    fn landings(sequences: &[Vec<Move>]) -> Vec<Vec<(u8, u8)>> {
+        let mut landings: Vec<_> = sequences
+            .iter()
+            .map(|sequence| sequence.iter().map(|m| m.to).collect::<Vec<_>>())
+            .collect();
+        landings.sort();
+        landings
+    }
+
+    #[test]
+    fn test_no_captures() {
+        let mut board: Board = Board::new(3, test_rates());
+        assert!(board.generate_forced_sequence().is_empty());
+    }
+
+    #[test]
+    fn test_t_shape() {
+        // after taking D4 the pawn on E5 can go on to the left or to the right
+        let mut board = board_with(&[
+            (2, 2, Color::White),
+            (3, 3, Color::Black),
+            (5, 3, Color::Black),
+            (5, 5, Color::Black),
+        ]);
+        let before = board.clone();
+        let sequences = board.generate_forced_sequence();
+        assert_eq!(board, before);
+        assert_eq!(
+            landings(&sequences),
+            vec![vec![(4, 4), (6, 2)], vec![(4, 4), (6, 6)]]
+        );
+    }
+
+    #[test]
+    fn test_branching() {
+        // the first capture already chooses between a short and a long sequence
+        let mut board = board_with(&[
+            (2, 2, Color::White),
+            (3, 1, Color::Black),
+            (3, 3, Color::Black),
+            (5, 5, Color::Black),
+        ]);
+        let sequences = board.generate_forced_sequence();
+        assert_eq!(
+            landings(&sequences),
+            vec![vec![(4, 0)], vec![(4, 4), (6, 6)]]
+        );
+        for sequence in sequences {
+            let mut played = board.clone();
+            played.replay(&sequence).unwrap();
+            assert_eq!(played.current_player(), Color::Black);
+        }
+    }
+}
+
+#[cfg(test)]
+mod flip_perspective_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position_is_symmetric() {
+        let board: Board = Board::new(3, test_rates());
+        assert_eq!(board.flip_perspective(), board);
+    }
+
+    #[test]
+    fn test_swaps_colors() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[(1, 3, PlayersPiece::new(Color::White, Piece::Queen))])
+            .unwrap();
+        board.apply_null_move();
+
+        let flipped = board.flip_perspective();
+        assert_eq!(flipped.current_player(), Color::White);
+        assert!(flipped.moves.is_empty());
+        assert_eq!(
+            flipped.piece_at(6, 4),
+            Some(PlayersPiece::new(Color::Black, Piece::Queen))
+        );
+        assert_eq!(flipped.hash, flipped.compute_zobrist_hash());
+    }
+
+    #[test]
+    fn test_involution() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut board: Board = Board::new(3, test_rates());
+        board.random_game(20, &mut rng);
+        board.clear_history();
+
+        assert_ne!(board.flip_perspective(), board);
+        assert_eq!(board.flip_perspective().flip_perspective(), board);
+    }
+}
+
+#[cfg(test)]
+mod parallel_engine_tests {
+    use super::*;
+
+    fn engine(threads: usize) -> ParallelEngine {
+        let mut rates = test_rates();
+        rates.max_depth = 3;
+        ParallelEngine::new(Board::new(3, rates), threads)
+    }
+
+    #[test]
+    fn test_finds_legal_move() {
+        let engine = engine(4);
+        let move_ = engine.find_best_move(Duration::from_millis(50));
+        assert!(engine.board.find_all_current_moves().contains(&move_));
+
+        assert!(!engine.table.is_empty());
+        SEARCH_TABLES.with_borrow(|tables| assert!(tables.shared.is_none()));
+    }
+
+    /// Entries searched deeper than the primary search can get, so stored by a helper
+    fn helper_entries(engine: &ParallelEngine) -> usize {
+        let max_depth = engine.board.rating.max_depth as u8;
+        engine
+            .table
+            .stripes
+            .iter()
+            .map(|stripe| {
+                let stripe = stripe.lock().unwrap();
+                let deeper = |entry: &&TTEntry| entry.depth > max_depth;
+                stripe.entries.iter().flatten().filter(deeper).count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_helpers_fill_the_table() {
+        let single = engine(1);
+        single.find_best_move(Duration::from_secs(60));
+        assert_eq!(helper_entries(&single), 0);
+
+        let parallel = engine(2);
+        parallel.find_best_move(Duration::from_secs(60));
+        assert!(helper_entries(&parallel) > 0);
+        assert!(parallel.table.len() > single.table.len());
+
+        // every thread reads and writes the same table
+        let table = Arc::new(SharedTable::new(8));
+        let entry = TTEntry {
+            hash: 42,
+            depth: 1,
+            score: 0.5,
+            flag: TTFlag::Exact,
+            best_move: None,
+        };
+        thread::scope(|scope| {
+            scope.spawn(|| table.store(entry));
+        });
+        assert_eq!(table.probe(42), Some(entry));
+    }
+
+    #[test]
+    fn test_single_thread_matches_search() {
+        SEARCH_TABLES.set(SearchTables::new());
+        let engine = engine(1);
+        let mut board = engine.board.clone();
+        let expected = board.find_best_move_timed(Duration::from_secs(60));
+
+        SEARCH_TABLES.set(SearchTables::new());
+        assert_eq!(engine.find_best_move(Duration::from_secs(60)), expected);
+    }
+}
+
+#[cfg(test)]
+mod ascii_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board: Board = Board::new(3, test_rates());
+        let grid = board.export_ascii(false);
+        assert_eq!(grid.lines().next(), Some("w.w.w.w."));
+        assert_eq!(grid.lines().nth(3), Some("........"));
+        assert_eq!(grid.lines().nth(7), Some(".b.b.b.b"));
+        assert!(grid.is_ascii() && !grid.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let mut board: Board = Board::new(3, test_rates());
+        board
+            .set_piece(3, 3, Some(PlayersPiece::new(Color::Black, Piece::Queen)))
+            .unwrap();
+        for _ in 0..10 {
+            board.random_game(4, &mut rng);
+            let mut expected = board.clone();
+            expected.clear_history();
+            let parsed = Board::from_ascii(&board.export_ascii(false), test_rates());
+            assert_eq!(parsed, Ok(expected));
+        }
+
+        let mut board_10: Board<10> = Board::new(4, test_rates());
+        board_10.clear_history();
+        let parsed = Board::<10>::from_ascii(&board_10.export_ascii(false), test_rates());
+        assert_eq!(parsed, Ok(board_10));
+    }
+
+    #[test]
+    fn test_errors() {
+        let parse = |s: &str| Board::<8>::from_ascii(s, test_rates()).err();
+        assert_eq!(parse("w.w.w.w.\n"), Some(ParseError::BadGrid));
+
+        let mut grid = ".".repeat(8) + "\n";
+        grid = grid.repeat(7) + "x.......\n";
+        assert_eq!(parse(&grid), Some(ParseError::BadGrid));
+
+        let light = ".w......\n".to_string() + &"........\n".repeat(7);
+        assert_eq!(
+            parse(&light),
+            Some(ParseError::Placement(PlacementError::LightSquare {
+                row: 0,
+                col: 1
+            }))
+        );
+    }
+}
+
+#[cfg(test)]
+mod game_tests {
+    use super::*;
+
+    #[test]
+    fn test_play_move() {
+        let meta = GameMetadata {
+            event: "Club night".to_string(),
+            date: "2024.03.15".to_string(),
+            ..GameMetadata::default()
+        };
+        let mut game = Game::new(meta.clone(), test_rates());
+        let move_ = game.board.find_all_current_moves()[0];
+        assert_eq!(game.play_move(move_), Ok(None));
+        assert_eq!(game.moves, vec![move_]);
+        assert_eq!(game.metadata.result, GameResult::Unfinished);
+
+        // rewinding the board for analysis keeps the game record
+        game.board.pop_all();
+        assert_eq!(game.moves, vec![move_]);
+
+        let illegal = Move {
+            to: (5, 5),
+            ..move_
+        };
+        assert!(game.play_move(illegal).is_err());
+        assert_eq!(game.moves, vec![move_]);
+
+        let pdn = game.to_pdn();
+        assert!(pdn.starts_with("[Event \"Club night\"]\n[Date \"2024.03.15\"]\n"));
+        assert_eq!(Game::from_pdn(&pdn, test_rates()).unwrap().metadata, meta);
+    }
+
+    #[test]
+    fn test_result_of_finished_game() {
+        let mut game = Game::new(GameMetadata::default(), test_rates());
+        game.board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (3, 3, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        let capture = game.board.find_all_current_moves()[0];
+        assert_eq!(game.play_move(capture), Ok(Some(Color::White)));
+        assert_eq!(game.metadata.result, GameResult::WhiteWins);
+        assert_eq!(GameResult::from_pdn("1-0"), Some(GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn test_self_play() {
+        let mut game = Game::new(GameMetadata::default(), test_rates());
+        for _ in 0..6 {
+            let move_ = game.board_mut().find_best_move();
+            game.play_move(move_).unwrap();
+        }
+        assert_eq!(game.moves(), game.board().moves.as_slice());
+
+        let loaded = Game::from_pdn(&game.to_pdn(), test_rates()).unwrap();
+        assert_eq!(loaded.moves(), game.moves());
+        assert_eq!(loaded.metadata(), game.metadata());
+        assert_eq!(loaded.board().board, game.board().board);
+    }
+}
+
+#[cfg(test)]
+mod search_depth_tests {
+    use super::*;
+
+    #[test]
+    fn test_depths_from_start() {
+        let mut board: Board = Board::new(3, test_rates());
+        let legal = board.find_all_current_moves();
+        for depth in [1, 3, 5, 7] {
+            let move_ = board.find_best_move_with_depth(depth);
+            assert!(legal.contains(&move_));
+            assert_eq!(board.rating, test_rates());
+        }
+    }
+
+    #[test]
+    fn test_matches_configured_depth() {
+        let mut rates = test_rates();
+        rates.max_depth = 3;
+        let mut configured: Board = Board::new(3, rates);
+        let expected = configured.find_best_move();
+
+        let mut board: Board = Board::new(3, test_rates());
+        assert_eq!(board.find_best_move_with_depth(3), expected);
+    }
+
+    #[test]
+    fn test_depth_restored_after_panic() {
+        #[derive(Debug)]
+        struct Failing;
+
+        impl Evaluator for Failing {
+            fn name(&self) -> &'static str {
+                "Failing"
+            }
+
+            fn evaluate(&self, _: &Board) -> f32 {
+                panic!("evaluation failed")
+            }
+        }
+
+        let mut board: Board = Board::new(3, test_rates());
+        board.set_evaluator(&Failing);
+        let search = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            board.find_best_move_with_depth(4)
+        }));
+        assert!(search.is_err());
+        assert_eq!(board.rating.max_depth, test_rates().max_depth);
+    }
+}
+
+#[cfg(test)]
+mod piece_at_tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_at() {
+        let board: Board = Board::new(3, test_rates());
+        let white_pawn = PlayersPiece::new(Color::White, Piece::Pawn);
+        assert_eq!(board.piece_at(0, 0), Some(white_pawn));
+        assert_eq!(board.piece_at(0, 1), None);
+        assert_eq!(board.piece_at(3, 3), None);
+        assert_eq!(board.piece_at(8, 0), None);
+        assert_eq!(board.piece_at(0, u8::MAX), None);
+    }
+}
+
+#[cfg(test)]
+mod evaluator_tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    static EVALUATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Pieces of the current player minus the opponent's, queens count as pawns
+    #[derive(Debug)]
+    struct MaterialOnlyEvaluator;
+
+    impl Evaluator for MaterialOnlyEvaluator {
+        fn name(&self) -> &'static str {
+            "Material only"
+        }
+
+        fn evaluate(&self, board: &Board) -> f32 {
+            EVALUATIONS.fetch_add(1, Ordering::Relaxed);
+            let player = board.current_player();
+            board.total_piece_count(player) as f32 - board.total_piece_count(player.other()) as f32
+        }
+    }
+
+    #[test]
+    fn test_default_evaluator() {
+        let board: Board = Board::new(3, test_rates());
+        assert_eq!(board.evaluator, &DefaultEvaluator as &dyn Evaluator);
+        assert_eq!(
+            board.rate_current_board(),
+            board.evaluation_breakdown().total
+        );
+    }
+
+    #[test]
+    fn test_custom_evaluator_in_search() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, Piece::Queen)),
+                (5, 5, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (6, 6, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        board.set_evaluator(&MaterialOnlyEvaluator);
+        assert_eq!(board.rate_current_board(), 1.0 - 2.0);
+        let mut default = board.clone();
+        default.set_evaluator(&DefaultEvaluator);
+        assert_ne!(board, default);
+
+        EVALUATIONS.store(0, Ordering::Relaxed);
+        let move_ = board.find_best_move();
+        assert!(board.find_all_current_moves().contains(&move_));
+        assert!(EVALUATIONS.load(Ordering::Relaxed) > 0);
+    }
+}
+
+#[cfg(test)]
+mod opening_book_tests {
+    use super::*;
+
+    fn small_book() -> OpeningBook {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let start: Board = Board::new(3, test_rates());
+        OpeningBook::build(&start, 4, 1, &mut rng)
+    }
+
+    #[test]
+    fn test_book_move() {
+        let book = small_book();
+        assert!(book.len() >= OpeningBook::BUILD_MOVES);
+
+        let mut board: Board = Board::new(3, test_rates());
+        let move_ = board.book_move(&book).unwrap();
+        assert_eq!(move_, board.find_best_move_with_depth(1));
+        assert_eq!(board.find_best_move_with_book(&book), move_);
+
+        let mut unknown = board.clone();
+        unknown
+            .set_piece(3, 3, Some(PlayersPiece::new(Color::White, Piece::Queen)))
+            .unwrap();
+        assert_eq!(unknown.book_move(&book), None);
+        let searched = unknown.find_best_move();
+        assert_eq!(unknown.find_best_move_with_book(&book), searched);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let book = small_book();
+        let path =
+            std::env::temp_dir().join(format!("checkengine-book-{}.bin", std::process::id()));
+        book.save(&path).unwrap();
+        let loaded = OpeningBook::load(&path);
+
+        fs::write(&path, &fs::read(&path).unwrap()[..20]).unwrap();
+        let truncated = OpeningBook::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), book);
+        assert_eq!(truncated.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[cfg(test)]
+mod move_squares_tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_move() {
+        let board: Board = Board::new(3, test_rates());
+        let move_ = board.legal_moves_from(2, 0)[0];
+        assert_eq!(move_.all_squares().collect::<Vec<_>>(), [(2, 0), (3, 1)]);
+        assert_eq!(move_.affected_squares().count(), 2);
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (3, 3, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        let capture = board.find_all_current_moves()[0];
+        assert_eq!(
+            capture.all_squares().collect::<Vec<_>>(),
+            [(2, 2), (4, 4), (3, 3)]
+        );
+        assert_eq!(capture.affected_squares().count(), 3);
+    }
+
+    #[test]
+    fn test_null_move() {
+        let pass = Move::null(Color::White);
+        assert_eq!(pass.all_squares().collect::<Vec<_>>(), [(0, 0), (0, 0)]);
+        assert_eq!(pass.affected_squares().count(), 0);
+    }
+}
+
+#[cfg(test)]
+mod threats_tests {
+    use super::*;
+
+    // White on C3 and Black on D4 threaten each other, Black on B4 has a piece behind it
+    fn board() -> Board {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (3, 3, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (3, 1, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (4, 0, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_threats() {
+        let board = board();
+        assert_eq!(board.threats(Color::White), [(3, 3)]);
+        assert_eq!(board.threats(Color::Black), [(2, 2)]);
+    }
+
+    #[test]
+    fn test_is_threatened() {
+        let board = board();
+        assert!(board.is_threatened(2, 2));
+        assert!(board.is_threatened(3, 3));
+        assert!(!board.is_threatened(3, 1));
+        assert!(!board.is_threatened(4, 0));
+        assert!(!board.is_threatened(0, 0));
+    }
+}
+
+#[cfg(all(test, feature = "svg"))]
+mod svg_tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let board: Board = Board::new(3, test_rates());
+        let svg = board.svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(svg.matches('⛀').count(), 12);
+        assert_eq!(svg.matches('⛂').count(), 12);
+        assert!(!svg.contains("<line"));
+    }
+
+    #[test]
+    fn test_move_arrows() {
+        let mut board: Board = Board::new(3, test_rates());
+        board.set_show_moves_for(Some((2, 2)));
+        assert_eq!(board.svg().matches("<line").count(), 2);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let board: Board = Board::new(3, test_rates());
+        let path = std::env::temp_dir().join(format!("checkengine-{}.svg", std::process::id()));
+        board.to_svg(&path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, board.svg());
+    }
+}
+
+#[cfg(test)]
+mod with_move_tests {
+    use super::*;
+
+    // minimax over material, as a custom search would write it
+    fn minimax(board: &mut Board, depth: usize) -> f32 {
+        let player = board.current_player();
+        if depth == 0 || board.winner().is_some() {
+            return board.total_piece_count(player) as f32
+                - board.total_piece_count(player.other()) as f32;
+        }
+
+        board
+            .find_all_current_moves()
+            .into_iter()
+            .map(|m| {
+                board.with_move_unchecked(m, |b| {
+                    if b.current_player() == player {
+                        minimax(b, depth)
+                    } else {
+                        -minimax(b, depth - 1)
+                    }
+                })
+            })
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    #[test]
+    fn test_board_is_restored() {
+        let mut board: Board = Board::new(3, test_rates());
+        let before = board.clone();
+        assert_eq!(minimax(&mut board, 3), 0.0);
+        assert_eq!(board, before);
+
+        let move_ = board.find_all_current_moves()[0];
+        let after = board.with_move(move_, |b| b.clone());
+        assert_eq!(after.last_move(), Some(move_));
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    #[should_panic(expected = "Legal move")]
+    fn test_with_move_checks_the_move() {
+        let mut board: Board = Board::new(3, test_rates());
+        let move_ = board.find_all_current_moves()[0];
+        board.with_move(
+            Move {
+                to: (5, 5),
+                ..move_
+            },
+            |_| (),
+        );
+    }
+}
+
+#[cfg(test)]
+mod undo_tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_on_empty_board() {
+        let mut board: Board = Board::new(3, test_rates());
+        assert_eq!(board.undo(), None);
+        assert_eq!(board, Board::new(3, test_rates()));
+    }
+
+    #[test]
+    fn test_undo_returns_last_move() {
+        let mut board: Board = Board::new(3, test_rates());
+        let before = board.clone();
+        let move_ = board.find_all_current_moves()[0];
+        board.push(move_).unwrap();
+
+        assert_eq!(board.undo(), Some(move_));
+        assert_eq!(board, before);
+        assert_eq!(board.undo(), None);
+    }
+}
+
+#[cfg(test)]
+mod tension_tests {
+    use super::*;
+
+    // White on C3 can take D2 or D4, Black on D4 can take C3,
+    // B4 keeps D2 from taking it too
+    fn board(tension: f32) -> Board {
+        let mut rates = test_rates();
+        rates.position.tension = tension;
+        rates.endgame.tension = tension;
+        let mut board: Board = Board::empty_with_rates(rates);
+        board
+            .set_position(&[
+                (2, 2, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (3, 1, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (3, 3, PlayersPiece::new(Color::Black, Piece::Pawn)),
+                (1, 3, PlayersPiece::new(Color::White, Piece::Pawn)),
+                (0, 6, PlayersPiece::new(Color::White, Piece::Pawn)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_move_counts() {
+        let board = board(0.0);
+        assert_eq!(board.capture_move_count(Color::White), 2);
+        assert_eq!(board.capture_move_count(Color::Black), 1);
+        // the pawns on B4 and A7 can move without capturing, C3 is blocked
+        assert_eq!(board.quiet_move_count(Color::White), 3);
+        assert_eq!(board.quiet_move_count(Color::Black), 2);
+
+        let start: Board = Board::new(3, test_rates());
+        assert_eq!(start.capture_move_count(Color::White), 0);
+        assert_eq!(start.quiet_move_count(Color::White), 7);
+    }
+
+    #[test]
+    fn test_tension_in_rating() {
+        let without = board(0.0).rate_current_board();
+        assert_eq!(board(1.0).rate_current_board(), without + (2.0 - 1.0));
+    }
+}
+
+#[cfg(test)]
+mod dtw_tests {
+    use super::*;
+
+    fn queen_against_pawn(pawn: (u8, u8)) -> Board {
+        let mut board: Board = Board::empty_with_rates(test_rates());
+        board
+            .set_position(&[
+                (0, 0, PlayersPiece::new(Color::White, Piece::Queen)),
+                (pawn.0, pawn.1, PlayersPiece::new(Color::Black, Piece::Pawn)),
+            ])
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn test_immediate_capture() {
+        let mut board = queen_against_pawn((6, 6));
+        assert_eq!(board.compute_dtw(Color::White, 5), Some(1));
+    }
+
+    #[test]
+    fn test_queen_waits_on_the_diagonal() {
+        // the pawn on H8 has to step onto the long diagonal the queen is on
+        let mut board = queen_against_pawn((7, 7));
+        let before = board.clone();
+        assert_eq!(board.compute_dtw(Color::White, 5), Some(3));
+        assert_eq!(board.compute_dtw(Color::White, 2), None);
+        assert_eq!(board.compute_dtw(Color::Black, 5), None);
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_longer_win() {
+        let mut board = queen_against_pawn((7, 3));
+        assert_eq!(board.compute_dtw(Color::White, 6), Some(5));
+    }
+
+    #[test]
+    fn test_game_already_won() {
+        let mut board = queen_against_pawn((6, 6));
+        board.set_piece(6, 6, None).unwrap();
+        assert_eq!(board.compute_dtw(Color::White, 0), Some(0));
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_scripted_analysis() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .arg("analyze")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"move C3 D4\neval\nperft 2\nundo\nundo\nquit\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Evaluation: "));
+    assert!(stdout.contains("Perft 2: "));
+    assert!(stdout.contains("Nothing to undo"));
+}
+
+#[test]
+fn test_analysis_from_pdn() {
+    let path =
+        std::env::temp_dir().join(format!("checkengine-cli-game-{}.pdn", std::process::id()));
+    std::fs::write(&path, "[Event \"Club night\"]\n\n1. 9-13 22-18 *\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .args(["analyze", "--pdn"])
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"undo\nundo\nundo\nquit\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(output.status.success());
+
+    // both moves of the game can be taken back
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("Nothing to undo").count(), 1);
+
+    let missing = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .args(["analyze", "--pdn", "no-such-game.pdn"])
+        .output()
+        .unwrap();
+    assert_eq!(missing.status.code(), Some(1));
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn test_analysis_svg() {
+    let path =
+        std::env::temp_dir().join(format!("checkengine-cli-board-{}.svg", std::process::id()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .args(["analyze", "--svg"])
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"move C3 D4\nquit\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let svg = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(output.status.success());
+    let svg = svg.unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.ends_with("</svg>\n"));
+}
@@ -0,0 +1,26 @@
+use std::process::Command;
+
+#[test]
+fn test_build_book() {
+    let path =
+        std::env::temp_dir().join(format!("checkengine-cli-book-{}.bin", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .args(["build_book", "--depth", "1", "--games", "2", "--out"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    let book = std::fs::read(&path);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Wrote "));
+    assert!(book.unwrap().starts_with(b"CKOB"));
+
+    let usage = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .args(["build_book", "--depth", "zero"])
+        .output()
+        .unwrap();
+    assert_eq!(usage.status.code(), Some(2));
+}
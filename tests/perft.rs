@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Node counts from the standard starting position, a capture counts one half-move per jump.
+// These are the published perft numbers of English draughts (checkers), e.g. Aart Bik's
+// checkers perft: up to 6 plies no man can promote or jump twice, so the rules agree with
+// these. From 7 plies on the counts part, since English draughts counts a multiple jump as
+// one move
+const EXPECTED: [(usize, u64); 6] = [(1, 7), (2, 49), (3, 302), (4, 1469), (5, 7361), (6, 36768)];
+
+fn run_analysis(script: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_checkengine"))
+        .arg("analyze")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_perft_starting_position() {
+    let script: String = EXPECTED
+        .iter()
+        .map(|(depth, _)| format!("perft {}\n", depth))
+        .collect();
+    let stdout = run_analysis(&script);
+
+    for (depth, nodes) in EXPECTED {
+        assert!(
+            stdout.contains(&format!("Perft {}: {}\n", depth, nodes)),
+            "Wrong perft {}",
+            depth
+        );
+    }
+}
+
+#[test]
+fn test_divide_sums_to_perft() {
+    let stdout = run_analysis("divide 4\n");
+    let total: u64 = stdout
+        .lines()
+        .filter(|line| line.contains(" -> "))
+        .map(|line| line.rsplit(": ").next().unwrap().parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(total, EXPECTED[3].1);
+}